@@ -1,13 +1,30 @@
+use crate::logic::search::SearchQuery;
+use crate::logic::undo::UndoHistory;
 use crate::logic::validation;
 use crate::models::analytics::Analytics;
-use crate::models::run::Run;
+use crate::models::athlete::{Athlete, DEFAULT_ATHLETE_ID};
+use crate::models::gear::{Gear, DEFAULT_RETIRE_MILES};
+use crate::models::health::QuarantinedRun;
+use crate::models::history::RunVersion;
+use crate::models::route::Route;
+use crate::models::run::{self, Run, RunType};
+use crate::models::split::Split;
+use crate::models::units::DistanceUnit;
+use anyhow::{anyhow, Result};
 use chrono::Local;
+use std::path::PathBuf;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Screen {
     QuickEntry,
     RunList,
+    RunDetail,
+    RunHistory,
     Analytics,
+    Trash,
+    Gear,
+    Routes,
+    DataHealth,
     Help,
 }
 
@@ -15,19 +32,75 @@ pub enum Screen {
 pub enum InputField {
     Date,
     Time,
+    Zone,
+    Route,
     Distance,
+    Duration,
+    AvgHeartRate,
+    MaxHeartRate,
+    Effort,
+    Type,
+    Shoe,
+    Tags,
     Note,
 }
 
+/// A run that can't be saved because another run already starts at the same
+/// date and time, waiting for the user to choose what to do.
+pub struct RunConflict {
+    /// The run being saved; it has an id when an edit caused the conflict.
+    pub run: Run,
+    /// The run already at that date and time.
+    pub existing: Run,
+}
+
+impl RunConflict {
+    /// The existing run with the new run's distance added. Durations are
+    /// added too when both runs have one, so the pace stays right; when only
+    /// one of them has a duration, the merged run keeps that one.
+    pub fn merged(&self) -> Run {
+        let mut merged = self.existing.clone();
+        merged.distance_miles += self.run.distance_miles;
+        merged.duration_seconds = match (self.existing.duration_seconds, self.run.duration_seconds)
+        {
+            (Some(existing), Some(new)) => Some(existing + new),
+            (existing, new) => existing.or(new),
+        };
+        merged
+    }
+}
+
 pub struct QuickEntryState {
     pub date: String,
     pub time: String,
+    /// UTC offset of the run, e.g. `UTC+09:00`.
+    pub zone: String,
+    pub route_id: Option<i64>,
     pub distance: String,
+    pub duration: String,
+    pub avg_heart_rate: String,
+    pub max_heart_rate: String,
+    pub effort: String,
+    pub run_type: Option<RunType>,
+    pub gear_id: Option<i64>,
+    pub tags: String,
     pub note: String,
+    /// Every tag already in use, for autocompleting the Tags field.
+    pub known_tags: Vec<String>,
+    /// All gear, for choosing the shoe and showing its name.
+    pub gear_options: Vec<Gear>,
+    /// Shoe selected for new runs.
+    pub default_gear_id: Option<i64>,
+    /// All routes, for choosing one and pre-filling its distance.
+    pub route_options: Vec<Route>,
     pub focused_field: InputField,
     pub error_message: Option<String>,
     pub success_message: Option<String>,
     pub editing_run_id: Option<i64>,
+    /// Athlete new runs are logged for; the active athlete.
+    pub athlete_id: i64,
+    /// Set while the duplicate date/time dialog is open.
+    pub conflict: Option<RunConflict>,
 }
 
 impl QuickEntryState {
@@ -36,12 +109,27 @@ impl QuickEntryState {
         Self {
             date: validation::format_date(&now.date()),
             time: validation::format_time(&now.time()),
+            zone: local_zone(),
+            route_id: None,
             distance: String::new(),
+            duration: String::new(),
+            avg_heart_rate: String::new(),
+            max_heart_rate: String::new(),
+            effort: String::new(),
+            run_type: None,
+            gear_id: None,
+            tags: String::new(),
             note: String::new(),
+            known_tags: Vec::new(),
+            gear_options: Vec::new(),
+            default_gear_id: None,
+            route_options: Vec::new(),
             focused_field: InputField::Distance,
             error_message: None,
             success_message: None,
             editing_run_id: None,
+            athlete_id: DEFAULT_ATHLETE_ID,
+            conflict: None,
         }
     }
 
@@ -49,18 +137,40 @@ impl QuickEntryState {
         let now = Local::now().naive_local();
         self.date = validation::format_date(&now.date());
         self.time = validation::format_time(&now.time());
+        self.zone = local_zone();
+        self.route_id = None;
         self.distance.clear();
+        self.duration.clear();
+        self.avg_heart_rate.clear();
+        self.max_heart_rate.clear();
+        self.effort.clear();
+        self.run_type = None;
+        self.gear_id = self.default_gear_id;
+        self.tags.clear();
         self.note.clear();
         self.focused_field = InputField::Distance;
         self.error_message = None;
         self.success_message = None;
         self.editing_run_id = None;
+        self.conflict = None;
     }
 
-    pub fn load_run(&mut self, run: &Run) {
+    pub fn load_run(&mut self, run: &Run, units: DistanceUnit) {
         self.date = validation::format_date(&run.date);
         self.time = validation::format_time(&run.time_started);
-        self.distance = run.distance_miles.to_string();
+        self.zone = validation::format_utc_offset(&run.offset());
+        self.route_id = run.route_id;
+        self.distance = validation::format_distance_input(run.distance_miles, units);
+        self.duration = run
+            .duration_seconds
+            .map(validation::format_duration)
+            .unwrap_or_default();
+        self.avg_heart_rate = optional_number(run.avg_heart_rate);
+        self.max_heart_rate = optional_number(run.max_heart_rate);
+        self.effort = optional_number(run.effort);
+        self.run_type = run.run_type;
+        self.gear_id = run.gear_id;
+        self.tags = validation::format_tags(&run.tags);
         self.note = run.note.clone().unwrap_or_default();
         self.focused_field = InputField::Distance;
         self.error_message = None;
@@ -68,6 +178,78 @@ impl QuickEntryState {
         self.editing_run_id = run.id;
     }
 
+    /// Parses and validates the form fields into a new, unsaved run.
+    pub fn build_run(&self, units: DistanceUnit) -> Result<Run> {
+        let date = validation::parse_date(&self.date)?;
+        let time = validation::parse_time(&self.time)?;
+        let zone = validation::parse_utc_offset(&self.zone)?;
+        let distance = validation::parse_distance(&self.distance, units)?;
+        let duration = validation::parse_duration(&self.duration)?;
+        let avg_heart_rate = validation::parse_heart_rate(&self.avg_heart_rate)?;
+        let max_heart_rate = validation::parse_heart_rate(&self.max_heart_rate)?;
+        if let (Some(avg), Some(max)) = (avg_heart_rate, max_heart_rate) {
+            if max < avg {
+                return Err(anyhow!(
+                    "Max heart rate can't be lower than average heart rate"
+                ));
+            }
+        }
+        let effort = validation::parse_effort(&self.effort)?;
+        let tags = validation::parse_tags(&self.tags)?;
+        let note = if self.note.is_empty() {
+            None
+        } else {
+            Some(self.note.clone())
+        };
+
+        let mut run = Run::new(date, time, distance, duration, note)?;
+        run.avg_heart_rate = avg_heart_rate;
+        run.max_heart_rate = max_heart_rate;
+        run.effort = effort;
+        run.run_type = self.run_type;
+        run.gear_id = self.gear_id;
+        run.route_id = self.route_id;
+        run.tags = tags;
+        run.utc_offset = Some(zone.unwrap_or_else(|| run::local_offset(date, time)));
+        run.athlete_id = self.athlete_id;
+        Ok(run)
+    }
+
+    /// The rest of a known tag that starts with the tag being typed, if the
+    /// Tags field is focused and there is exactly one way to complete it.
+    pub fn tag_suggestion(&self) -> Option<&str> {
+        if self.focused_field != InputField::Tags {
+            return None;
+        }
+
+        let partial = self.tags.rsplit(',').next().unwrap_or("").trim_start();
+        if partial.is_empty() {
+            return None;
+        }
+
+        let mut matches = self.known_tags.iter().filter(|tag| {
+            tag.len() > partial.len()
+                && tag.is_char_boundary(partial.len())
+                && tag[..partial.len()].eq_ignore_ascii_case(partial)
+        });
+        let tag = matches.next()?;
+        if matches.next().is_some() {
+            return None;
+        }
+        Some(&tag[partial.len()..])
+    }
+
+    /// Completes the tag being typed with the current suggestion.
+    pub fn accept_tag_suggestion(&mut self) -> bool {
+        match self.tag_suggestion().map(str::to_string) {
+            Some(rest) => {
+                self.tags.push_str(&rest);
+                true
+            }
+            None => false,
+        }
+    }
+
     pub fn is_editing(&self) -> bool {
         self.editing_run_id.is_some()
     }
@@ -75,8 +257,17 @@ impl QuickEntryState {
     pub fn next_field(&mut self) {
         self.focused_field = match self.focused_field {
             InputField::Date => InputField::Time,
-            InputField::Time => InputField::Distance,
-            InputField::Distance => InputField::Note,
+            InputField::Time => InputField::Zone,
+            InputField::Zone => InputField::Route,
+            InputField::Route => InputField::Distance,
+            InputField::Distance => InputField::Duration,
+            InputField::Duration => InputField::AvgHeartRate,
+            InputField::AvgHeartRate => InputField::MaxHeartRate,
+            InputField::MaxHeartRate => InputField::Effort,
+            InputField::Effort => InputField::Type,
+            InputField::Type => InputField::Shoe,
+            InputField::Shoe => InputField::Tags,
+            InputField::Tags => InputField::Note,
             InputField::Note => InputField::Date,
         };
     }
@@ -85,37 +276,541 @@ impl QuickEntryState {
         self.focused_field = match self.focused_field {
             InputField::Date => InputField::Note,
             InputField::Time => InputField::Date,
-            InputField::Distance => InputField::Time,
-            InputField::Note => InputField::Distance,
+            InputField::Zone => InputField::Time,
+            InputField::Route => InputField::Zone,
+            InputField::Distance => InputField::Route,
+            InputField::Duration => InputField::Distance,
+            InputField::AvgHeartRate => InputField::Duration,
+            InputField::MaxHeartRate => InputField::AvgHeartRate,
+            InputField::Effort => InputField::MaxHeartRate,
+            InputField::Type => InputField::Effort,
+            InputField::Shoe => InputField::Type,
+            InputField::Tags => InputField::Shoe,
+            InputField::Note => InputField::Tags,
         };
     }
 
-    pub fn current_input_mut(&mut self) -> &mut String {
+    /// The text of the focused field, or `None` for the Route, Type and Shoe
+    /// selectors, which are cycled through instead of typed into.
+    pub fn current_input_mut(&mut self) -> Option<&mut String> {
         match self.focused_field {
-            InputField::Date => &mut self.date,
-            InputField::Time => &mut self.time,
-            InputField::Distance => &mut self.distance,
-            InputField::Note => &mut self.note,
+            InputField::Date => Some(&mut self.date),
+            InputField::Time => Some(&mut self.time),
+            InputField::Zone => Some(&mut self.zone),
+            InputField::Distance => Some(&mut self.distance),
+            InputField::Duration => Some(&mut self.duration),
+            InputField::AvgHeartRate => Some(&mut self.avg_heart_rate),
+            InputField::MaxHeartRate => Some(&mut self.max_heart_rate),
+            InputField::Effort => Some(&mut self.effort),
+            InputField::Route | InputField::Type | InputField::Shoe => None,
+            InputField::Tags => Some(&mut self.tags),
+            InputField::Note => Some(&mut self.note),
+        }
+    }
+
+    pub fn cycle_run_type(&mut self, forward: bool) {
+        self.run_type = RunType::cycle(self.run_type, forward);
+    }
+
+    /// Steps through the shoes still in use, with no shoe between the last
+    /// and the first. A retired shoe already on the run is kept as a choice.
+    pub fn cycle_gear(&mut self, forward: bool) {
+        let mut choices: Vec<Option<i64>> = vec![None];
+        choices.extend(
+            self.gear_options
+                .iter()
+                .filter(|gear| !gear.retired || gear.id == self.gear_id)
+                .map(|gear| gear.id),
+        );
+
+        let position = choices
+            .iter()
+            .position(|&id| id == self.gear_id)
+            .unwrap_or(0);
+        let next = if forward {
+            (position + 1) % choices.len()
+        } else {
+            (position + choices.len() - 1) % choices.len()
+        };
+        self.gear_id = choices[next];
+    }
+
+    /// Steps through the routes, with no route between the last and the
+    /// first. Choosing a route fills in its distance.
+    pub fn cycle_route(&mut self, forward: bool, units: DistanceUnit) {
+        let mut choices: Vec<Option<i64>> = vec![None];
+        choices.extend(self.route_options.iter().map(|route| route.id));
+
+        let position = choices
+            .iter()
+            .position(|&id| id == self.route_id)
+            .unwrap_or(0);
+        let next = if forward {
+            (position + 1) % choices.len()
+        } else {
+            (position + choices.len() - 1) % choices.len()
+        };
+        self.route_id = choices[next];
+
+        if let Some(route) = self.route() {
+            self.distance = validation::format_distance_input(route.distance_miles, units);
         }
     }
+
+    pub fn route(&self) -> Option<&Route> {
+        let id = self.route_id?;
+        self.route_options.iter().find(|route| route.id == Some(id))
+    }
+
+    pub fn gear_name(&self, id: Option<i64>) -> Option<&str> {
+        gear_name(&self.gear_options, id)
+    }
 }
 
+/// How many runs the Run List fetches at a time.
+pub const RUN_PAGE_SIZE: usize = 50;
+
+/// Fetch the next page once the selection is this close to the last loaded
+/// run, so scrolling never waits on an empty screen.
+const RUN_PAGE_PREFETCH: usize = 10;
+
 pub struct RunListState {
+    /// The pages loaded so far, newest first.
     pub runs: Vec<Run>,
+    /// Every run matching the current search, including pages not loaded yet.
+    pub total_runs: usize,
     pub selected_index: usize,
     pub scroll_offset: usize,
+    /// Text being typed at the `/` prompt, while the prompt is open.
+    pub search_input: Option<String>,
+    /// The applied search; the list only shows matching runs.
+    pub search: SearchQuery,
 }
 
 impl RunListState {
+    pub fn new() -> Self {
+        Self {
+            runs: Vec::new(),
+            total_runs: 0,
+            selected_index: 0,
+            scroll_offset: 0,
+            search_input: None,
+            search: SearchQuery::default(),
+        }
+    }
+
+    pub fn is_searching(&self) -> bool {
+        self.search_input.is_some()
+    }
+
+    pub fn has_more(&self) -> bool {
+        self.runs.len() < self.total_runs
+    }
+
+    /// True when the selection is near the end of the loaded runs and more
+    /// are waiting to be fetched.
+    pub fn needs_more(&self) -> bool {
+        self.has_more() && self.selected_index + RUN_PAGE_PREFETCH >= self.runs.len()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LapField {
+    Distance,
+    Duration,
+}
+
+pub struct LapInput {
+    pub distance: String,
+    pub duration: String,
+}
+
+impl LapInput {
+    fn empty() -> Self {
+        Self {
+            distance: String::new(),
+            duration: String::new(),
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.distance.trim().is_empty() && self.duration.trim().is_empty()
+    }
+}
+
+/// Editable rows for a run's laps. Tabbing past the last lap adds another.
+pub struct LapEditor {
+    pub laps: Vec<LapInput>,
+    pub selected_lap: usize,
+    pub focused_field: LapField,
+    pub error_message: Option<String>,
+}
+
+impl LapEditor {
+    pub fn new(splits: &[Split], units: DistanceUnit) -> Self {
+        let mut laps: Vec<LapInput> = splits
+            .iter()
+            .map(|split| LapInput {
+                distance: validation::format_distance_input(split.distance_miles, units),
+                duration: validation::format_duration(split.duration_seconds),
+            })
+            .collect();
+        if laps.is_empty() {
+            laps.push(LapInput::empty());
+        }
+
+        Self {
+            laps,
+            selected_lap: 0,
+            focused_field: LapField::Distance,
+            error_message: None,
+        }
+    }
+
+    pub fn next_field(&mut self) {
+        match self.focused_field {
+            LapField::Distance => self.focused_field = LapField::Duration,
+            LapField::Duration => {
+                if self.selected_lap + 1 == self.laps.len() {
+                    self.laps.push(LapInput::empty());
+                }
+                self.selected_lap += 1;
+                self.focused_field = LapField::Distance;
+            }
+        }
+    }
+
+    pub fn prev_field(&mut self) {
+        match self.focused_field {
+            LapField::Duration => self.focused_field = LapField::Distance,
+            LapField::Distance if self.selected_lap > 0 => {
+                self.selected_lap -= 1;
+                self.focused_field = LapField::Duration;
+            }
+            LapField::Distance => {}
+        }
+    }
+
+    pub fn select_previous(&mut self) {
+        self.selected_lap = self.selected_lap.saturating_sub(1);
+    }
+
+    pub fn select_next(&mut self) {
+        if self.selected_lap + 1 < self.laps.len() {
+            self.selected_lap += 1;
+        }
+    }
+
+    /// Removes the selected lap, leaving one empty lap if it was the last.
+    pub fn remove_selected(&mut self) {
+        self.laps.remove(self.selected_lap);
+        if self.laps.is_empty() {
+            self.laps.push(LapInput::empty());
+        }
+        self.selected_lap = self.selected_lap.min(self.laps.len() - 1);
+    }
+
+    pub fn current_input_mut(&mut self) -> &mut String {
+        let lap = &mut self.laps[self.selected_lap];
+        match self.focused_field {
+            LapField::Distance => &mut lap.distance,
+            LapField::Duration => &mut lap.duration,
+        }
+    }
+
+    /// Parses the rows into splits, skipping blank rows and numbering the
+    /// rest from 1.
+    pub fn build_splits(&self, units: DistanceUnit) -> Result<Vec<Split>> {
+        self.laps
+            .iter()
+            .filter(|lap| !lap.is_empty())
+            .enumerate()
+            .map(|(i, lap)| {
+                let lap_number = i as u32 + 1;
+                let distance = validation::parse_distance(&lap.distance, units)
+                    .map_err(|e| anyhow!("Lap {}: {}", lap_number, e))?;
+                let duration = validation::parse_duration(&lap.duration)
+                    .map_err(|e| anyhow!("Lap {}: {}", lap_number, e))?
+                    .filter(|&seconds| seconds > 0)
+                    .ok_or_else(|| anyhow!("Lap {}: Duration is required", lap_number))?;
+                Ok(Split {
+                    lap_number,
+                    distance_miles: distance,
+                    duration_seconds: duration,
+                })
+            })
+            .collect()
+    }
+}
+
+/// The run opened from the Run List, with its laps.
+pub struct RunDetailState {
+    pub run: Option<Run>,
+    pub splits: Vec<Split>,
+    pub gear_name: Option<String>,
+    pub route_name: Option<String>,
+    pub editor: Option<LapEditor>,
+    pub message: Option<String>,
+}
+
+impl RunDetailState {
+    pub fn new() -> Self {
+        Self {
+            run: None,
+            splits: Vec::new(),
+            gear_name: None,
+            route_name: None,
+            editor: None,
+            message: None,
+        }
+    }
+}
+
+pub struct RunHistoryState {
+    /// The run as it is now.
+    pub run: Option<Run>,
+    /// Its recorded changes, newest first.
+    pub versions: Vec<RunVersion>,
+    pub selected_index: usize,
+    pub message: Option<String>,
+}
+
+impl RunHistoryState {
+    pub fn new() -> Self {
+        Self {
+            run: None,
+            versions: Vec::new(),
+            selected_index: 0,
+            message: None,
+        }
+    }
+
+    pub fn selected(&self) -> Option<&RunVersion> {
+        self.versions.get(self.selected_index)
+    }
+}
+
+pub struct TrashState {
+    pub runs: Vec<Run>,
+    pub selected_index: usize,
+    pub scroll_offset: usize,
+    pub confirm_empty: bool,
+    pub message: Option<String>,
+}
+
+impl TrashState {
     pub fn new() -> Self {
         Self {
             runs: Vec::new(),
             selected_index: 0,
             scroll_offset: 0,
+            confirm_empty: false,
+            message: None,
         }
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GearField {
+    Name,
+    RetireAt,
+}
+
+/// The add/edit form on the Gear screen.
+pub struct GearForm {
+    pub editing: Option<Gear>,
+    pub name: String,
+    pub retire_at: String,
+    pub focused_field: GearField,
+    pub error_message: Option<String>,
+}
+
+impl GearForm {
+    pub fn new(units: DistanceUnit) -> Self {
+        Self {
+            editing: None,
+            name: String::new(),
+            retire_at: validation::format_distance_input(DEFAULT_RETIRE_MILES, units),
+            focused_field: GearField::Name,
+            error_message: None,
+        }
+    }
+
+    pub fn edit(gear: &Gear, units: DistanceUnit) -> Self {
+        Self {
+            editing: Some(gear.clone()),
+            name: gear.name.clone(),
+            retire_at: validation::format_distance_input(gear.retire_at_miles, units),
+            focused_field: GearField::Name,
+            error_message: None,
+        }
+    }
+
+    /// Parses the form into gear, keeping the id and retired flag when editing.
+    pub fn build_gear(&self, units: DistanceUnit) -> Result<Gear> {
+        let retire_at = validation::parse_distance(&self.retire_at, units)?;
+        let mut gear = Gear::new(&self.name, retire_at)?;
+        if let Some(ref existing) = self.editing {
+            gear.id = existing.id;
+            gear.retired = existing.retired;
+        }
+        Ok(gear)
+    }
+
+    pub fn toggle_field(&mut self) {
+        self.focused_field = match self.focused_field {
+            GearField::Name => GearField::RetireAt,
+            GearField::RetireAt => GearField::Name,
+        };
+    }
+
+    pub fn current_input_mut(&mut self) -> &mut String {
+        match self.focused_field {
+            GearField::Name => &mut self.name,
+            GearField::RetireAt => &mut self.retire_at,
+        }
+    }
+}
+
+pub struct GearState {
+    pub gear: Vec<Gear>,
+    pub selected_index: usize,
+    pub default_gear_id: Option<i64>,
+    pub form: Option<GearForm>,
+    pub confirm_delete: bool,
+    pub message: Option<String>,
+}
+
+impl GearState {
+    pub fn new() -> Self {
+        Self {
+            gear: Vec::new(),
+            selected_index: 0,
+            default_gear_id: None,
+            form: None,
+            confirm_delete: false,
+            message: None,
+        }
+    }
+
+    pub fn selected(&self) -> Option<&Gear> {
+        self.gear.get(self.selected_index)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RouteField {
+    Name,
+    Distance,
+    Description,
+}
+
+/// The add/edit form on the Routes screen.
+pub struct RouteForm {
+    pub editing_id: Option<i64>,
+    pub name: String,
+    pub distance: String,
+    pub description: String,
+    pub focused_field: RouteField,
+    pub error_message: Option<String>,
+}
+
+impl RouteForm {
+    pub fn new() -> Self {
+        Self {
+            editing_id: None,
+            name: String::new(),
+            distance: String::new(),
+            description: String::new(),
+            focused_field: RouteField::Name,
+            error_message: None,
+        }
+    }
+
+    pub fn edit(route: &Route, units: DistanceUnit) -> Self {
+        Self {
+            editing_id: route.id,
+            name: route.name.clone(),
+            distance: validation::format_distance_input(route.distance_miles, units),
+            description: route.description.clone().unwrap_or_default(),
+            focused_field: RouteField::Name,
+            error_message: None,
+        }
+    }
+
+    pub fn build_route(&self, units: DistanceUnit) -> Result<Route> {
+        let distance = validation::parse_distance(&self.distance, units)?;
+        let description = Some(self.description.trim().to_string()).filter(|d| !d.is_empty());
+        let mut route = Route::new(&self.name, distance, description)?;
+        route.id = self.editing_id;
+        Ok(route)
+    }
+
+    pub fn next_field(&mut self) {
+        self.focused_field = match self.focused_field {
+            RouteField::Name => RouteField::Distance,
+            RouteField::Distance => RouteField::Description,
+            RouteField::Description => RouteField::Name,
+        };
+    }
+
+    pub fn prev_field(&mut self) {
+        self.focused_field = match self.focused_field {
+            RouteField::Name => RouteField::Description,
+            RouteField::Distance => RouteField::Name,
+            RouteField::Description => RouteField::Distance,
+        };
+    }
+
+    pub fn current_input_mut(&mut self) -> &mut String {
+        match self.focused_field {
+            RouteField::Name => &mut self.name,
+            RouteField::Distance => &mut self.distance,
+            RouteField::Description => &mut self.description,
+        }
+    }
+}
+
+pub struct RoutesState {
+    pub routes: Vec<Route>,
+    pub selected_index: usize,
+    pub form: Option<RouteForm>,
+    pub confirm_delete: bool,
+    pub message: Option<String>,
+}
+
+impl RoutesState {
+    pub fn new() -> Self {
+        Self {
+            routes: Vec::new(),
+            selected_index: 0,
+            form: None,
+            confirm_delete: false,
+            message: None,
+        }
+    }
+
+    pub fn selected(&self) -> Option<&Route> {
+        self.routes.get(self.selected_index)
+    }
+}
+
+/// This computer's current UTC offset, to pre-fill the Zone field.
+fn local_zone() -> String {
+    validation::format_utc_offset(Local::now().offset())
+}
+
+fn optional_number<T: ToString>(value: Option<T>) -> String {
+    value.map(|v| v.to_string()).unwrap_or_default()
+}
+
+pub fn gear_name(gear: &[Gear], id: Option<i64>) -> Option<&str> {
+    let id = id?;
+    gear.iter()
+        .find(|item| item.id == Some(id))
+        .map(|item| item.name.as_str())
+}
+
 pub struct AnalyticsState {
     pub analytics: Analytics,
 }
@@ -128,27 +823,111 @@ impl AnalyticsState {
     }
 }
 
+/// The athlete switcher, shown over whichever screen is open.
+pub struct AthletePicker {
+    pub selected_index: usize,
+    /// The name being typed, while adding or renaming an athlete.
+    pub name_input: Option<String>,
+    /// Whether `name_input` renames the selected athlete rather than adding
+    /// a new one.
+    pub renaming: bool,
+    pub message: Option<String>,
+}
+
+impl AthletePicker {
+    pub fn new(selected_index: usize) -> Self {
+        Self {
+            selected_index,
+            name_input: None,
+            renaming: false,
+            message: None,
+        }
+    }
+}
+
+pub struct DataHealthState {
+    pub runs: Vec<QuarantinedRun>,
+    pub selected_index: usize,
+    /// The corrected value being typed, while fixing the selected run.
+    pub fix_input: Option<String>,
+    pub confirm_delete: bool,
+    pub message: Option<String>,
+}
+
+impl DataHealthState {
+    pub fn new() -> Self {
+        Self {
+            runs: Vec::new(),
+            selected_index: 0,
+            fix_input: None,
+            confirm_delete: false,
+            message: None,
+        }
+    }
+
+    pub fn selected(&self) -> Option<&QuarantinedRun> {
+        self.runs.get(self.selected_index)
+    }
+}
+
 pub struct App {
     pub screen: Screen,
     pub should_quit: bool,
     pub quick_entry_state: QuickEntryState,
     pub run_list_state: RunListState,
+    pub run_detail_state: RunDetailState,
+    pub run_history_state: RunHistoryState,
     pub analytics_state: AnalyticsState,
+    pub trash_state: TrashState,
+    pub gear_state: GearState,
+    pub routes_state: RoutesState,
+    pub data_health_state: DataHealthState,
+    /// Every athlete, and the one whose runs are shown.
+    pub athletes: Vec<Athlete>,
+    pub athlete_id: i64,
+    /// Set while the athlete switcher is open.
+    pub athlete_picker: Option<AthletePicker>,
     pub waiting_for_nav: bool,
+    pub units: DistanceUnit,
+    pub history: UndoHistory,
+    pub status_message: Option<String>,
+    /// `None` when running against a throwaway in-memory log.
+    pub db_path: Option<PathBuf>,
 }
 
 impl App {
-    pub fn new() -> Self {
+    pub fn new(db_path: Option<PathBuf>) -> Self {
         Self {
             screen: Screen::QuickEntry,
             should_quit: false,
             quick_entry_state: QuickEntryState::new(),
             run_list_state: RunListState::new(),
+            run_detail_state: RunDetailState::new(),
+            run_history_state: RunHistoryState::new(),
             analytics_state: AnalyticsState::new(),
+            trash_state: TrashState::new(),
+            gear_state: GearState::new(),
+            routes_state: RoutesState::new(),
+            data_health_state: DataHealthState::new(),
+            athletes: Vec::new(),
+            athlete_id: DEFAULT_ATHLETE_ID,
+            athlete_picker: None,
             waiting_for_nav: false,
+            units: DistanceUnit::Miles,
+            history: UndoHistory::new(),
+            status_message: None,
+            db_path,
         }
     }
 
+    /// The name of the athlete whose runs are shown.
+    pub fn athlete_name(&self) -> Option<&str> {
+        self.athletes
+            .iter()
+            .find(|athlete| athlete.id == Some(self.athlete_id))
+            .map(|athlete| athlete.name.as_str())
+    }
+
     pub fn switch_to_screen(&mut self, screen: Screen) {
         self.screen = screen;
     }
@@ -157,3 +936,44 @@ impl App {
         self.should_quit = true;
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{NaiveDate, NaiveTime};
+
+    fn conflict(existing_duration: Option<u32>, new_duration: Option<u32>) -> RunConflict {
+        let date = NaiveDate::from_ymd_opt(2024, 5, 1).unwrap();
+        let time = NaiveTime::from_hms_opt(7, 0, 0).unwrap();
+        RunConflict {
+            run: Run::new(date, time, 2.0, new_duration, None).unwrap(),
+            existing: Run::new(date, time, 3.0, existing_duration, None).unwrap(),
+        }
+    }
+
+    #[test]
+    fn merging_adds_durations_when_both_runs_have_one() {
+        let merged = conflict(Some(1800), Some(1200)).merged();
+        assert_eq!(merged.distance_miles, 5.0);
+        assert_eq!(merged.duration_seconds, Some(3000));
+    }
+
+    #[test]
+    fn merging_keeps_the_only_duration_there_is() {
+        assert_eq!(
+            conflict(Some(1800), None).merged().duration_seconds,
+            Some(1800)
+        );
+        assert_eq!(
+            conflict(None, Some(1200)).merged().duration_seconds,
+            Some(1200)
+        );
+    }
+
+    #[test]
+    fn merging_runs_without_durations_leaves_none() {
+        let merged = conflict(None, None).merged();
+        assert_eq!(merged.distance_miles, 5.0);
+        assert_eq!(merged.duration_seconds, None);
+    }
+}
@@ -0,0 +1,136 @@
+use crate::db::merge::MergePolicy;
+use anyhow::{bail, Result};
+use std::path::PathBuf;
+
+pub const USAGE: &str = "\
+Usage: runlogger [OPTIONS] [COMMAND]
+
+Commands:
+  (none)              Start the run logger
+  backups             List database backups with their run counts
+  restore <BACKUP>    Restore a backup by its number in `backups` or its file name
+  check               Check the daily totals used by Analytics and rebuild them if wrong
+  encrypt             Encrypt the database with a passphrase (needs the `encryption` build)
+  decrypt             Turn an encrypted database back into a plain one
+  merge <PATH>        Bring the runs of another runlogger database into this one
+  help                Show this message
+
+Options:
+  --db <PATH>         Use the database at PATH
+  --profile <NAME>    Use the named profile's database
+  --in-memory         Start with an empty log that is discarded on exit
+  --prefer <WHICH>    With merge: keep the `newer` (default), `here` or `other`
+                      version of runs changed in both databases
+
+Environment:
+  RUNLOGGER_DB            Database path (overridden by --db / --profile)
+  RUNLOGGER_PROFILE       Profile name (overridden by --db / --profile)
+  RUNLOGGER_BACKUP_KEEP   Number of daily backups to keep (default 7, 0 disables)
+  RUNLOGGER_PASSPHRASE    Passphrase of an encrypted database, instead of being asked";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Command {
+    Run,
+    ListBackups,
+    RestoreBackup(String),
+    CheckTotals,
+    Encrypt,
+    Decrypt,
+    Merge { path: PathBuf, policy: MergePolicy },
+    Help,
+}
+
+/// Where the database should come from. `--db`, `--profile` and `--in-memory`
+/// are mutually exclusive; environment variables are consulted when none is given.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DbLocation {
+    Default,
+    Path(PathBuf),
+    Profile(String),
+    Memory,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cli {
+    pub location: DbLocation,
+    pub command: Command,
+}
+
+pub fn parse_args<I: IntoIterator<Item = String>>(args: I) -> Result<Cli> {
+    let mut location = DbLocation::Default;
+    let mut policy = None;
+    let mut positional = Vec::new();
+    let mut args = args.into_iter();
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--db" | "--profile" | "--in-memory" => {
+                if location != DbLocation::Default {
+                    bail!(
+                        "Use only one of --db, --profile or --in-memory\n\n{}",
+                        USAGE
+                    );
+                }
+                if arg == "--in-memory" {
+                    location = DbLocation::Memory;
+                    continue;
+                }
+                let Some(value) = args.next() else {
+                    bail!("{} requires a value\n\n{}", arg, USAGE);
+                };
+                location = if arg == "--db" {
+                    DbLocation::Path(PathBuf::from(value))
+                } else {
+                    DbLocation::Profile(value)
+                };
+            }
+            "--prefer" => {
+                let Some(value) = args.next() else {
+                    bail!("--prefer requires a value\n\n{}", USAGE);
+                };
+                let Some(parsed) = MergePolicy::parse(&value) else {
+                    bail!(
+                        "--prefer must be newer, here or other, not '{}'\n\n{}",
+                        value,
+                        USAGE
+                    );
+                };
+                policy = Some(parsed);
+            }
+            "-h" | "--help" => positional.push("help".to_string()),
+            _ if arg.starts_with("--") => bail!("Unknown option '{}'\n\n{}", arg, USAGE),
+            _ => positional.push(arg),
+        }
+    }
+
+    let mut positional = positional.into_iter();
+    let command = match positional.next().as_deref() {
+        None => Command::Run,
+        Some("backups") => Command::ListBackups,
+        Some("restore") => match positional.next() {
+            Some(backup) => Command::RestoreBackup(backup),
+            None => bail!("restore requires a backup number or file name\n\n{}", USAGE),
+        },
+        Some("check") => Command::CheckTotals,
+        Some("encrypt") => Command::Encrypt,
+        Some("decrypt") => Command::Decrypt,
+        Some("merge") => match positional.next() {
+            Some(path) => Command::Merge {
+                path: PathBuf::from(path),
+                policy: policy.take().unwrap_or(MergePolicy::Newer),
+            },
+            None => bail!("merge requires the path of the other database\n\n{}", USAGE),
+        },
+        Some("help") => Command::Help,
+        Some(other) => bail!("Unknown command '{}'\n\n{}", other, USAGE),
+    };
+
+    if let Some(extra) = positional.next() {
+        bail!("Unexpected argument '{}'\n\n{}", extra, USAGE);
+    }
+    if policy.is_some() {
+        bail!("--prefer can only be used with merge\n\n{}", USAGE);
+    }
+
+    Ok(Cli { location, command })
+}
@@ -0,0 +1,350 @@
+use crate::db::{connection, encryption};
+use anyhow::{bail, Context, Result};
+use chrono::{Local, NaiveDateTime};
+use rusqlite::backup::Backup;
+use rusqlite::{Connection, OpenFlags};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+const DEFAULT_BACKUPS_TO_KEEP: usize = 7;
+const BACKUP_KEEP_ENV: &str = "RUNLOGGER_BACKUP_KEEP";
+
+const TIMESTAMP_FORMAT: &str = "%Y%m%d-%H%M%S";
+
+pub struct BackupInfo {
+    pub path: PathBuf,
+    pub taken_at: NaiveDateTime,
+    pub run_count: Option<i64>,
+}
+
+impl BackupInfo {
+    pub fn file_name(&self) -> String {
+        self.path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default()
+    }
+}
+
+/// Number of rotated backups to keep, from `RUNLOGGER_BACKUP_KEEP`.
+pub fn backups_to_keep() -> Result<usize> {
+    match std::env::var(BACKUP_KEEP_ENV) {
+        Ok(value) => value
+            .trim()
+            .parse()
+            .with_context(|| format!("{} must be a whole number", BACKUP_KEEP_ENV)),
+        Err(_) => Ok(DEFAULT_BACKUPS_TO_KEEP),
+    }
+}
+
+/// Finds a backup by its 1-based position in `list_backups` or by file name.
+pub fn find_backup(db_path: &Path, selector: &str) -> Result<PathBuf> {
+    let backups = list_backup_files(db_path)?;
+
+    let found = match selector.parse::<usize>() {
+        Ok(number) => number.checked_sub(1).and_then(|index| backups.get(index)),
+        Err(_) => backups
+            .iter()
+            .find(|(path, _)| path.file_name().is_some_and(|name| name == selector)),
+    };
+
+    match found {
+        Some((path, _)) => Ok(path.clone()),
+        None => bail!(
+            "No backup matching '{}'. Run `runlogger backups` to list them.",
+            selector
+        ),
+    }
+}
+
+/// Backups live in a `backups` directory next to the database, named
+/// `<db stem>-<timestamp>.db` so several databases can share the directory.
+/// A second backup in the same second gets a `-2` suffix, and so on.
+pub fn backup_dir(db_path: &Path) -> PathBuf {
+    db_path
+        .parent()
+        .map(|dir| dir.join("backups"))
+        .unwrap_or_else(|| PathBuf::from("backups"))
+}
+
+fn backup_prefix(db_path: &Path) -> String {
+    let stem = db_path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "runs".to_string());
+    format!("{}-", stem)
+}
+
+/// Takes a consistent snapshot of the open database using SQLite's online
+/// backup API, which is safe even while the database is in use. Backups of an
+/// encrypted database are encrypted with the same passphrase.
+pub fn create_backup(
+    conn: &Connection,
+    db_path: &Path,
+    passphrase: Option<&str>,
+) -> Result<PathBuf> {
+    let dir = backup_dir(db_path);
+    fs::create_dir_all(&dir).context("Failed to create backup directory")?;
+
+    let name = format!(
+        "{}{}",
+        backup_prefix(db_path),
+        Local::now().format(TIMESTAMP_FORMAT)
+    );
+    let mut backup_path = dir.join(format!("{}.db", name));
+    let mut sequence = 1;
+    while backup_path.exists() {
+        sequence += 1;
+        backup_path = dir.join(format!("{}-{}.db", name, sequence));
+    }
+
+    let mut backup_conn = Connection::open(&backup_path).context("Failed to create backup")?;
+    if let Some(passphrase) = passphrase {
+        backup_conn
+            .pragma_update(None, "key", passphrase)
+            .context("Failed to set the backup passphrase")?;
+    }
+    copy_database(conn, &mut backup_conn).context("Failed to back up database")?;
+
+    Ok(backup_path)
+}
+
+fn copy_database(from: &Connection, to: &mut Connection) -> rusqlite::Result<()> {
+    Backup::new(from, to)?.run_to_completion(100, Duration::ZERO, None)
+}
+
+/// Backs up the database if no backup has been taken today, then prunes old
+/// backups so at most `keep` remain. A `keep` of zero disables backups.
+pub fn backup_if_due(
+    conn: &Connection,
+    db_path: &Path,
+    passphrase: Option<&str>,
+    keep: usize,
+) -> Result<Option<PathBuf>> {
+    if keep == 0 {
+        return Ok(None);
+    }
+
+    let today = Local::now().date_naive();
+    let backups = list_backup_files(db_path)?;
+    if backups.iter().any(|(_, taken_at)| taken_at.date() == today) {
+        return Ok(None);
+    }
+
+    let path = create_backup(conn, db_path, passphrase)?;
+    rotate_backups(db_path, keep)?;
+    Ok(Some(path))
+}
+
+/// Removes all but the newest `keep` backups.
+pub fn rotate_backups(db_path: &Path, keep: usize) -> Result<()> {
+    for (path, _) in list_backup_files(db_path)?.into_iter().skip(keep) {
+        fs::remove_file(&path)
+            .with_context(|| format!("Failed to remove old backup {}", path.display()))?;
+    }
+    Ok(())
+}
+
+/// Lists backups newest first, with the number of runs in each.
+pub fn list_backups(db_path: &Path, passphrase: Option<&str>) -> Result<Vec<BackupInfo>> {
+    let backups = list_backup_files(db_path)?
+        .into_iter()
+        .map(|(path, taken_at)| {
+            let run_count = count_runs(&path, passphrase).ok();
+            BackupInfo {
+                path,
+                taken_at,
+                run_count,
+            }
+        })
+        .collect();
+
+    Ok(backups)
+}
+
+/// Backups of this database that aren't encrypted.
+pub fn plain_backups(db_path: &Path) -> Result<Vec<PathBuf>> {
+    backups_encrypted(db_path, false)
+}
+
+/// Backups of this database that are encrypted.
+pub fn encrypted_backups(db_path: &Path) -> Result<Vec<PathBuf>> {
+    backups_encrypted(db_path, true)
+}
+
+fn backups_encrypted(db_path: &Path, encrypted: bool) -> Result<Vec<PathBuf>> {
+    let mut backups = Vec::new();
+    for (path, _) in list_backup_files(db_path)? {
+        if encryption::is_encrypted(&path)? == encrypted {
+            backups.push(path);
+        }
+    }
+    Ok(backups)
+}
+
+/// Replaces the contents of the open database with a backup. The current
+/// state is backed up first so a restore can itself be undone. Old backups
+/// are pruned afterwards, so the one restored can't be removed first, down to
+/// `keep` but never losing the new one.
+pub fn restore_backup(
+    conn: &mut Connection,
+    db_path: &Path,
+    backup_path: &Path,
+    passphrase: Option<&str>,
+    keep: usize,
+) -> Result<()> {
+    if !backup_path.exists() {
+        bail!("Backup {} does not exist", backup_path.display());
+    }
+
+    create_backup(conn, db_path, passphrase)
+        .context("Failed to back up current database before restoring")?;
+
+    let backup_conn = open_backup(backup_path, passphrase)?;
+    copy_database(&backup_conn, conn).context("Failed to restore backup")?;
+    drop(backup_conn);
+
+    rotate_backups(db_path, keep.max(1))
+}
+
+/// This database's backups, newest first.
+fn list_backup_files(db_path: &Path) -> Result<Vec<(PathBuf, NaiveDateTime)>> {
+    let dir = backup_dir(db_path);
+    if !dir.exists() {
+        return Ok(Vec::new());
+    }
+
+    let prefix = backup_prefix(db_path);
+    let mut backups = Vec::new();
+
+    for entry in fs::read_dir(&dir).context("Failed to read backup directory")? {
+        let path = entry?.path();
+        let Some(name) = path.file_name().and_then(|name| name.to_str()) else {
+            continue;
+        };
+        let Some(timestamp) = name
+            .strip_prefix(prefix.as_str())
+            .and_then(|rest| rest.strip_suffix(".db"))
+        else {
+            continue;
+        };
+        let (timestamp, sequence) = match timestamp.rsplit_once('-') {
+            Some((rest, sequence))
+                if rest.contains('-') && sequence.bytes().all(|b| b.is_ascii_digit()) =>
+            {
+                (rest, sequence.parse::<u32>().unwrap_or(0))
+            }
+            _ => (timestamp, 1),
+        };
+        if let Ok(taken_at) = NaiveDateTime::parse_from_str(timestamp, TIMESTAMP_FORMAT) {
+            backups.push((path, taken_at, sequence));
+        }
+    }
+
+    backups.sort_by_key(|(_, taken_at, sequence)| std::cmp::Reverse((*taken_at, *sequence)));
+    Ok(backups
+        .into_iter()
+        .map(|(path, taken_at, _)| (path, taken_at))
+        .collect())
+}
+
+/// Opens a backup read-only. Backups taken while the database was encrypted
+/// are encrypted too, but older ones may not be.
+fn open_backup(path: &Path, passphrase: Option<&str>) -> Result<Connection> {
+    let conn = Connection::open_with_flags(path, OpenFlags::SQLITE_OPEN_READ_ONLY)
+        .context("Failed to open backup")?;
+    let passphrase = passphrase.filter(|_| encryption::is_encrypted(path).unwrap_or(true));
+    connection::unlock(&conn, path, passphrase)?;
+    Ok(conn)
+}
+
+fn count_runs(path: &Path, passphrase: Option<&str>) -> Result<i64> {
+    let conn = open_backup(path, passphrase)?;
+
+    let count = conn.query_row(
+        "SELECT COUNT(*) FROM runs WHERE deleted_at IS NULL",
+        [],
+        |row| row.get(0),
+    )?;
+
+    Ok(count)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::{migrations, queries};
+    use crate::models::run::Run;
+    use chrono::{NaiveDate, NaiveTime};
+
+    /// A temporary directory for one test's database and backups, removed
+    /// when the test ends.
+    struct TestDir(PathBuf);
+
+    impl TestDir {
+        fn new(name: &str) -> Self {
+            let dir =
+                std::env::temp_dir().join(format!("runlogger-{}-{}", name, std::process::id()));
+            let _ = fs::remove_dir_all(&dir);
+            fs::create_dir_all(&dir).unwrap();
+            Self(dir)
+        }
+
+        fn database(&self) -> (PathBuf, Connection) {
+            let path = self.0.join("runs.db");
+            let mut conn = Connection::open(&path).unwrap();
+            migrations::init_database(&mut conn).unwrap();
+            (path, conn)
+        }
+    }
+
+    impl Drop for TestDir {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    fn run_count(conn: &Connection) -> i64 {
+        conn.query_row("SELECT COUNT(*) FROM runs", [], |row| row.get(0))
+            .unwrap()
+    }
+
+    #[test]
+    fn backups_taken_in_the_same_second_each_get_a_file() {
+        let dir = TestDir::new("same-second");
+        let (db_path, conn) = dir.database();
+
+        let taken: Vec<PathBuf> = (0..3)
+            .map(|_| create_backup(&conn, &db_path, None).unwrap())
+            .collect();
+
+        let listed: Vec<PathBuf> = list_backup_files(&db_path)
+            .unwrap()
+            .into_iter()
+            .map(|(path, _)| path)
+            .collect();
+        let newest_first: Vec<PathBuf> = taken.into_iter().rev().collect();
+        assert_eq!(listed, newest_first);
+    }
+
+    #[test]
+    fn restoring_prunes_old_backups_after_restoring() {
+        let dir = TestDir::new("restore");
+        let (db_path, mut conn) = dir.database();
+        let oldest = create_backup(&conn, &db_path, None).unwrap();
+        let date = NaiveDate::from_ymd_opt(2024, 5, 1).unwrap();
+        let time = NaiveTime::from_hms_opt(7, 0, 0).unwrap();
+        queries::insert_run(&conn, &Run::new(date, time, 5.0, None, None).unwrap()).unwrap();
+        create_backup(&conn, &db_path, None).unwrap();
+        create_backup(&conn, &db_path, None).unwrap();
+
+        // The oldest of three, so the backup taken first makes it one too many
+        restore_backup(&mut conn, &db_path, &oldest, None, 3).unwrap();
+
+        assert_eq!(run_count(&conn), 0);
+        let backups = list_backup_files(&db_path).unwrap();
+        assert_eq!(backups.len(), 3);
+        assert!(!oldest.exists());
+    }
+}
@@ -1,28 +1,132 @@
-use anyhow::{Context, Result};
-use rusqlite::Connection;
+use crate::cli::DbLocation;
+use crate::db::migrations;
+use anyhow::{bail, Context, Result};
+use rusqlite::backup::Backup;
+use rusqlite::{Connection, ErrorCode, OpenFlags};
+use std::env;
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
 
-pub fn get_db_path() -> Result<PathBuf> {
+const DB_PATH_ENV: &str = "RUNLOGGER_DB";
+const PROFILE_ENV: &str = "RUNLOGGER_PROFILE";
+
+fn app_dir() -> Result<PathBuf> {
     let data_dir = dirs::data_dir().context("Failed to get data directory")?;
 
-    let app_dir = data_dir.join("runlogger");
+    Ok(data_dir.join("runlogger"))
+}
+
+/// Resolves the database path. Command-line options win over environment
+/// variables, which win over the default `<data_dir>/runlogger/runs.db`.
+pub fn get_db_path(location: &DbLocation) -> Result<PathBuf> {
+    let location = match location {
+        DbLocation::Default => {
+            if let Some(path) = env::var_os(DB_PATH_ENV).filter(|path| !path.is_empty()) {
+                DbLocation::Path(PathBuf::from(path))
+            } else if let Some(name) = env::var(PROFILE_ENV).ok().filter(|name| !name.is_empty()) {
+                DbLocation::Profile(name)
+            } else {
+                DbLocation::Default
+            }
+        }
+        other => other.clone(),
+    };
+
+    let db_path = match location {
+        DbLocation::Default => app_dir()?.join("runs.db"),
+        DbLocation::Path(path) => path,
+        DbLocation::Profile(name) => profile_db_path(&name)?,
+        DbLocation::Memory => bail!("An in-memory log has no database file"),
+    };
+
+    if let Some(dir) = db_path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+        if !dir.exists() {
+            fs::create_dir_all(dir).context("Failed to create database directory")?;
+        }
+    }
+
+    Ok(db_path)
+}
 
-    if !app_dir.exists() {
-        fs::create_dir_all(&app_dir).context("Failed to create application directory")?;
+/// Each profile gets its own database under `<data_dir>/runlogger/profiles`.
+fn profile_db_path(name: &str) -> Result<PathBuf> {
+    let valid = !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+    if !valid {
+        bail!(
+            "Invalid profile name '{}'. Use letters, numbers, '-' and '_' only",
+            name
+        );
     }
 
-    Ok(app_dir.join("runs.db"))
+    Ok(app_dir()?.join("profiles").join(format!("{}.db", name)))
 }
 
-pub fn open_connection(db_path: &PathBuf) -> Result<Connection> {
+/// Opens the database, unlocking it with `passphrase` if it is encrypted.
+pub fn open_connection(db_path: &Path, passphrase: Option<&str>) -> Result<Connection> {
     let conn = Connection::open(db_path).context("Failed to open database connection")?;
+    unlock(&conn, db_path, passphrase)?;
+    conn.pragma_update(None, "foreign_keys", true)
+        .context("Failed to enable foreign keys")?;
 
     Ok(conn)
 }
 
-pub fn init_db(db_path: &PathBuf) -> Result<Connection> {
-    let conn = open_connection(db_path)?;
-    crate::db::migrations::init_database(&conn).context("Failed to initialize database")?;
+/// Sets the key of an encrypted database and checks it by reading the
+/// schema, since SQLCipher only finds out the key is wrong on first read.
+/// Plain databases are opened with no passphrase.
+pub fn unlock(conn: &Connection, db_path: &Path, passphrase: Option<&str>) -> Result<()> {
+    if let Some(passphrase) = passphrase {
+        // SQLCipher logs failed decryption to stderr; the error below says it
+        #[cfg(feature = "encryption")]
+        conn.pragma_update(None, "cipher_log_level", "NONE")?;
+        conn.pragma_update(None, "key", passphrase)
+            .context("Failed to set the database passphrase")?;
+    }
+
+    match conn.query_row("SELECT COUNT(*) FROM sqlite_master", [], |_| Ok(())) {
+        Ok(()) => Ok(()),
+        Err(rusqlite::Error::SqliteFailure(e, _)) if e.code == ErrorCode::NotADatabase => {
+            match passphrase {
+                Some(_) => bail!("Wrong passphrase for {}", db_path.display()),
+                None => bail!(
+                    "{} is encrypted or is not a runlogger database",
+                    db_path.display()
+                ),
+            }
+        }
+        Err(e) => Err(e).context("Failed to read database"),
+    }
+}
+
+pub fn init_db(db_path: &Path, passphrase: Option<&str>) -> Result<Connection> {
+    let mut conn = open_connection(db_path, passphrase)?;
+    migrations::init_database(&mut conn).context("Failed to initialize database")?;
+    Ok(conn)
+}
+
+/// Opens a database to read from without changing it. One with an older
+/// schema is copied into memory and upgraded there instead.
+pub fn open_read_only(db_path: &Path, passphrase: Option<&str>) -> Result<Connection> {
+    let mut conn = Connection::open_with_flags(db_path, OpenFlags::SQLITE_OPEN_READ_ONLY)
+        .context("Failed to open database connection")?;
+    unlock(&conn, db_path, passphrase)?;
+
+    if migrations::schema_version(&conn)? < migrations::latest_version() {
+        let mut copy = Connection::open_in_memory().context("Failed to open database copy")?;
+        if let Some(passphrase) = passphrase {
+            copy.pragma_update(None, "key", passphrase)
+                .context("Failed to set the database passphrase")?;
+        }
+        Backup::new(&conn, &mut copy)?
+            .run_to_completion(100, Duration::ZERO, None)
+            .context("Failed to copy database")?;
+        conn = copy;
+    }
+    migrations::init_database(&mut conn).context("Failed to initialize database")?;
+
     Ok(conn)
 }
@@ -0,0 +1,93 @@
+use anyhow::{bail, Context, Result};
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+
+/// Every plain SQLite database starts with this header. An encrypted one
+/// looks like random bytes from the first page on.
+const SQLITE_HEADER: &[u8; 16] = b"SQLite format 3\0";
+
+/// True if the database file exists and isn't a plain SQLite database, which
+/// for a runlogger database means it was encrypted with `runlogger encrypt`.
+/// A missing or empty file is a new, plain database.
+pub fn is_encrypted(db_path: &Path) -> Result<bool> {
+    let mut file = match File::open(db_path) {
+        Ok(file) => file,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(false),
+        Err(e) => return Err(e).context("Failed to open database"),
+    };
+
+    let mut header = Vec::with_capacity(SQLITE_HEADER.len());
+    file.by_ref()
+        .take(SQLITE_HEADER.len() as u64)
+        .read_to_end(&mut header)
+        .context("Failed to read database")?;
+    Ok(!header.is_empty() && header != SQLITE_HEADER)
+}
+
+/// Fails unless this build was made with the `encryption` feature, which
+/// swaps the bundled SQLite for SQLCipher.
+pub fn ensure_supported() -> Result<()> {
+    if !cfg!(feature = "encryption") {
+        bail!(
+            "This build of runlogger can't open encrypted databases. \
+             Rebuild it with `cargo build --release --features encryption`"
+        );
+    }
+    Ok(())
+}
+
+/// Rewrites a plain database as one encrypted with `passphrase`.
+#[cfg(feature = "encryption")]
+pub fn encrypt_database(db_path: &Path, passphrase: &str) -> Result<()> {
+    let conn = super::connection::open_connection(db_path, None)?;
+    export(conn, db_path, passphrase)
+}
+
+/// Rewrites an encrypted database as a plain one.
+#[cfg(feature = "encryption")]
+pub fn decrypt_database(db_path: &Path, passphrase: &str) -> Result<()> {
+    let conn = super::connection::open_connection(db_path, Some(passphrase))?;
+    export(conn, db_path, "")
+}
+
+/// Copies the open database into a new file keyed with `key` (empty for a
+/// plain database) using SQLCipher's `sqlcipher_export`, then swaps it in.
+/// The original is only replaced once the copy is complete.
+#[cfg(feature = "encryption")]
+fn export(conn: rusqlite::Connection, db_path: &Path, key: &str) -> Result<()> {
+    use rusqlite::params;
+
+    let converted_path = db_path.with_extension("converting");
+    if converted_path.exists() {
+        std::fs::remove_file(&converted_path)
+            .context("Failed to remove a leftover partial conversion")?;
+    }
+
+    conn.execute(
+        "ATTACH DATABASE ?1 AS converted KEY ?2",
+        params![converted_path.to_string_lossy(), key],
+    )
+    .context("Failed to create the converted database")?;
+    conn.query_row("SELECT sqlcipher_export('converted')", [], |_| Ok(()))
+        .context("Failed to copy the database")?;
+    // The schema version isn't part of the export
+    let version = super::migrations::schema_version(&conn)?;
+    conn.pragma_update(Some("converted"), "user_version", version)?;
+    conn.execute("DETACH DATABASE converted", [])?;
+    drop(conn);
+
+    std::fs::rename(&converted_path, db_path)
+        .context("Failed to replace the database with the converted copy")?;
+    Ok(())
+}
+
+#[cfg(not(feature = "encryption"))]
+pub fn encrypt_database(_db_path: &Path, _passphrase: &str) -> Result<()> {
+    ensure_supported()
+}
+
+#[cfg(not(feature = "encryption"))]
+pub fn decrypt_database(_db_path: &Path, _passphrase: &str) -> Result<()> {
+    ensure_supported()
+}
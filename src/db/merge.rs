@@ -0,0 +1,867 @@
+use crate::db::queries;
+use crate::models::athlete::{Athlete, DEFAULT_ATHLETE_ID};
+use crate::models::gear::Gear;
+use crate::models::history::HistoryAction;
+use crate::models::route::Route;
+use crate::models::run::Run;
+use anyhow::{Context, Result};
+use chrono::{DateTime, NaiveDate, NaiveTime, Utc};
+use rusqlite::{params, Connection};
+use std::collections::HashMap;
+use uuid::Uuid;
+
+/// Which version to keep when a run was changed in both databases.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MergePolicy {
+    /// Whichever version was changed last.
+    Newer,
+    /// This database's version.
+    Here,
+    /// The other database's version.
+    Other,
+}
+
+impl MergePolicy {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "newer" => Some(MergePolicy::Newer),
+            "here" => Some(MergePolicy::Here),
+            "other" => Some(MergePolicy::Other),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            MergePolicy::Newer => "newer",
+            MergePolicy::Here => "here",
+            MergePolicy::Other => "other",
+        }
+    }
+}
+
+/// A run changed differently in both databases since they were last in step.
+/// `other` is the other database's version with its athlete, shoe and route
+/// given this database's ids.
+pub struct MergeConflict {
+    pub here: Run,
+    pub other: Run,
+    pub here_changed_at: DateTime<Utc>,
+    pub other_changed_at: DateTime<Utc>,
+    pub took_other: bool,
+}
+
+/// A run from the other database that couldn't be brought over, and why.
+pub struct SkippedRun {
+    pub run: Run,
+    pub reason: String,
+}
+
+#[derive(Default)]
+pub struct MergeReport {
+    /// Runs that only the other database had.
+    pub imported: Vec<Run>,
+    /// Runs only changed in the other database, now updated here.
+    pub updated: Vec<Run>,
+    pub conflicts: Vec<MergeConflict>,
+    pub skipped: Vec<SkippedRun>,
+    /// Runs that were the same in both, or only changed here.
+    pub unchanged: usize,
+}
+
+/// This database's ids for the athletes, gear and routes of the other one,
+/// matched by name.
+struct IdMap {
+    athletes: HashMap<i64, i64>,
+    gear: HashMap<i64, i64>,
+    routes: HashMap<i64, i64>,
+}
+
+impl IdMap {
+    /// Matches everything by name, adding athletes, gear and routes that are
+    /// missing here.
+    fn build(conn: &Connection, other: &Connection) -> Result<Self> {
+        let local_athletes = queries::get_athletes(conn)?;
+        let mut athletes = HashMap::new();
+        for athlete in queries::get_athletes(other)? {
+            let local_id = match find_by_name(&local_athletes, &athlete.name, |a| (&a.name, a.id)) {
+                Some(id) => id,
+                None => queries::insert_athlete(
+                    conn,
+                    &Athlete {
+                        id: None,
+                        ..athlete.clone()
+                    },
+                )?,
+            };
+            athletes.extend(athlete.id.map(|id| (id, local_id)));
+        }
+
+        let local_gear = queries::get_gear(conn)?;
+        let mut gear = HashMap::new();
+        for item in queries::get_gear(other)? {
+            let local_id = match find_by_name(&local_gear, &item.name, |g| (&g.name, g.id)) {
+                Some(id) => id,
+                None => queries::insert_gear(
+                    conn,
+                    &Gear {
+                        id: None,
+                        ..item.clone()
+                    },
+                )?,
+            };
+            gear.extend(item.id.map(|id| (id, local_id)));
+        }
+
+        let local_routes = queries::get_routes(conn)?;
+        let mut routes = HashMap::new();
+        for route in queries::get_routes(other)? {
+            let local_id = match find_by_name(&local_routes, &route.name, |r| (&r.name, r.id)) {
+                Some(id) => id,
+                None => queries::insert_route(
+                    conn,
+                    &Route {
+                        id: None,
+                        ..route.clone()
+                    },
+                )?,
+            };
+            routes.extend(route.id.map(|id| (id, local_id)));
+        }
+
+        Ok(Self {
+            athletes,
+            gear,
+            routes,
+        })
+    }
+
+    /// A run of the other database as it would be stored here.
+    fn translate(&self, run: &Run) -> Run {
+        Run {
+            id: None,
+            athlete_id: self
+                .athletes
+                .get(&run.athlete_id)
+                .copied()
+                .unwrap_or(DEFAULT_ATHLETE_ID),
+            gear_id: run.gear_id.and_then(|id| self.gear.get(&id).copied()),
+            route_id: run.route_id.and_then(|id| self.routes.get(&id).copied()),
+            ..run.clone()
+        }
+    }
+}
+
+/// Names are unique regardless of case, as in the tables.
+fn find_by_name<T>(
+    items: &[T],
+    name: &str,
+    key: impl Fn(&T) -> (&String, Option<i64>),
+) -> Option<i64> {
+    items
+        .iter()
+        .map(key)
+        .find_map(|(other, id)| other.eq_ignore_ascii_case(name).then_some(id).flatten())
+}
+
+/// True if two versions of a run hold the same values, whatever their ids
+/// and whenever they were trashed.
+fn same_values(a: &Run, b: &Run) -> bool {
+    let normalize = |run: &Run| Run {
+        id: None,
+        uuid: Uuid::nil(),
+        deleted_at: None,
+        ..run.clone()
+    };
+    normalize(a) == normalize(b) && a.deleted_at.is_some() == b.deleted_at.is_some()
+}
+
+/// When each run was last changed, from the change history. Runs with no
+/// history haven't changed since they were logged.
+fn last_changes(conn: &Connection) -> Result<HashMap<i64, DateTime<Utc>>> {
+    let mut stmt =
+        conn.prepare("SELECT run_id, MAX(changed_at) FROM run_history GROUP BY run_id")?;
+    let rows = stmt
+        .query_map([], |row| {
+            Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?))
+        })?
+        .collect::<Result<Vec<_>, _>>()
+        .context("Failed to read run history")?;
+
+    Ok(rows
+        .into_iter()
+        .filter_map(|(run_id, changed_at)| {
+            DateTime::parse_from_rfc3339(&changed_at)
+                .ok()
+                .map(|changed_at| (run_id, changed_at.with_timezone(&Utc)))
+        })
+        .collect())
+}
+
+/// What still identifies a run when the UUIDs differ: whose it is, when it
+/// started and when it was first logged. Copies of a database upgraded
+/// separately give the runs they share different UUIDs, but keep all of these.
+#[derive(PartialEq, Eq, Hash)]
+struct RunKey {
+    athlete_id: i64,
+    date: NaiveDate,
+    time_started: NaiveTime,
+    created_at: DateTime<Utc>,
+}
+
+impl RunKey {
+    fn of(run: &Run) -> Self {
+        Self {
+            athlete_id: run.athlete_id,
+            date: run.date,
+            time_started: run.time_started,
+            created_at: run.created_at,
+        }
+    }
+}
+
+/// Finds runs by UUID, or failing that by their `RunKey`.
+struct RunIndex<T> {
+    by_uuid: HashMap<Uuid, T>,
+    by_key: HashMap<RunKey, T>,
+}
+
+impl<T: Clone> RunIndex<T> {
+    fn new() -> Self {
+        Self {
+            by_uuid: HashMap::new(),
+            by_key: HashMap::new(),
+        }
+    }
+
+    fn insert(&mut self, uuid: Uuid, key: RunKey, value: T) {
+        self.by_uuid.entry(uuid).or_insert_with(|| value.clone());
+        self.by_key.entry(key).or_insert(value);
+    }
+
+    /// `run` must already have this database's athlete ids.
+    fn get(&self, run: &Run) -> Option<&T> {
+        self.by_uuid
+            .get(&run.uuid)
+            .or_else(|| self.by_key.get(&RunKey::of(run)))
+    }
+}
+
+/// The runs permanently deleted from this database, so a merge doesn't bring
+/// them back.
+fn deleted_runs(conn: &Connection) -> Result<RunIndex<()>> {
+    let mut stmt = conn.prepare(
+        "SELECT json_extract(old_values, '$.uuid'), json_extract(old_values, '$.athlete_id'),
+                json_extract(old_values, '$.date'), json_extract(old_values, '$.time_started'),
+                json_extract(old_values, '$.created_at')
+         FROM run_history
+         WHERE action = ?1 AND old_values IS NOT NULL",
+    )?;
+    let rows = stmt
+        .query_map(params![HistoryAction::Delete.as_str()], |row| {
+            Ok((
+                row.get::<_, Option<String>>(0)?,
+                row.get::<_, Option<i64>>(1)?,
+                row.get::<_, Option<String>>(2)?,
+                row.get::<_, Option<String>>(3)?,
+                row.get::<_, Option<String>>(4)?,
+            ))
+        })?
+        .collect::<Result<Vec<_>, _>>()
+        .context("Failed to read run history")?;
+
+    let mut deleted = RunIndex::new();
+    for (uuid, athlete_id, date, time_started, created_at) in rows {
+        let (Some(uuid), Some(athlete_id), Some(date), Some(time_started), Some(created_at)) = (
+            uuid.and_then(|uuid| Uuid::parse_str(&uuid).ok()),
+            athlete_id,
+            date.and_then(|date| NaiveDate::parse_from_str(&date, "%Y-%m-%d").ok()),
+            time_started.and_then(|time| NaiveTime::parse_from_str(&time, "%H:%M:%S").ok()),
+            created_at.and_then(|created_at| DateTime::parse_from_rfc3339(&created_at).ok()),
+        ) else {
+            continue;
+        };
+        let key = RunKey {
+            athlete_id,
+            date,
+            time_started,
+            created_at: created_at.with_timezone(&Utc),
+        };
+        deleted.insert(uuid, key, ());
+    }
+    Ok(deleted)
+}
+
+/// Brings the runs of another database into this one. Runs are matched by
+/// UUID, which is copied with them, falling back to their `RunKey`. A run
+/// matched that way takes the other database's UUID, so from then on the two
+/// copies match by UUID.
+///
+/// Runs only the other database has are imported, except ones in its trash
+/// or permanently deleted here. When a run differs, the change history tells
+/// which side moved on: if one side's history holds the other's current
+/// version, only that side changed and its version is kept. Otherwise the run
+/// was changed in both and `policy` decides. Nothing is ever deleted here.
+///
+/// The merge is all or nothing: if it fails part-way, this database is left
+/// as it was.
+pub fn merge(conn: &Connection, other: &Connection, policy: MergePolicy) -> Result<MergeReport> {
+    let tx = conn.unchecked_transaction()?;
+    let report = merge_runs(&tx, other, policy)?;
+    tx.commit().context("Failed to commit the merge")?;
+    Ok(report)
+}
+
+fn merge_runs(conn: &Connection, other: &Connection, policy: MergePolicy) -> Result<MergeReport> {
+    let ids = IdMap::build(conn, other)?;
+    let deleted_here = deleted_runs(conn)?;
+    let here_changes = last_changes(conn)?;
+    let other_changes = last_changes(other)?;
+    let mut here_runs = RunIndex::new();
+    for run in queries::get_every_run(conn)? {
+        here_runs.insert(run.uuid, RunKey::of(&run), run);
+    }
+
+    let mut report = MergeReport::default();
+    for other_run in queries::get_every_run(other)? {
+        let other_id = other_run.id.context("Run has no id")?;
+        let theirs = ids.translate(&other_run);
+
+        let Some(here) = here_runs.get(&theirs) else {
+            if theirs.deleted_at.is_none() && deleted_here.get(&theirs).is_none() {
+                import_run(conn, other, other_id, theirs, &mut report)?;
+            }
+            continue;
+        };
+        let here_id = here.id.context("Run has no id")?;
+        if here.uuid != theirs.uuid {
+            queries::set_run_uuid(conn, here_id, theirs.uuid)?;
+        }
+        let here = &Run {
+            uuid: theirs.uuid,
+            ..here.clone()
+        };
+        if same_values(here, &theirs) {
+            report.unchanged += 1;
+            continue;
+        }
+
+        let other_has_ours = queries::get_run_history(other, other_id)?
+            .iter()
+            .filter_map(|version| version.after.as_ref())
+            .any(|version| same_values(&ids.translate(version), here));
+        let here_has_theirs = queries::get_run_history(conn, here_id)?
+            .iter()
+            .filter_map(|version| version.after.as_ref())
+            .any(|version| same_values(version, &theirs));
+
+        let take_other = match (other_has_ours, here_has_theirs) {
+            (true, false) => {
+                report.updated.push(theirs.clone());
+                true
+            }
+            (false, true) => {
+                report.unchanged += 1;
+                false
+            }
+            _ => {
+                let here_changed_at = here_changes
+                    .get(&here_id)
+                    .copied()
+                    .unwrap_or(here.created_at);
+                let other_changed_at = other_changes
+                    .get(&other_id)
+                    .copied()
+                    .unwrap_or(other_run.created_at);
+                let took_other = match policy {
+                    MergePolicy::Newer => other_changed_at > here_changed_at,
+                    MergePolicy::Here => false,
+                    MergePolicy::Other => true,
+                };
+                report.conflicts.push(MergeConflict {
+                    here: here.clone(),
+                    other: theirs.clone(),
+                    here_changed_at,
+                    other_changed_at,
+                    took_other,
+                });
+                took_other
+            }
+        };
+
+        if take_other {
+            if let Err(e) = take_other_version(conn, other, here, &theirs, other_id) {
+                report.skipped.push(SkippedRun {
+                    run: theirs,
+                    reason: format!("{:#}", e),
+                });
+            }
+        }
+    }
+
+    Ok(report)
+}
+
+fn import_run(
+    conn: &Connection,
+    other: &Connection,
+    other_id: i64,
+    run: Run,
+    report: &mut MergeReport,
+) -> Result<()> {
+    if queries::find_run_at(conn, run.athlete_id, run.date, run.time_started, None)?.is_some() {
+        report.skipped.push(SkippedRun {
+            run,
+            reason: "another run already starts at that time here".to_string(),
+        });
+        return Ok(());
+    }
+
+    let id = queries::insert_run(conn, &run)?;
+    queries::set_splits(conn, id, &queries::get_splits(other, other_id)?)?;
+    report.imported.push(run);
+    Ok(())
+}
+
+/// Replaces a run's values, laps and trash state with the other database's.
+/// Recorded as edits, so the run's history shows what the merge changed.
+fn take_other_version(
+    conn: &Connection,
+    other: &Connection,
+    here: &Run,
+    theirs: &Run,
+    other_id: i64,
+) -> Result<()> {
+    let id = here.id.context("Run has no id")?;
+    queries::update_run(
+        conn,
+        &Run {
+            id: Some(id),
+            uuid: here.uuid,
+            created_at: here.created_at,
+            deleted_at: here.deleted_at,
+            ..theirs.clone()
+        },
+    )?;
+
+    match (here.deleted_at.is_some(), theirs.deleted_at.is_some()) {
+        (false, true) => queries::trash_run(conn, id)?,
+        (true, false) => queries::restore_run(conn, id)?,
+        _ => {}
+    }
+    queries::set_splits(conn, id, &queries::get_splits(other, other_id)?)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::migrations;
+    use crate::models::split::Split;
+
+    fn database() -> Connection {
+        let mut conn = Connection::open_in_memory().unwrap();
+        migrations::init_database(&mut conn).unwrap();
+        conn
+    }
+
+    fn run_on(day: u32) -> Run {
+        let date = NaiveDate::from_ymd_opt(2024, 5, day).unwrap();
+        let time = NaiveTime::from_hms_opt(7, 0, 0).unwrap();
+        Run::new(date, time, 5.0, Some(2400), None).unwrap()
+    }
+
+    /// A run on `day` as if first logged at 08:00:00 that day, to the second
+    /// like imported or seeded runs.
+    fn logged_run_on(day: u32) -> Run {
+        Run {
+            created_at: format!("2024-05-{:02}T08:00:00Z", day).parse().unwrap(),
+            ..run_on(day)
+        }
+    }
+
+    /// The same run logged in both databases, returning its id in each.
+    fn shared_run(here: &Connection, other: &Connection) -> (i64, i64) {
+        let run = run_on(1);
+        (
+            queries::insert_run(here, &run).unwrap(),
+            queries::insert_run(other, &run).unwrap(),
+        )
+    }
+
+    /// Sets a run's distance, with its whole history dated `changed_at`.
+    fn edit(conn: &Connection, id: i64, distance_miles: f64, changed_at: &str) {
+        let run = queries::get_run(conn, id).unwrap();
+        queries::update_run(
+            conn,
+            &Run {
+                distance_miles,
+                ..run
+            },
+        )
+        .unwrap();
+        conn.execute(
+            "UPDATE run_history SET changed_at = ?1 WHERE run_id = ?2",
+            params![changed_at, id],
+        )
+        .unwrap();
+    }
+
+    fn distance(conn: &Connection, id: i64) -> f64 {
+        queries::get_run(conn, id).unwrap().distance_miles
+    }
+
+    fn run_count(conn: &Connection) -> i64 {
+        conn.query_row("SELECT COUNT(*) FROM runs", [], |row| row.get(0))
+            .unwrap()
+    }
+
+    #[test]
+    fn failed_merge_leaves_the_database_unchanged() {
+        let here = database();
+        let other = database();
+        queries::insert_run(&other, &run_on(1)).unwrap();
+        let clash = run_on(2);
+        queries::insert_run(&other, &clash).unwrap();
+
+        // A quarantined run isn't matched, so importing the other run with
+        // its UUID fails after the first run was already imported.
+        let mut quarantined = run_on(3);
+        quarantined.uuid = clash.uuid;
+        queries::insert_run(&here, &quarantined).unwrap();
+        here.execute("UPDATE runs SET quarantine_reason = 'unreadable'", [])
+            .unwrap();
+
+        assert!(merge(&here, &other, MergePolicy::Newer).is_err());
+        assert_eq!(run_count(&here), 1);
+        assert!(here.is_autocommit());
+    }
+
+    #[test]
+    fn different_runs_logged_in_the_same_second_are_not_matched() {
+        let here = database();
+        let other = database();
+        let logged_at = logged_run_on(1).created_at;
+        queries::insert_run(&here, &logged_run_on(1)).unwrap();
+        queries::insert_run(
+            &other,
+            &Run {
+                created_at: logged_at,
+                ..logged_run_on(2)
+            },
+        )
+        .unwrap();
+
+        let report = merge(&here, &other, MergePolicy::Other).unwrap();
+        assert_eq!(report.imported.len(), 1);
+        assert!(report.conflicts.is_empty());
+        assert_eq!(run_count(&here), 2);
+    }
+
+    #[test]
+    fn a_run_matched_without_its_uuid_takes_the_other_uuid() {
+        let here = database();
+        let other = database();
+        let run = logged_run_on(1);
+        let id = queries::insert_run(&here, &run).unwrap();
+        let theirs = Run {
+            uuid: Uuid::new_v4(),
+            ..run
+        };
+        queries::insert_run(&other, &theirs).unwrap();
+
+        let report = merge(&here, &other, MergePolicy::Newer).unwrap();
+        assert_eq!(report.unchanged, 1);
+        assert_eq!(queries::get_run(&here, id).unwrap().uuid, theirs.uuid);
+        let history = queries::get_run_history(&here, id).unwrap();
+        assert_eq!(history[0].after.as_ref().unwrap().uuid, theirs.uuid);
+    }
+
+    #[test]
+    fn a_deleted_run_does_not_hold_back_another_logged_in_the_same_second() {
+        let here = database();
+        let other = database();
+        let id = queries::insert_run(&here, &logged_run_on(1)).unwrap();
+        queries::trash_run(&here, id).unwrap();
+        queries::empty_trash(&here, DEFAULT_ATHLETE_ID).unwrap();
+        queries::insert_run(
+            &other,
+            &Run {
+                created_at: logged_run_on(1).created_at,
+                ..logged_run_on(2)
+            },
+        )
+        .unwrap();
+
+        let report = merge(&here, &other, MergePolicy::Newer).unwrap();
+        assert_eq!(report.imported.len(), 1);
+    }
+
+    #[test]
+    fn imports_runs_only_the_other_database_has() {
+        let here = database();
+        let other = database();
+        let id = queries::insert_run(&other, &run_on(1)).unwrap();
+        let laps = [
+            Split {
+                lap_number: 1,
+                distance_miles: 2.5,
+                duration_seconds: 1150,
+            },
+            Split {
+                lap_number: 2,
+                distance_miles: 2.5,
+                duration_seconds: 1250,
+            },
+        ];
+        queries::set_splits(&other, id, &laps).unwrap();
+        let trashed = queries::insert_run(&other, &run_on(2)).unwrap();
+        queries::trash_run(&other, trashed).unwrap();
+
+        let report = merge(&here, &other, MergePolicy::Newer).unwrap();
+        assert_eq!(report.imported.len(), 1);
+        let runs = queries::get_every_run(&here).unwrap();
+        assert_eq!(runs.len(), 1);
+        assert_eq!(runs[0].date, run_on(1).date);
+        let imported_laps = queries::get_splits(&here, runs[0].id.unwrap()).unwrap();
+        assert_eq!(imported_laps.len(), 2);
+        assert_eq!(imported_laps[1].duration_seconds, 1250);
+    }
+
+    #[test]
+    fn updates_runs_changed_only_in_the_other_database() {
+        let here = database();
+        let other = database();
+        let (here_id, other_id) = shared_run(&here, &other);
+        edit(&other, other_id, 6.0, "2024-06-01T12:00:00+00:00");
+
+        let report = merge(&here, &other, MergePolicy::Here).unwrap();
+        assert_eq!(report.updated.len(), 1);
+        assert!(report.conflicts.is_empty());
+        assert_eq!(distance(&here, here_id), 6.0);
+    }
+
+    #[test]
+    fn keeps_runs_changed_only_here() {
+        let here = database();
+        let other = database();
+        let (here_id, _) = shared_run(&here, &other);
+        edit(&here, here_id, 6.0, "2024-06-01T12:00:00+00:00");
+
+        let report = merge(&here, &other, MergePolicy::Other).unwrap();
+        assert_eq!(report.unchanged, 1);
+        assert!(report.conflicts.is_empty());
+        assert_eq!(distance(&here, here_id), 6.0);
+    }
+
+    #[test]
+    fn carries_a_move_to_the_trash_across() {
+        let here = database();
+        let other = database();
+        let (here_id, other_id) = shared_run(&here, &other);
+        queries::trash_run(&other, other_id).unwrap();
+
+        merge(&here, &other, MergePolicy::Newer).unwrap();
+        assert!(queries::get_run(&here, here_id)
+            .unwrap()
+            .deleted_at
+            .is_some());
+    }
+
+    #[test]
+    fn reports_runs_changed_in_both_as_conflicts() {
+        let here = database();
+        let other = database();
+        let (here_id, other_id) = shared_run(&here, &other);
+        edit(&here, here_id, 6.0, "2024-06-01T12:00:00+00:00");
+        edit(&other, other_id, 7.0, "2024-06-02T12:00:00+00:00");
+
+        let report = merge(&here, &other, MergePolicy::Newer).unwrap();
+        assert_eq!(report.conflicts.len(), 1);
+        let conflict = &report.conflicts[0];
+        assert_eq!(conflict.here.distance_miles, 6.0);
+        assert_eq!(conflict.other.distance_miles, 7.0);
+        assert_eq!(
+            conflict.here_changed_at,
+            "2024-06-01T12:00:00Z".parse::<DateTime<Utc>>().unwrap()
+        );
+        assert_eq!(
+            conflict.other_changed_at,
+            "2024-06-02T12:00:00Z".parse::<DateTime<Utc>>().unwrap()
+        );
+    }
+
+    #[test]
+    fn newer_keeps_the_version_changed_last() {
+        let here = database();
+        let other = database();
+        let (here_id, other_id) = shared_run(&here, &other);
+        edit(&here, here_id, 6.0, "2024-06-01T12:00:00+00:00");
+        edit(&other, other_id, 7.0, "2024-06-02T12:00:00+00:00");
+        let later_here = queries::insert_run(&here, &run_on(2)).unwrap();
+        let earlier_other = queries::insert_run(
+            &other,
+            &Run {
+                uuid: queries::get_run(&here, later_here).unwrap().uuid,
+                ..run_on(2)
+            },
+        )
+        .unwrap();
+        edit(&here, later_here, 8.0, "2024-06-04T12:00:00+00:00");
+        edit(&other, earlier_other, 9.0, "2024-06-03T12:00:00+00:00");
+
+        let report = merge(&here, &other, MergePolicy::Newer).unwrap();
+        assert_eq!(report.conflicts.len(), 2);
+        assert_eq!(distance(&here, here_id), 7.0);
+        assert_eq!(distance(&here, later_here), 8.0);
+    }
+
+    #[test]
+    fn here_keeps_this_version_of_a_conflict() {
+        let here = database();
+        let other = database();
+        let (here_id, other_id) = shared_run(&here, &other);
+        edit(&here, here_id, 6.0, "2024-06-01T12:00:00+00:00");
+        edit(&other, other_id, 7.0, "2024-06-02T12:00:00+00:00");
+
+        let report = merge(&here, &other, MergePolicy::Here).unwrap();
+        assert!(!report.conflicts[0].took_other);
+        assert_eq!(distance(&here, here_id), 6.0);
+    }
+
+    #[test]
+    fn other_takes_the_other_version_of_a_conflict() {
+        let here = database();
+        let other = database();
+        let (here_id, other_id) = shared_run(&here, &other);
+        edit(&here, here_id, 6.0, "2024-06-02T12:00:00+00:00");
+        edit(&other, other_id, 7.0, "2024-06-01T12:00:00+00:00");
+
+        let report = merge(&here, &other, MergePolicy::Other).unwrap();
+        assert!(report.conflicts[0].took_other);
+        assert_eq!(distance(&here, here_id), 7.0);
+    }
+
+    #[test]
+    fn matches_athletes_gear_and_routes_by_name() {
+        let here = database();
+        let other = database();
+        let alex = queries::insert_athlete(&here, &Athlete::new("Alex").unwrap()).unwrap();
+        let shoe = queries::insert_gear(&here, &Gear::new("Pegasus", 400.0).unwrap()).unwrap();
+        queries::insert_gear(&other, &Gear::new("Trail shoes", 400.0).unwrap()).unwrap();
+        let other_shoe =
+            queries::insert_gear(&other, &Gear::new("pegasus", 400.0).unwrap()).unwrap();
+        let sam = queries::insert_athlete(&other, &Athlete::new("Sam").unwrap()).unwrap();
+        let loop_route =
+            queries::insert_route(&other, &Route::new("Park loop", 3.1, None).unwrap()).unwrap();
+        queries::insert_run(
+            &other,
+            &Run {
+                athlete_id: sam,
+                gear_id: Some(other_shoe),
+                route_id: Some(loop_route),
+                ..run_on(1)
+            },
+        )
+        .unwrap();
+
+        merge(&here, &other, MergePolicy::Newer).unwrap();
+        let athletes = queries::get_athletes(&here).unwrap();
+        let sam_here = athletes
+            .iter()
+            .find(|a| a.name == "Sam")
+            .unwrap()
+            .id
+            .unwrap();
+        assert_ne!(sam_here, alex);
+        assert_eq!(queries::get_gear(&here).unwrap().len(), 2);
+        let route_here = queries::get_routes(&here).unwrap()[0].id;
+        let run = &queries::get_all_runs(&here, sam_here).unwrap()[0];
+        assert_eq!(run.gear_id, Some(shoe));
+        assert_eq!(run.route_id, route_here);
+    }
+
+    #[test]
+    fn does_not_bring_back_runs_deleted_here() {
+        let here = database();
+        let other = database();
+        let (here_id, _) = shared_run(&here, &other);
+        queries::trash_run(&here, here_id).unwrap();
+        queries::empty_trash(&here, DEFAULT_ATHLETE_ID).unwrap();
+
+        let report = merge(&here, &other, MergePolicy::Other).unwrap();
+        assert!(report.imported.is_empty());
+        assert_eq!(run_count(&here), 0);
+    }
+
+    /// Each run as (uuid, date, miles, trashed), in UUID order.
+    fn contents(conn: &Connection) -> Vec<(Uuid, NaiveDate, f64, bool)> {
+        let mut runs: Vec<_> = queries::get_every_run(conn)
+            .unwrap()
+            .into_iter()
+            .map(|run| {
+                (
+                    run.uuid,
+                    run.date,
+                    run.distance_miles,
+                    run.deleted_at.is_some(),
+                )
+            })
+            .collect();
+        runs.sort_by_key(|run| run.0);
+        runs
+    }
+
+    #[test]
+    fn merging_back_and_forth_leaves_both_sides_the_same() {
+        let a = database();
+        let b = database();
+        let first = queries::insert_run(&a, &run_on(1)).unwrap();
+        queries::insert_run(&a, &run_on(2)).unwrap();
+        let third = queries::insert_run(&a, &run_on(3)).unwrap();
+        merge(&b, &a, MergePolicy::Newer).unwrap();
+        let second_in_b = queries::get_all_runs(&b, DEFAULT_ATHLETE_ID)
+            .unwrap()
+            .into_iter()
+            .find(|run| run.date == run_on(2).date)
+            .and_then(|run| run.id)
+            .unwrap();
+
+        edit(&a, first, 6.0, "2024-06-01T12:00:00+00:00");
+        queries::trash_run(&a, third).unwrap();
+        queries::insert_run(&a, &run_on(4)).unwrap();
+        edit(&b, second_in_b, 7.0, "2024-06-01T12:00:00+00:00");
+        queries::trash_run(&b, second_in_b).unwrap();
+        queries::insert_run(&b, &run_on(5)).unwrap();
+
+        merge(&b, &a, MergePolicy::Newer).unwrap();
+        merge(&a, &b, MergePolicy::Newer).unwrap();
+
+        let runs = contents(&a);
+        assert_eq!(runs, contents(&b));
+        assert_eq!(runs.len(), 5);
+        let miles: Vec<(f64, bool)> = {
+            let mut by_date = runs.clone();
+            by_date.sort_by_key(|run| run.1);
+            by_date.iter().map(|run| (run.2, run.3)).collect()
+        };
+        assert_eq!(
+            miles,
+            vec![
+                (6.0, false),
+                (7.0, true),
+                (5.0, true),
+                (5.0, false),
+                (5.0, false)
+            ]
+        );
+
+        for (here, other) in [(&a, &b), (&b, &a)] {
+            let report = merge(here, other, MergePolicy::Newer).unwrap();
+            assert!(report.imported.is_empty());
+            assert!(report.updated.is_empty());
+            assert!(report.conflicts.is_empty());
+            assert_eq!(report.unchanged, 5);
+        }
+    }
+}
@@ -1,8 +1,20 @@
-use rusqlite::{Connection, Result};
+use anyhow::{bail, Context, Result};
+use rusqlite::Connection;
 
-pub fn init_database(conn: &Connection) -> Result<()> {
-    conn.execute_batch(
-        "
+struct Migration {
+    description: &'static str,
+    sql: &'static str,
+}
+
+// Ordered list of schema migrations. The database's `PRAGMA user_version`
+// records how many of these have been applied, so entries must never be
+// edited or reordered once released - add a new one to the end instead.
+const MIGRATIONS: &[Migration] = &[
+    Migration {
+        description: "create runs table",
+        // Databases created before versioning was introduced already have this
+        // table at user_version 0, hence IF NOT EXISTS.
+        sql: "
         CREATE TABLE IF NOT EXISTS runs (
             id INTEGER PRIMARY KEY AUTOINCREMENT,
             date TEXT NOT NULL,
@@ -16,6 +28,631 @@ pub fn init_database(conn: &Connection) -> Result<()> {
         CREATE INDEX IF NOT EXISTS idx_runs_date ON runs(date DESC);
         CREATE INDEX IF NOT EXISTS idx_runs_created_at ON runs(created_at DESC);
         ",
-    )?;
+    },
+    Migration {
+        description: "add run duration",
+        sql: "ALTER TABLE runs ADD COLUMN duration_seconds INTEGER;",
+    },
+    Migration {
+        description: "create settings table",
+        sql: "
+        CREATE TABLE settings (
+            key TEXT PRIMARY KEY,
+            value TEXT NOT NULL
+        );
+        ",
+    },
+    Migration {
+        description: "soft delete runs",
+        // Rebuild the table so the date/time uniqueness only applies to runs
+        // that are not in the trash.
+        sql: "
+        CREATE TABLE runs_new (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            date TEXT NOT NULL,
+            time_started TEXT NOT NULL,
+            distance_miles REAL NOT NULL,
+            note TEXT,
+            created_at TEXT NOT NULL,
+            duration_seconds INTEGER,
+            deleted_at TEXT
+        );
+
+        INSERT INTO runs_new (id, date, time_started, distance_miles, note, created_at, duration_seconds)
+        SELECT id, date, time_started, distance_miles, note, created_at, duration_seconds FROM runs;
+
+        DROP TABLE runs;
+        ALTER TABLE runs_new RENAME TO runs;
+
+        CREATE UNIQUE INDEX idx_runs_date_time ON runs(date, time_started)
+            WHERE deleted_at IS NULL;
+        CREATE INDEX idx_runs_date ON runs(date DESC);
+        CREATE INDEX idx_runs_created_at ON runs(created_at DESC);
+        CREATE INDEX idx_runs_deleted_at ON runs(deleted_at);
+        ",
+    },
+    Migration {
+        description: "full-text index over run notes",
+        sql: "
+        CREATE VIRTUAL TABLE runs_fts USING fts5(note, content='runs', content_rowid='id');
+        INSERT INTO runs_fts(runs_fts) VALUES ('rebuild');
+
+        CREATE TRIGGER runs_fts_insert AFTER INSERT ON runs BEGIN
+            INSERT INTO runs_fts(rowid, note) VALUES (new.id, new.note);
+        END;
+        CREATE TRIGGER runs_fts_delete AFTER DELETE ON runs BEGIN
+            INSERT INTO runs_fts(runs_fts, rowid, note) VALUES ('delete', old.id, old.note);
+        END;
+        CREATE TRIGGER runs_fts_update AFTER UPDATE OF note ON runs BEGIN
+            INSERT INTO runs_fts(runs_fts, rowid, note) VALUES ('delete', old.id, old.note);
+            INSERT INTO runs_fts(rowid, note) VALUES (new.id, new.note);
+        END;
+        ",
+    },
+    Migration {
+        description: "tags",
+        sql: "
+        CREATE TABLE tags (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            name TEXT NOT NULL UNIQUE COLLATE NOCASE
+        );
+
+        CREATE TABLE run_tags (
+            run_id INTEGER NOT NULL REFERENCES runs(id) ON DELETE CASCADE,
+            tag_id INTEGER NOT NULL REFERENCES tags(id) ON DELETE CASCADE,
+            PRIMARY KEY (run_id, tag_id)
+        );
+
+        CREATE INDEX idx_run_tags_tag_id ON run_tags(tag_id);
+        ",
+    },
+    Migration {
+        description: "run type",
+        sql: "
+        ALTER TABLE runs ADD COLUMN run_type TEXT
+            CHECK (run_type IN ('easy', 'tempo', 'intervals', 'long', 'race', 'recovery'));
+        ",
+    },
+    Migration {
+        description: "gear",
+        sql: "
+        CREATE TABLE gear (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            name TEXT NOT NULL UNIQUE COLLATE NOCASE,
+            retire_at_miles REAL NOT NULL,
+            retired INTEGER NOT NULL DEFAULT 0,
+            created_at TEXT NOT NULL
+        );
+
+        ALTER TABLE runs ADD COLUMN gear_id INTEGER REFERENCES gear(id) ON DELETE SET NULL;
+
+        CREATE INDEX idx_runs_gear_id ON runs(gear_id);
+        ",
+    },
+    Migration {
+        description: "routes",
+        sql: "
+        CREATE TABLE routes (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            name TEXT NOT NULL UNIQUE COLLATE NOCASE,
+            distance_miles REAL NOT NULL,
+            description TEXT,
+            created_at TEXT NOT NULL
+        );
+
+        ALTER TABLE runs ADD COLUMN route_id INTEGER REFERENCES routes(id) ON DELETE SET NULL;
+
+        CREATE INDEX idx_runs_route_id ON runs(route_id);
+        ",
+    },
+    Migration {
+        description: "heart rate and effort",
+        sql: "
+        ALTER TABLE runs ADD COLUMN avg_heart_rate INTEGER CHECK (avg_heart_rate BETWEEN 30 AND 250);
+        ALTER TABLE runs ADD COLUMN max_heart_rate INTEGER CHECK (max_heart_rate BETWEEN 30 AND 250);
+        ALTER TABLE runs ADD COLUMN effort INTEGER CHECK (effort BETWEEN 1 AND 10);
+        ",
+    },
+    Migration {
+        description: "splits",
+        sql: "
+        CREATE TABLE splits (
+            run_id INTEGER NOT NULL REFERENCES runs(id) ON DELETE CASCADE,
+            lap_number INTEGER NOT NULL CHECK (lap_number > 0),
+            distance_miles REAL NOT NULL CHECK (distance_miles > 0),
+            duration_seconds INTEGER NOT NULL CHECK (duration_seconds > 0),
+            PRIMARY KEY (run_id, lap_number)
+        );
+        ",
+    },
+    Migration {
+        description: "daily totals",
+        sql: "
+        CREATE TABLE daily_totals (
+            date TEXT PRIMARY KEY,
+            distance_miles REAL NOT NULL,
+            run_count INTEGER NOT NULL
+        );
+
+        INSERT INTO daily_totals (date, distance_miles, run_count)
+        SELECT date, SUM(distance_miles), COUNT(*) FROM runs
+        WHERE deleted_at IS NULL
+        GROUP BY date;
+
+        CREATE TRIGGER daily_totals_insert AFTER INSERT ON runs
+        WHEN new.deleted_at IS NULL BEGIN
+            INSERT INTO daily_totals (date, distance_miles, run_count)
+            VALUES (new.date, new.distance_miles, 1)
+            ON CONFLICT (date) DO UPDATE SET
+                distance_miles = distance_miles + excluded.distance_miles,
+                run_count = run_count + 1;
+        END;
+        CREATE TRIGGER daily_totals_delete AFTER DELETE ON runs
+        WHEN old.deleted_at IS NULL BEGIN
+            UPDATE daily_totals
+            SET distance_miles = distance_miles - old.distance_miles, run_count = run_count - 1
+            WHERE date = old.date;
+            DELETE FROM daily_totals WHERE date = old.date AND run_count <= 0;
+        END;
+        CREATE TRIGGER daily_totals_update AFTER UPDATE OF date, distance_miles, deleted_at ON runs
+        BEGIN
+            UPDATE daily_totals
+            SET distance_miles = distance_miles - old.distance_miles, run_count = run_count - 1
+            WHERE date = old.date AND old.deleted_at IS NULL;
+            DELETE FROM daily_totals WHERE date = old.date AND run_count <= 0;
+            INSERT INTO daily_totals (date, distance_miles, run_count)
+            SELECT new.date, new.distance_miles, 1 WHERE new.deleted_at IS NULL
+            ON CONFLICT (date) DO UPDATE SET
+                distance_miles = distance_miles + excluded.distance_miles,
+                run_count = run_count + 1;
+        END;
+        ",
+    },
+    Migration {
+        description: "quarantine for unreadable runs",
+        sql: "
+        ALTER TABLE runs ADD COLUMN quarantine_reason TEXT;
+
+        -- Quarantined runs are left out of the daily totals like trashed ones
+        DROP TRIGGER daily_totals_insert;
+        DROP TRIGGER daily_totals_delete;
+        DROP TRIGGER daily_totals_update;
+
+        CREATE TRIGGER daily_totals_insert AFTER INSERT ON runs
+        WHEN new.deleted_at IS NULL AND new.quarantine_reason IS NULL BEGIN
+            INSERT INTO daily_totals (date, distance_miles, run_count)
+            VALUES (new.date, new.distance_miles, 1)
+            ON CONFLICT (date) DO UPDATE SET
+                distance_miles = distance_miles + excluded.distance_miles,
+                run_count = run_count + 1;
+        END;
+        CREATE TRIGGER daily_totals_delete AFTER DELETE ON runs
+        WHEN old.deleted_at IS NULL AND old.quarantine_reason IS NULL BEGIN
+            UPDATE daily_totals
+            SET distance_miles = distance_miles - old.distance_miles, run_count = run_count - 1
+            WHERE date = old.date;
+            DELETE FROM daily_totals WHERE date = old.date AND run_count <= 0;
+        END;
+        CREATE TRIGGER daily_totals_update
+        AFTER UPDATE OF date, distance_miles, deleted_at, quarantine_reason ON runs
+        BEGIN
+            UPDATE daily_totals
+            SET distance_miles = distance_miles - old.distance_miles, run_count = run_count - 1
+            WHERE date = old.date AND old.deleted_at IS NULL AND old.quarantine_reason IS NULL;
+            DELETE FROM daily_totals WHERE date = old.date AND run_count <= 0;
+            INSERT INTO daily_totals (date, distance_miles, run_count)
+            SELECT new.date, new.distance_miles, 1
+            WHERE new.deleted_at IS NULL AND new.quarantine_reason IS NULL
+            ON CONFLICT (date) DO UPDATE SET
+                distance_miles = distance_miles + excluded.distance_miles,
+                run_count = run_count + 1;
+        END;
+        ",
+    },
+    Migration {
+        description: "UTC offset of each run",
+        // NULL for runs logged before offsets were recorded; they are read in
+        // this computer's zone
+        sql: "ALTER TABLE runs ADD COLUMN utc_offset_seconds INTEGER;",
+    },
+    Migration {
+        description: "run change history",
+        // Snapshots are JSON objects of the run's columns. There's no foreign
+        // key so the history of a deleted run is kept
+        sql: "
+        CREATE TABLE run_history (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            run_id INTEGER NOT NULL,
+            action TEXT NOT NULL,
+            old_values TEXT,
+            new_values TEXT,
+            changed_at TEXT NOT NULL
+        );
+        CREATE INDEX idx_run_history_run ON run_history(run_id, id);
+        ",
+    },
+    Migration {
+        description: "athletes",
+        // The column can't be NOT NULL: SQLite only adds a foreign key column
+        // with a NULL default. Every existing run goes to the first athlete,
+        // and so does every history snapshot, so earlier versions stay readable
+        sql: "
+        CREATE TABLE athletes (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            name TEXT NOT NULL UNIQUE COLLATE NOCASE,
+            created_at TEXT NOT NULL
+        );
+        INSERT INTO athletes (id, name, created_at)
+        VALUES (1, 'Me', strftime('%Y-%m-%dT%H:%M:%SZ', 'now'));
+
+        ALTER TABLE runs ADD COLUMN athlete_id INTEGER REFERENCES athletes(id);
+        UPDATE runs SET athlete_id = 1;
+        UPDATE run_history SET
+            old_values = json_set(old_values, '$.athlete_id', 1),
+            new_values = json_set(new_values, '$.athlete_id', 1);
+
+        -- Two athletes can start a run at the same time
+        DROP INDEX idx_runs_date_time;
+        CREATE UNIQUE INDEX idx_runs_date_time ON runs(athlete_id, date, time_started)
+            WHERE deleted_at IS NULL;
+
+        -- Daily totals are kept per athlete
+        DROP TRIGGER daily_totals_insert;
+        DROP TRIGGER daily_totals_delete;
+        DROP TRIGGER daily_totals_update;
+        DROP TABLE daily_totals;
+
+        CREATE TABLE daily_totals (
+            athlete_id INTEGER NOT NULL,
+            date TEXT NOT NULL,
+            distance_miles REAL NOT NULL,
+            run_count INTEGER NOT NULL,
+            PRIMARY KEY (athlete_id, date)
+        );
+
+        INSERT INTO daily_totals (athlete_id, date, distance_miles, run_count)
+        SELECT athlete_id, date, SUM(distance_miles), COUNT(*) FROM runs
+        WHERE deleted_at IS NULL AND quarantine_reason IS NULL
+        GROUP BY athlete_id, date;
+
+        CREATE TRIGGER daily_totals_insert AFTER INSERT ON runs
+        WHEN new.deleted_at IS NULL AND new.quarantine_reason IS NULL BEGIN
+            INSERT INTO daily_totals (athlete_id, date, distance_miles, run_count)
+            VALUES (new.athlete_id, new.date, new.distance_miles, 1)
+            ON CONFLICT (athlete_id, date) DO UPDATE SET
+                distance_miles = distance_miles + excluded.distance_miles,
+                run_count = run_count + 1;
+        END;
+        CREATE TRIGGER daily_totals_delete AFTER DELETE ON runs
+        WHEN old.deleted_at IS NULL AND old.quarantine_reason IS NULL BEGIN
+            UPDATE daily_totals
+            SET distance_miles = distance_miles - old.distance_miles, run_count = run_count - 1
+            WHERE athlete_id = old.athlete_id AND date = old.date;
+            DELETE FROM daily_totals
+            WHERE athlete_id = old.athlete_id AND date = old.date AND run_count <= 0;
+        END;
+        CREATE TRIGGER daily_totals_update
+        AFTER UPDATE OF athlete_id, date, distance_miles, deleted_at, quarantine_reason ON runs
+        BEGIN
+            UPDATE daily_totals
+            SET distance_miles = distance_miles - old.distance_miles, run_count = run_count - 1
+            WHERE athlete_id = old.athlete_id AND date = old.date
+              AND old.deleted_at IS NULL AND old.quarantine_reason IS NULL;
+            DELETE FROM daily_totals
+            WHERE athlete_id = old.athlete_id AND date = old.date AND run_count <= 0;
+            INSERT INTO daily_totals (athlete_id, date, distance_miles, run_count)
+            SELECT new.athlete_id, new.date, new.distance_miles, 1
+            WHERE new.deleted_at IS NULL AND new.quarantine_reason IS NULL
+            ON CONFLICT (athlete_id, date) DO UPDATE SET
+                distance_miles = distance_miles + excluded.distance_miles,
+                run_count = run_count + 1;
+        END;
+        ",
+    },
+    Migration {
+        description: "run uuids",
+        // Random version 4 UUIDs in the usual lowercase hyphenated form, so
+        // copies of one database upgraded separately give a run different
+        // UUIDs; merging matches those by athlete, start and log time. The
+        // trigger covers runs inserted by tools that don't know the column.
+        // History snapshots get the UUID of their run too, including runs
+        // deleted since, so every version stays readable
+        sql: "
+        ALTER TABLE runs ADD COLUMN uuid TEXT;
+        UPDATE runs SET uuid = lower(
+            hex(randomblob(4)) || '-' || hex(randomblob(2)) || '-4' ||
+            substr(hex(randomblob(2)), 2) || '-' ||
+            substr('89ab', 1 + (abs(random()) % 4), 1) ||
+            substr(hex(randomblob(2)), 2) || '-' || hex(randomblob(6))
+        );
+        CREATE UNIQUE INDEX idx_runs_uuid ON runs(uuid);
+
+        CREATE TRIGGER runs_uuid AFTER INSERT ON runs WHEN new.uuid IS NULL BEGIN
+            UPDATE runs SET uuid = lower(
+                hex(randomblob(4)) || '-' || hex(randomblob(2)) || '-4' ||
+                substr(hex(randomblob(2)), 2) || '-' ||
+                substr('89ab', 1 + (abs(random()) % 4), 1) ||
+                substr(hex(randomblob(2)), 2) || '-' || hex(randomblob(6))
+            )
+            WHERE id = new.id;
+        END;
+
+        CREATE TEMP TABLE history_uuids AS
+        SELECT run_id, COALESCE((SELECT uuid FROM runs WHERE id = run_id), lower(
+            hex(randomblob(4)) || '-' || hex(randomblob(2)) || '-4' ||
+            substr(hex(randomblob(2)), 2) || '-' ||
+            substr('89ab', 1 + (abs(random()) % 4), 1) ||
+            substr(hex(randomblob(2)), 2) || '-' || hex(randomblob(6))
+        )) AS uuid
+        FROM (SELECT DISTINCT run_id FROM run_history);
+        UPDATE run_history SET
+            old_values = json_set(old_values, '$.uuid',
+                (SELECT uuid FROM history_uuids h WHERE h.run_id = run_history.run_id)),
+            new_values = json_set(new_values, '$.uuid',
+                (SELECT uuid FROM history_uuids h WHERE h.run_id = run_history.run_id));
+        DROP TABLE history_uuids;
+        ",
+    },
+];
+
+pub fn latest_version() -> u32 {
+    MIGRATIONS.len() as u32
+}
+
+pub fn schema_version(conn: &Connection) -> Result<u32> {
+    conn.query_row("PRAGMA user_version", [], |row| row.get(0))
+        .context("Failed to read schema version")
+}
+
+pub fn init_database(conn: &mut Connection) -> Result<()> {
+    let current = schema_version(conn)?;
+    let latest = latest_version();
+
+    if current > latest {
+        bail!(
+            "Database schema version {} is newer than this build supports ({}). \
+             Please upgrade runlogger.",
+            current,
+            latest
+        );
+    }
+
+    if current == latest {
+        return Ok(());
+    }
+
+    // Apply all pending migrations atomically so a failure part-way through
+    // leaves the database at its original version.
+    let tx = conn.transaction()?;
+    for (index, migration) in MIGRATIONS.iter().enumerate().skip(current as usize) {
+        let version = index as u32 + 1;
+        tx.execute_batch(migration.sql).with_context(|| {
+            format!(
+                "Failed to apply migration {} ({})",
+                version, migration.description
+            )
+        })?;
+        tx.pragma_update(None, "user_version", version)?;
+    }
+    tx.commit().context("Failed to commit schema migrations")?;
+
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::merge::{self, MergePolicy};
+    use crate::db::queries;
+    use uuid::Uuid;
+
+    /// (date, time, miles, note) of the runs in every fixture. Two share a
+    /// day so the daily totals have something to add up.
+    const RUNS: [(&str, &str, f64, &str); 3] = [
+        ("2024-03-01", "07:00:00", 5.0, "hills before work"),
+        ("2024-03-01", "18:30:00", 2.5, "easy shakeout"),
+        ("2024-03-02", "06:45:00", 10.0, "long run by the river"),
+    ];
+
+    /// A database as a build from schema version `version` left it, holding
+    /// `RUNS`. Version 0 is the schema from before versioning, which the
+    /// first migration creates unchanged.
+    fn database_at(version: u32) -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.pragma_update(None, "foreign_keys", true).unwrap();
+        for migration in &MIGRATIONS[..(version as usize).max(1)] {
+            conn.execute_batch(migration.sql).unwrap();
+        }
+        conn.pragma_update(None, "user_version", version).unwrap();
+
+        // Runs have belonged to an athlete since version 16
+        let athlete = if version >= 16 { ", athlete_id" } else { "" };
+        for (date, time, miles, note) in RUNS {
+            conn.execute(
+                &format!(
+                    "INSERT INTO runs (date, time_started, distance_miles, note, created_at{})
+                     VALUES (?1, ?2, ?3, ?4, '2024-03-02T12:00:00+00:00'{})",
+                    athlete,
+                    if version >= 16 { ", 1" } else { "" }
+                ),
+                rusqlite::params![date, time, miles, note],
+            )
+            .unwrap();
+        }
+
+        // Since version 15 each run has a history entry, snapshotting the
+        // columns runs had at that version
+        if version >= 15 {
+            conn.execute(
+                &format!(
+                    "INSERT INTO run_history (run_id, action, new_values, changed_at)
+                     SELECT id, 'insert', json_object(
+                         'id', id, 'date', date, 'time_started', time_started,
+                         'distance_miles', distance_miles, 'duration_seconds', duration_seconds,
+                         'note', note, 'created_at', created_at, 'deleted_at', deleted_at,
+                         'run_type', run_type, 'gear_id', gear_id, 'route_id', route_id,
+                         'avg_heart_rate', avg_heart_rate, 'max_heart_rate', max_heart_rate,
+                         'effort', effort, 'tags', NULL,
+                         'utc_offset_seconds', utc_offset_seconds{}
+                     ), created_at
+                     FROM runs",
+                    if version >= 16 {
+                        ", 'athlete_id', athlete_id"
+                    } else {
+                        ""
+                    }
+                ),
+                [],
+            )
+            .unwrap();
+        }
+        conn
+    }
+
+    fn schema(conn: &Connection) -> Vec<(String, String, Option<String>)> {
+        let mut stmt = conn
+            .prepare("SELECT type, name, sql FROM sqlite_master ORDER BY type, name")
+            .unwrap();
+        stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))
+            .unwrap()
+            .collect::<rusqlite::Result<_>>()
+            .unwrap()
+    }
+
+    #[test]
+    fn upgrades_every_version_to_the_latest() {
+        let mut fresh = Connection::open_in_memory().unwrap();
+        init_database(&mut fresh).unwrap();
+        let latest_schema = schema(&fresh);
+
+        for version in 0..latest_version() {
+            let mut conn = database_at(version);
+            init_database(&mut conn)
+                .unwrap_or_else(|e| panic!("upgrading from version {}: {:#}", version, e));
+
+            assert_eq!(schema_version(&conn).unwrap(), latest_version());
+            assert_eq!(schema(&conn), latest_schema, "from version {}", version);
+
+            let runs = queries::get_every_run(&conn).unwrap();
+            assert_eq!(runs.len(), RUNS.len(), "from version {}", version);
+            for (run, (date, time, miles, note)) in runs.iter().zip(RUNS) {
+                assert_eq!(run.date.to_string(), date);
+                assert_eq!(run.time_started.to_string(), time);
+                assert_eq!(run.distance_miles, miles);
+                assert_eq!(run.note.as_deref(), Some(note));
+                assert_eq!(run.athlete_id, 1);
+                assert_eq!(run.uuid.get_version_num(), 4);
+
+                if version >= 15 {
+                    let history = queries::get_run_history(&conn, run.id.unwrap()).unwrap();
+                    let after = history[0].after.as_ref();
+                    assert_eq!(
+                        after.map(|version| (version.uuid, version.distance_miles)),
+                        Some((run.uuid, miles)),
+                        "history from version {}",
+                        version
+                    );
+                }
+            }
+            let mut uuids: Vec<Uuid> = runs.iter().map(|run| run.uuid).collect();
+            uuids.sort();
+            uuids.dedup();
+            assert_eq!(uuids.len(), RUNS.len(), "from version {}", version);
+
+            let matches: Vec<String> = conn
+                .prepare(
+                    "SELECT note FROM runs WHERE id IN
+                     (SELECT rowid FROM runs_fts WHERE runs_fts MATCH 'river')",
+                )
+                .unwrap()
+                .query_map([], |row| row.get(0))
+                .unwrap()
+                .collect::<rusqlite::Result<_>>()
+                .unwrap();
+            assert_eq!(
+                matches,
+                vec!["long run by the river"],
+                "from version {}",
+                version
+            );
+
+            assert!(queries::check_daily_totals(&conn).unwrap().is_empty());
+            let day: (f64, u32) = conn
+                .query_row(
+                    "SELECT distance_miles, run_count FROM daily_totals
+                     WHERE athlete_id = 1 AND date = '2024-03-01'",
+                    [],
+                    |row| Ok((row.get(0)?, row.get(1)?)),
+                )
+                .unwrap();
+            assert_eq!(day, (7.5, 2), "from version {}", version);
+        }
+    }
+
+    #[test]
+    fn refuses_a_database_from_a_newer_build() {
+        let mut conn = database_at(latest_version());
+        conn.pragma_update(None, "user_version", latest_version() + 1)
+            .unwrap();
+
+        let error = init_database(&mut conn).unwrap_err().to_string();
+        assert!(
+            error.contains("newer than this build supports"),
+            "{}",
+            error
+        );
+        assert_eq!(schema_version(&conn).unwrap(), latest_version() + 1);
+    }
+
+    #[test]
+    fn rolls_back_when_a_migration_fails_part_way() {
+        // Migration 15 applies, then 16 fails because its table exists
+        let mut conn = database_at(14);
+        conn.execute_batch("CREATE TABLE athletes (id INTEGER PRIMARY KEY)")
+            .unwrap();
+
+        let error = format!("{:#}", init_database(&mut conn).unwrap_err());
+        assert!(error.contains("migration 16 (athletes)"), "{}", error);
+        assert_eq!(schema_version(&conn).unwrap(), 14);
+        let history_tables: u32 = conn
+            .query_row(
+                "SELECT COUNT(*) FROM sqlite_master WHERE name = 'run_history'",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(history_tables, 0);
+        let runs: u32 = conn
+            .query_row("SELECT COUNT(*) FROM runs", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(runs, RUNS.len() as u32);
+    }
+
+    #[test]
+    fn copies_upgraded_separately_still_merge_without_duplicates() {
+        // Both copies predate UUIDs, so each upgrade gives the runs new ones
+        let mut here = database_at(16);
+        let mut other = database_at(16);
+        init_database(&mut here).unwrap();
+        init_database(&mut other).unwrap();
+        let uuids = |conn: &Connection| {
+            let mut uuids: Vec<Uuid> = queries::get_every_run(conn)
+                .unwrap()
+                .iter()
+                .map(|run| run.uuid)
+                .collect();
+            uuids.sort();
+            uuids
+        };
+        assert_ne!(uuids(&here), uuids(&other));
+
+        let report = merge::merge(&here, &other, MergePolicy::Newer).unwrap();
+        assert!(report.imported.is_empty());
+        assert_eq!(report.unchanged, RUNS.len());
+        assert_eq!(uuids(&here), uuids(&other));
+
+        let report = merge::merge(&other, &here, MergePolicy::Newer).unwrap();
+        assert!(report.imported.is_empty());
+        assert_eq!(report.unchanged, RUNS.len());
+    }
+}
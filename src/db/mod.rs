@@ -1,3 +1,7 @@
+pub mod backup;
 pub mod connection;
+pub mod encryption;
+pub mod merge;
 pub mod migrations;
 pub mod queries;
+pub mod store;
@@ -1,114 +1,1307 @@
-use crate::models::run::Run;
-use anyhow::{Context, Result};
-use chrono::{NaiveDate, NaiveTime};
-use rusqlite::{params, Connection};
+use crate::logic::search::{self, SearchQuery};
+use crate::logic::validation;
+use crate::models::analytics::{DailyTotal, TagTotal};
+use crate::models::athlete::Athlete;
+use crate::models::gear::Gear;
+use crate::models::health::QuarantinedRun;
+use crate::models::history::{HistoryAction, RunVersion};
+use crate::models::route::Route;
+use crate::models::run::{Run, RunCursor, RunType};
+use crate::models::split::Split;
+use crate::models::units::DistanceUnit;
+use anyhow::{anyhow, bail, Context, Result};
+use chrono::{FixedOffset, NaiveDate, NaiveTime};
+use rusqlite::types::{Type, Value, ValueRef};
+use rusqlite::{params, Connection, OptionalExtension, Row};
+use std::ops::Deref;
+use uuid::Uuid;
+
+// Tags are folded into a single comma-separated column so every run query
+// returns complete runs without a second round trip.
+const RUN_COLUMNS: &str =
+    "id, date, time_started, distance_miles, duration_seconds, note, created_at, deleted_at, run_type, gear_id, route_id,
+     avg_heart_rate, max_heart_rate, effort,
+     (SELECT group_concat(t.name, ',') FROM run_tags rt JOIN tags t ON t.id = rt.tag_id
+      WHERE rt.run_id = runs.id) AS tags,
+     utc_offset_seconds, athlete_id, uuid";
+
+/// Names of the columns in `RUN_COLUMNS`, by position.
+const RUN_COLUMN_NAMES: [&str; 18] = [
+    "id",
+    "date",
+    "time_started",
+    "distance_miles",
+    "duration_seconds",
+    "note",
+    "created_at",
+    "deleted_at",
+    "run_type",
+    "gear_id",
+    "route_id",
+    "avg_heart_rate",
+    "max_heart_rate",
+    "effort",
+    "tags",
+    "utc_offset_seconds",
+    "athlete_id",
+    "uuid",
+];
+
+/// A stored value that couldn't be parsed, tagged with its column so the
+/// Data Health screen can point at it.
+fn invalid_value(column: usize, reason: &str) -> rusqlite::Error {
+    rusqlite::Error::FromSqlConversionFailure(column, Type::Text, reason.into())
+}
+
+/// True for errors caused by a bad value in a row rather than by the query.
+fn is_bad_value(error: &rusqlite::Error) -> bool {
+    matches!(
+        error,
+        rusqlite::Error::FromSqlConversionFailure(..)
+            | rusqlite::Error::InvalidColumnType(..)
+            | rusqlite::Error::IntegralValueOutOfRange(..)
+    )
+}
+
+/// Collects the rows that could be read, skipping any with a bad value so one
+/// corrupt run can't hide the rest. Such runs are quarantined by
+/// `quarantine_bad_runs` and listed on the Data Health screen.
+fn collect_readable<T>(
+    rows: impl Iterator<Item = rusqlite::Result<T>>,
+) -> rusqlite::Result<Vec<T>> {
+    let mut items = Vec::new();
+    for row in rows {
+        match row {
+            Ok(item) => items.push(item),
+            Err(e) if is_bad_value(&e) => {}
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(items)
+}
+
+/// A transaction for a single write, held as a savepoint so it also works
+/// inside a larger transaction such as a merge. Rolled back unless committed.
+struct WriteTransaction<'conn> {
+    conn: &'conn Connection,
+    committed: bool,
+}
+
+impl<'conn> WriteTransaction<'conn> {
+    fn begin(conn: &'conn Connection) -> rusqlite::Result<Self> {
+        conn.execute_batch("SAVEPOINT write")?;
+        Ok(Self {
+            conn,
+            committed: false,
+        })
+    }
+
+    fn commit(mut self) -> rusqlite::Result<()> {
+        self.conn.execute_batch("RELEASE write")?;
+        self.committed = true;
+        Ok(())
+    }
+}
+
+impl Deref for WriteTransaction<'_> {
+    type Target = Connection;
+
+    fn deref(&self) -> &Connection {
+        self.conn
+    }
+}
+
+impl Drop for WriteTransaction<'_> {
+    fn drop(&mut self) {
+        if !self.committed {
+            let _ = self.conn.execute_batch("ROLLBACK TO write; RELEASE write");
+        }
+    }
+}
+
+fn run_from_row(row: &Row) -> rusqlite::Result<Run> {
+    let date_str: String = row.get(1)?;
+    let time_str: String = row.get(2)?;
+    let created_str: String = row.get(6)?;
+    let deleted_str: Option<String> = row.get(7)?;
+    let run_type_str: Option<String> = row.get(8)?;
+    let tags_str: Option<String> = row.get(14)?;
+    let offset_seconds: Option<i32> = row.get(15)?;
+    let uuid_str: String = row.get(17)?;
+
+    let mut tags: Vec<String> = tags_str
+        .map(|s| s.split(',').map(str::to_string).collect())
+        .unwrap_or_default();
+    tags.sort_by_key(|tag| tag.to_lowercase());
+
+    Ok(Run {
+        id: Some(row.get(0)?),
+        uuid: Uuid::parse_str(&uuid_str).map_err(|_| invalid_value(17, "not a valid UUID"))?,
+        athlete_id: row.get(16)?,
+        date: NaiveDate::parse_from_str(&date_str, "%Y-%m-%d")
+            .map_err(|_| invalid_value(1, "not a valid date (YYYY-MM-DD)"))?,
+        time_started: NaiveTime::parse_from_str(&time_str, "%H:%M:%S")
+            .map_err(|_| invalid_value(2, "not a valid time (HH:MM:SS)"))?,
+        distance_miles: row.get(3)?,
+        duration_seconds: row.get(4)?,
+        note: row.get(5)?,
+        run_type: run_type_str
+            .map(|s| RunType::parse(&s).ok_or_else(|| invalid_value(8, "not a known run type")))
+            .transpose()?,
+        gear_id: row.get(9)?,
+        route_id: row.get(10)?,
+        avg_heart_rate: row.get(11)?,
+        max_heart_rate: row.get(12)?,
+        effort: row.get(13)?,
+        tags,
+        utc_offset: offset_seconds
+            .map(|seconds| {
+                FixedOffset::east_opt(seconds)
+                    .ok_or_else(|| invalid_value(15, "not a valid UTC offset"))
+            })
+            .transpose()?,
+        created_at: chrono::DateTime::parse_from_rfc3339(&created_str)
+            .map_err(|_| invalid_value(6, "not a valid RFC 3339 timestamp"))?
+            .with_timezone(&chrono::Utc),
+        deleted_at: deleted_str
+            .map(|s| {
+                chrono::DateTime::parse_from_rfc3339(&s)
+                    .map(|dt| dt.with_timezone(&chrono::Utc))
+                    .map_err(|_| invalid_value(7, "not a valid RFC 3339 timestamp"))
+            })
+            .transpose()?,
+    })
+}
 
 pub fn insert_run(conn: &Connection, run: &Run) -> Result<i64> {
-    conn.execute(
-        "INSERT INTO runs (date, time_started, distance_miles, note, created_at)
-         VALUES (?1, ?2, ?3, ?4, ?5)",
+    let tx = WriteTransaction::begin(conn)?;
+    tx.execute(
+        "INSERT INTO runs (date, time_started, distance_miles, duration_seconds, note, run_type, gear_id, route_id,
+                           avg_heart_rate, max_heart_rate, effort, created_at, utc_offset_seconds, athlete_id, uuid)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15)",
         params![
             run.date.to_string(),
             run.time_started.to_string(),
             run.distance_miles,
+            run.duration_seconds,
             run.note,
+            run.run_type.map(RunType::as_str),
+            run.gear_id,
+            run.route_id,
+            run.avg_heart_rate,
+            run.max_heart_rate,
+            run.effort,
             run.created_at.to_rfc3339(),
+            run.utc_offset.map(|offset| offset.local_minus_utc()),
+            run.athlete_id,
+            run.uuid.to_string(),
         ],
     )
-    .context("Failed to insert run")?;
+    .context("Failed to insert run (a run with the same UUID may already exist)")?;
 
-    Ok(conn.last_insert_rowid())
+    let id = tx.last_insert_rowid();
+    set_run_tags(&tx, id, &run.tags)?;
+    record_history(&tx, id, HistoryAction::Insert, None)?;
+    tx.commit()?;
+
+    Ok(id)
 }
 
-pub fn get_all_runs(conn: &Connection) -> Result<Vec<Run>> {
-    let mut stmt = conn.prepare(
-        "SELECT id, date, time_started, distance_miles, note, created_at
+pub fn get_all_runs(conn: &Connection, athlete_id: i64) -> Result<Vec<Run>> {
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {}
          FROM runs
+         WHERE athlete_id = ?1 AND deleted_at IS NULL AND quarantine_reason IS NULL
          ORDER BY date DESC, time_started DESC",
-    )?;
+        RUN_COLUMNS
+    ))?;
 
-    let runs = stmt
-        .query_map([], |row| {
-            let date_str: String = row.get(1)?;
-            let time_str: String = row.get(2)?;
-            let created_str: String = row.get(5)?;
+    let runs = collect_readable(stmt.query_map(params![athlete_id], run_from_row)?)?;
 
-            Ok(Run {
-                id: Some(row.get(0)?),
-                date: NaiveDate::parse_from_str(&date_str, "%Y-%m-%d")
-                    .map_err(|_| rusqlite::Error::InvalidQuery)?,
-                time_started: NaiveTime::parse_from_str(&time_str, "%H:%M:%S")
-                    .map_err(|_| rusqlite::Error::InvalidQuery)?,
-                distance_miles: row.get(3)?,
-                note: row.get(4)?,
-                created_at: chrono::DateTime::parse_from_rfc3339(&created_str)
-                    .map_err(|_| rusqlite::Error::InvalidQuery)?
-                    .with_timezone(&chrono::Utc),
-            })
-        })?
-        .collect::<Result<Vec<_>, _>>()?;
+    Ok(runs)
+}
+
+/// Every readable run of every athlete, including those in the trash, oldest
+/// first. Used when merging another database.
+pub fn get_every_run(conn: &Connection) -> Result<Vec<Run>> {
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {}
+         FROM runs
+         WHERE quarantine_reason IS NULL
+         ORDER BY date, time_started, id",
+        RUN_COLUMNS
+    ))?;
+
+    let runs = collect_readable(stmt.query_map([], run_from_row)?)?;
 
     Ok(runs)
 }
 
-#[allow(dead_code)]
+pub fn get_run(conn: &Connection, id: i64) -> Result<Run> {
+    conn.query_row(
+        &format!("SELECT {} FROM runs WHERE id = ?1", RUN_COLUMNS),
+        params![id],
+        run_from_row,
+    )
+    .context("Failed to load run")
+}
+
+/// The athlete's run (not in the trash) starting at exactly this date and
+/// time, other than `ignore_id`. Only one can exist, because of the unique
+/// index.
+pub fn find_run_at(
+    conn: &Connection,
+    athlete_id: i64,
+    date: NaiveDate,
+    time_started: NaiveTime,
+    ignore_id: Option<i64>,
+) -> Result<Option<Run>> {
+    conn.query_row(
+        &format!(
+            "SELECT {} FROM runs
+             WHERE athlete_id = ?1 AND date = ?2 AND time_started = ?3 AND id IS NOT ?4
+               AND deleted_at IS NULL AND quarantine_reason IS NULL",
+            RUN_COLUMNS
+        ),
+        params![
+            athlete_id,
+            date.to_string(),
+            time_started.to_string(),
+            ignore_id
+        ],
+        run_from_row,
+    )
+    .optional()
+    .context("Failed to look for a run at that date and time")
+}
+
 pub fn get_runs_by_date_range(
     conn: &Connection,
+    athlete_id: i64,
     start_date: NaiveDate,
     end_date: NaiveDate,
 ) -> Result<Vec<Run>> {
-    let mut stmt = conn.prepare(
-        "SELECT id, date, time_started, distance_miles, note, created_at
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {}
          FROM runs
-         WHERE date >= ?1 AND date <= ?2
+         WHERE athlete_id = ?1 AND date >= ?2 AND date <= ?3
+           AND deleted_at IS NULL AND quarantine_reason IS NULL
          ORDER BY date DESC, time_started DESC",
-    )?;
+        RUN_COLUMNS
+    ))?;
 
-    let runs = stmt
-        .query_map(
-            params![start_date.to_string(), end_date.to_string()],
-            |row| {
-                let date_str: String = row.get(1)?;
-                let time_str: String = row.get(2)?;
-                let created_str: String = row.get(5)?;
-
-                Ok(Run {
-                    id: Some(row.get(0)?),
-                    date: NaiveDate::parse_from_str(&date_str, "%Y-%m-%d")
-                        .map_err(|_| rusqlite::Error::InvalidQuery)?,
-                    time_started: NaiveTime::parse_from_str(&time_str, "%H:%M:%S")
-                        .map_err(|_| rusqlite::Error::InvalidQuery)?,
-                    distance_miles: row.get(3)?,
-                    note: row.get(4)?,
-                    created_at: chrono::DateTime::parse_from_rfc3339(&created_str)
-                        .map_err(|_| rusqlite::Error::InvalidQuery)?
-                        .with_timezone(&chrono::Utc),
-                })
-            },
-        )?
-        .collect::<Result<Vec<_>, _>>()?;
+    let runs = collect_readable(stmt.query_map(
+        params![athlete_id, start_date.to_string(), end_date.to_string()],
+        run_from_row,
+    )?)?;
+
+    Ok(runs)
+}
+
+/// Builds the WHERE conditions for the athlete's runs whose note contains
+/// every search term (as a word prefix) and that carry every tag in the
+/// query, with the values they bind in order.
+fn search_conditions(athlete_id: i64, query: &SearchQuery) -> (Vec<String>, Vec<Value>) {
+    let mut conditions =
+        vec!["athlete_id = ?1 AND deleted_at IS NULL AND quarantine_reason IS NULL".to_string()];
+    let mut values: Vec<Value> = vec![Value::Integer(athlete_id)];
+
+    if !query.terms.is_empty() {
+        values.push(Value::Text(search::fts_query(&query.terms)));
+        conditions.push(format!(
+            "id IN (SELECT rowid FROM runs_fts WHERE runs_fts MATCH ?{})",
+            values.len()
+        ));
+    }
+
+    for tag in &query.tags {
+        values.push(Value::Text(tag.clone()));
+        conditions.push(format!(
+            "id IN (SELECT rt.run_id FROM run_tags rt JOIN tags t ON t.id = rt.tag_id
+                    WHERE t.name = ?{})",
+            values.len()
+        ));
+    }
+
+    (conditions, values)
+}
+
+/// Fetches up to `limit` of the athlete's runs matching the search, newest
+/// first, starting after `after`. Paging by key rather than offset keeps
+/// later pages as cheap as the first. An empty query matches every run not in
+/// the trash.
+pub fn get_runs_page(
+    conn: &Connection,
+    athlete_id: i64,
+    query: &SearchQuery,
+    after: Option<&RunCursor>,
+    limit: usize,
+) -> Result<Vec<Run>> {
+    let (mut conditions, mut values) = search_conditions(athlete_id, query);
+
+    if let Some(cursor) = after {
+        values.push(Value::Text(cursor.date.to_string()));
+        values.push(Value::Text(cursor.time_started.to_string()));
+        values.push(Value::Integer(cursor.id));
+        conditions.push(format!(
+            "(date, time_started, id) < (?{}, ?{}, ?{})",
+            values.len() - 2,
+            values.len() - 1,
+            values.len()
+        ));
+    }
+
+    values.push(Value::Integer(limit as i64));
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {}
+         FROM runs
+         WHERE {}
+         ORDER BY date DESC, time_started DESC, id DESC
+         LIMIT ?{}",
+        RUN_COLUMNS,
+        conditions.join(" AND "),
+        values.len()
+    ))?;
+
+    let runs = collect_readable(stmt.query_map(rusqlite::params_from_iter(values), run_from_row)?)
+        .context("Failed to load runs")?;
 
     Ok(runs)
 }
 
+/// Counts the athlete's runs matching the search without loading them.
+pub fn count_runs(conn: &Connection, athlete_id: i64, query: &SearchQuery) -> Result<usize> {
+    let (conditions, values) = search_conditions(athlete_id, query);
+    let count: i64 = conn
+        .query_row(
+            &format!(
+                "SELECT COUNT(*) FROM runs WHERE {}",
+                conditions.join(" AND ")
+            ),
+            rusqlite::params_from_iter(values),
+            |row| row.get(0),
+        )
+        .context("Failed to count runs")?;
+
+    Ok(count as usize)
+}
+
 pub fn update_run(conn: &Connection, run: &Run) -> Result<()> {
     let id = run.id.context("Run must have an id to be updated")?;
-    conn.execute(
-        "UPDATE runs SET date = ?1, time_started = ?2, distance_miles = ?3, note = ?4 WHERE id = ?5",
+    let tx = WriteTransaction::begin(conn)?;
+    let before = run_snapshot(&tx, id)?;
+    tx.execute(
+        "UPDATE runs SET date = ?1, time_started = ?2, distance_miles = ?3, duration_seconds = ?4, note = ?5,
+         run_type = ?6, gear_id = ?7, route_id = ?8,
+         avg_heart_rate = ?9, max_heart_rate = ?10, effort = ?11, utc_offset_seconds = ?12,
+         athlete_id = ?13
+         WHERE id = ?14",
         params![
             run.date.to_string(),
             run.time_started.to_string(),
             run.distance_miles,
+            run.duration_seconds,
             run.note,
+            run.run_type.map(RunType::as_str),
+            run.gear_id,
+            run.route_id,
+            run.avg_heart_rate,
+            run.max_heart_rate,
+            run.effort,
+            run.utc_offset.map(|offset| offset.local_minus_utc()),
+            run.athlete_id,
             id,
         ],
     )
     .context("Failed to update run")?;
+    set_run_tags(&tx, id, &run.tags)?;
+    record_history(&tx, id, HistoryAction::Update, before)?;
+    tx.commit()?;
+    Ok(())
+}
+
+/// Gives a run a different UUID, in its history too so every version still
+/// belongs to the run. Used by a merge that found the run under another UUID.
+pub fn set_run_uuid(conn: &Connection, id: i64, uuid: Uuid) -> Result<()> {
+    let tx = WriteTransaction::begin(conn)?;
+    tx.execute(
+        "UPDATE runs SET uuid = ?1 WHERE id = ?2",
+        params![uuid.to_string(), id],
+    )
+    .context("Failed to change the run's UUID")?;
+    tx.execute(
+        "UPDATE run_history SET
+             old_values = json_set(old_values, '$.uuid', ?1),
+             new_values = json_set(new_values, '$.uuid', ?1)
+         WHERE run_id = ?2",
+        params![uuid.to_string(), id],
+    )
+    .context("Failed to change the run's UUID in its history")?;
+    tx.commit()?;
+    Ok(())
+}
+
+/// Replaces a run's tags, creating any tags that don't exist yet and
+/// dropping tags no run uses any more.
+fn set_run_tags(conn: &Connection, run_id: i64, tags: &[String]) -> Result<()> {
+    conn.execute("DELETE FROM run_tags WHERE run_id = ?1", params![run_id])
+        .context("Failed to update tags")?;
+
+    for tag in tags {
+        conn.execute(
+            "INSERT OR IGNORE INTO tags (name) VALUES (?1)",
+            params![tag],
+        )
+        .context("Failed to save tag")?;
+        conn.execute(
+            "INSERT OR IGNORE INTO run_tags (run_id, tag_id)
+             SELECT ?1, id FROM tags WHERE name = ?2",
+            params![run_id, tag],
+        )
+        .context("Failed to tag run")?;
+    }
+
+    delete_unused_tags(conn)
+}
+
+fn delete_unused_tags(conn: &Connection) -> Result<()> {
+    conn.execute(
+        "DELETE FROM tags WHERE id NOT IN (SELECT tag_id FROM run_tags)",
+        [],
+    )
+    .context("Failed to remove unused tags")?;
+    Ok(())
+}
+
+/// Every tag in use, alphabetically, for autocomplete.
+pub fn get_all_tags(conn: &Connection) -> Result<Vec<String>> {
+    let mut stmt = conn.prepare("SELECT name FROM tags ORDER BY name COLLATE NOCASE")?;
+    let tags = stmt
+        .query_map([], |row| row.get(0))?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(tags)
+}
+
+/// Moves a run to the trash. Trashed runs are excluded from every other
+/// query until restored.
+pub fn trash_run(conn: &Connection, id: i64) -> Result<()> {
+    let tx = WriteTransaction::begin(conn)?;
+    let before = run_snapshot(&tx, id)?;
+    let updated = tx
+        .execute(
+            "UPDATE runs SET deleted_at = ?1 WHERE id = ?2 AND deleted_at IS NULL",
+            params![chrono::Utc::now().to_rfc3339(), id],
+        )
+        .context("Failed to move run to trash")?;
+    if updated == 0 {
+        bail!("Run no longer exists");
+    }
+    record_history(&tx, id, HistoryAction::Trash, before)?;
+    tx.commit()?;
+    Ok(())
+}
+
+pub fn get_trashed_runs(conn: &Connection, athlete_id: i64) -> Result<Vec<Run>> {
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {}
+         FROM runs
+         WHERE athlete_id = ?1 AND deleted_at IS NOT NULL AND quarantine_reason IS NULL
+         ORDER BY deleted_at DESC",
+        RUN_COLUMNS
+    ))?;
+
+    let runs = collect_readable(stmt.query_map(params![athlete_id], run_from_row)?)?;
+
+    Ok(runs)
+}
+
+pub fn restore_run(conn: &Connection, id: i64) -> Result<()> {
+    let tx = WriteTransaction::begin(conn)?;
+    let before = run_snapshot(&tx, id)?;
+    let updated = tx
+        .execute(
+            "UPDATE runs SET deleted_at = NULL WHERE id = ?1 AND deleted_at IS NOT NULL",
+            params![id],
+        )
+        .context("Failed to restore run (another run may already exist at that date and time)")?;
+    if updated == 0 {
+        bail!("Run is no longer in the trash");
+    }
+    record_history(&tx, id, HistoryAction::Restore, before)?;
+    tx.commit()?;
+    Ok(())
+}
+
+/// Permanently deletes every run in the athlete's trash, returning how many
+/// were removed.
+pub fn empty_trash(conn: &Connection, athlete_id: i64) -> Result<usize> {
+    let tx = WriteTransaction::begin(conn)?;
+    tx.execute(
+        &format!(
+            "INSERT INTO run_history (run_id, action, old_values, new_values, changed_at)
+             SELECT id, ?1, {}, NULL, ?2 FROM runs
+             WHERE athlete_id = ?3 AND deleted_at IS NOT NULL",
+            snapshot_expression()
+        ),
+        params![
+            HistoryAction::Delete.as_str(),
+            chrono::Utc::now().to_rfc3339(),
+            athlete_id
+        ],
+    )
+    .context("Failed to record deleted runs")?;
+    let removed = tx
+        .execute(
+            "DELETE FROM runs WHERE athlete_id = ?1 AND deleted_at IS NOT NULL",
+            params![athlete_id],
+        )
+        .context("Failed to empty trash")?;
+    delete_unused_tags(&tx)?;
+    tx.commit()?;
+    Ok(removed)
+}
+
+/// A JSON object of a run row's values, keyed by `RUN_COLUMN_NAMES`, as
+/// stored in the change history.
+fn snapshot_expression() -> String {
+    let fields: Vec<String> = RUN_COLUMN_NAMES
+        .iter()
+        .map(|&name| match name {
+            "tags" => "'tags', (SELECT group_concat(t.name, ',') FROM run_tags rt
+                     JOIN tags t ON t.id = rt.tag_id WHERE rt.run_id = runs.id)"
+                .to_string(),
+            name => format!("'{0}', {0}", name),
+        })
+        .collect();
+    format!("json_object({})", fields.join(", "))
+}
+
+/// The current values of a run as a history snapshot, or `None` if the row
+/// doesn't exist.
+fn run_snapshot(conn: &Connection, id: i64) -> Result<Option<String>> {
+    conn.query_row(
+        &format!("SELECT {} FROM runs WHERE id = ?1", snapshot_expression()),
+        params![id],
+        |row| row.get(0),
+    )
+    .optional()
+    .context("Failed to read run for its history")
+}
+
+/// Adds a history entry for a change to a run, taking its values after the
+/// change from the row itself. Nothing is recorded if nothing changed.
+fn record_history(
+    conn: &Connection,
+    run_id: i64,
+    action: HistoryAction,
+    before: Option<String>,
+) -> Result<()> {
+    let after = run_snapshot(conn, run_id)?;
+    if before == after {
+        return Ok(());
+    }
+
+    conn.execute(
+        "INSERT INTO run_history (run_id, action, old_values, new_values, changed_at)
+         VALUES (?1, ?2, ?3, ?4, ?5)",
+        params![
+            run_id,
+            action.as_str(),
+            before,
+            after,
+            chrono::Utc::now().to_rfc3339()
+        ],
+    )
+    .context("Failed to record run history")?;
     Ok(())
 }
 
-pub fn delete_run(conn: &Connection, id: i64) -> Result<()> {
-    conn.execute("DELETE FROM runs WHERE id = ?1", params![id])
+/// Reads a history snapshot back into a run, as if it were a row of
+/// `RUN_COLUMNS`.
+fn run_from_snapshot(conn: &Connection, snapshot: &str) -> rusqlite::Result<Run> {
+    let columns: Vec<String> = RUN_COLUMN_NAMES
+        .iter()
+        .map(|name| format!("json_extract(?1, '$.{}')", name))
+        .collect();
+    conn.query_row(
+        &format!("SELECT {}", columns.join(", ")),
+        params![snapshot],
+        run_from_row,
+    )
+}
+
+/// Every recorded change to a run, newest first. A snapshot that can no
+/// longer be read (such as one taken while the run was quarantined) is left
+/// empty.
+pub fn get_run_history(conn: &Connection, run_id: i64) -> Result<Vec<RunVersion>> {
+    let mut stmt = conn.prepare(
+        "SELECT action, changed_at, old_values, new_values
+         FROM run_history
+         WHERE run_id = ?1
+         ORDER BY id DESC",
+    )?;
+    let rows = stmt
+        .query_map(params![run_id], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, Option<String>>(2)?,
+                row.get::<_, Option<String>>(3)?,
+            ))
+        })?
+        .collect::<Result<Vec<_>, _>>()
+        .context("Failed to load run history")?;
+
+    let snapshot =
+        |values: Option<String>| values.and_then(|values| run_from_snapshot(conn, &values).ok());
+    rows.into_iter()
+        .map(|(action, changed_at, old_values, new_values)| {
+            Ok(RunVersion {
+                run_id,
+                action: HistoryAction::parse(&action)
+                    .ok_or_else(|| anyhow!("Unknown history action '{}'", action))?,
+                changed_at: chrono::DateTime::parse_from_rfc3339(&changed_at)
+                    .context("Invalid history timestamp")?
+                    .with_timezone(&chrono::Utc),
+                before: snapshot(old_values),
+                after: snapshot(new_values),
+            })
+        })
+        .collect()
+}
+
+/// Why a run row can't be read: the index of the offending column, when
+/// known, and the reason. `None` if the row reads fine.
+fn diagnose_run(row: &Row) -> Option<(Option<usize>, String)> {
+    let error = run_from_row(row).err()?;
+    Some(match error {
+        rusqlite::Error::FromSqlConversionFailure(column, _, e) => (Some(column), e.to_string()),
+        rusqlite::Error::InvalidColumnType(column, _, found) => (
+            Some(column),
+            format!(
+                "expected {} but found {}",
+                expected_type(RUN_COLUMN_NAMES[column]),
+                describe_type(found)
+            ),
+        ),
+        rusqlite::Error::IntegralValueOutOfRange(column, value) => {
+            (Some(column), format!("{} is out of range", value))
+        }
+        other => (None, other.to_string()),
+    })
+}
+
+fn expected_type(column: &str) -> &'static str {
+    match column {
+        "distance_miles" => "a number",
+        "id" | "duration_seconds" | "gear_id" | "route_id" | "avg_heart_rate"
+        | "max_heart_rate" | "effort" | "utc_offset_seconds" | "athlete_id" => "a whole number",
+        _ => "text",
+    }
+}
+
+fn describe_type(found: Type) -> &'static str {
+    match found {
+        Type::Null => "nothing",
+        Type::Integer => "a whole number",
+        Type::Real => "a decimal number",
+        Type::Text => "text",
+        Type::Blob => "binary data",
+    }
+}
+
+fn describe_problem(column: Option<usize>, reason: &str) -> String {
+    match column {
+        Some(column) => format!("{}: {}", RUN_COLUMN_NAMES[column], reason),
+        None => reason.to_string(),
+    }
+}
+
+/// The stored value of a column as text, whatever its type.
+fn raw_value(row: &Row, column: usize) -> String {
+    match row.get_ref(column) {
+        Ok(ValueRef::Null) => "(empty)".to_string(),
+        Ok(ValueRef::Integer(i)) => i.to_string(),
+        Ok(ValueRef::Real(f)) => f.to_string(),
+        Ok(ValueRef::Text(text)) => String::from_utf8_lossy(text).into_owned(),
+        Ok(ValueRef::Blob(bytes)) => format!("({} bytes of binary data)", bytes.len()),
+        Err(_) => String::new(),
+    }
+}
+
+/// Quarantines every run whose row can't be read, so it stops counting
+/// anywhere, and releases quarantined runs that read fine again. Returns how
+/// many runs are quarantined.
+pub fn quarantine_bad_runs(conn: &Connection) -> Result<usize> {
+    let mut changes: Vec<(i64, Option<String>)> = Vec::new();
+    let mut quarantined = 0;
+    {
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {}, quarantine_reason FROM runs",
+            RUN_COLUMNS
+        ))?;
+        let mut rows = stmt.query([])?;
+        while let Some(row) = rows.next()? {
+            let id: i64 = row.get(0)?;
+            let current: Option<String> = row.get(RUN_COLUMN_NAMES.len())?;
+            let reason =
+                diagnose_run(row).map(|(column, reason)| describe_problem(column, &reason));
+            if reason.is_some() {
+                quarantined += 1;
+            }
+            if reason != current {
+                changes.push((id, reason));
+            }
+        }
+    }
+
+    let tx = WriteTransaction::begin(conn)?;
+    for (id, reason) in changes {
+        tx.execute(
+            "UPDATE runs SET quarantine_reason = ?1 WHERE id = ?2",
+            params![reason, id],
+        )
+        .context("Failed to quarantine run")?;
+    }
+    tx.commit()?;
+
+    Ok(quarantined)
+}
+
+pub fn get_quarantined_runs(conn: &Connection) -> Result<Vec<QuarantinedRun>> {
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {}, quarantine_reason FROM runs
+         WHERE quarantine_reason IS NOT NULL
+         ORDER BY id",
+        RUN_COLUMNS
+    ))?;
+
+    let runs = stmt
+        .query_map([], |row| {
+            let id = row.get(0)?;
+            Ok(match diagnose_run(row) {
+                Some((column, reason)) => QuarantinedRun {
+                    id,
+                    column: column.map(|column| RUN_COLUMN_NAMES[column]),
+                    value: column
+                        .map(|column| raw_value(row, column))
+                        .unwrap_or_default(),
+                    reason,
+                },
+                // Fixed outside the app since it was quarantined
+                None => QuarantinedRun {
+                    id,
+                    column: None,
+                    value: String::new(),
+                    reason: row.get(RUN_COLUMN_NAMES.len())?,
+                },
+            })
+        })?
+        .collect::<Result<Vec<_>, _>>()
+        .context("Failed to load quarantined runs")?;
+
+    Ok(runs)
+}
+
+/// Parses a corrected value for one column of a quarantined run.
+fn parse_column_value(column: &str, input: &str) -> Result<Value> {
+    let input = input.trim();
+    let text_or_null = |text: String| {
+        if text.is_empty() {
+            Value::Null
+        } else {
+            Value::Text(text)
+        }
+    };
+    let required = || {
+        if input.is_empty() {
+            bail!("Enter a value for {}", column);
+        }
+        Ok(input)
+    };
+    let timestamp = |input: &str| -> Result<String> {
+        chrono::DateTime::parse_from_rfc3339(input)
+            .map(|dt| dt.with_timezone(&chrono::Utc).to_rfc3339())
+            .map_err(|_| anyhow!("Use an RFC 3339 timestamp, e.g. 2024-05-01T07:30:00+00:00"))
+    };
+
+    let value = match column {
+        "date" => Value::Text(validation::parse_date(required()?)?.to_string()),
+        "time_started" => Value::Text(validation::parse_time(required()?)?.to_string()),
+        "distance_miles" => Value::Real(validation::parse_distance(
+            required()?,
+            DistanceUnit::Miles,
+        )?),
+        "duration_seconds" => validation::parse_duration(input)?
+            .map_or(Value::Null, |seconds| Value::Integer(seconds.into())),
+        "note" => text_or_null(input.to_string()),
+        "created_at" => Value::Text(timestamp(required()?)?),
+        "deleted_at" if input.is_empty() => Value::Null,
+        "deleted_at" => Value::Text(timestamp(input)?),
+        "run_type" if input.is_empty() => Value::Null,
+        "run_type" => {
+            let run_type = RunType::parse(&input.to_lowercase()).ok_or_else(|| {
+                anyhow!(
+                    "Type must be one of {}, or empty",
+                    RunType::ALL.map(RunType::as_str).join(", ")
+                )
+            })?;
+            Value::Text(run_type.as_str().to_string())
+        }
+        "gear_id" | "route_id" if input.is_empty() => Value::Null,
+        "gear_id" | "route_id" => Value::Integer(
+            input
+                .parse()
+                .map_err(|_| anyhow!("Enter an id number, or leave it empty"))?,
+        ),
+        "athlete_id" => Value::Integer(
+            required()?
+                .parse()
+                .map_err(|_| anyhow!("Enter the id number of an athlete"))?,
+        ),
+        "avg_heart_rate" | "max_heart_rate" => validation::parse_heart_rate(input)?
+            .map_or(Value::Null, |bpm| Value::Integer(bpm.into())),
+        "effort" => validation::parse_effort(input)?
+            .map_or(Value::Null, |effort| Value::Integer(effort.into())),
+        "utc_offset_seconds" => validation::parse_utc_offset(input)?
+            .map_or(Value::Null, |offset| {
+                Value::Integer(offset.local_minus_utc().into())
+            }),
+        // A run whose UUID was lost can be given a new one
+        "uuid" if input.is_empty() => Value::Text(Uuid::new_v4().to_string()),
+        "uuid" => Value::Text(
+            Uuid::parse_str(input)
+                .map_err(|_| anyhow!("Enter a UUID, or leave it empty for a new one"))?
+                .to_string(),
+        ),
+        _ => bail!("{} can't be edited", column),
+    };
+    Ok(value)
+}
+
+/// Replaces one value of a quarantined run. The run is released once its
+/// row reads cleanly; otherwise it stays quarantined with the next problem.
+pub fn fix_quarantined_run(conn: &Connection, id: i64, column: &str, input: &str) -> Result<()> {
+    let value = parse_column_value(column, input)?;
+
+    let tx = WriteTransaction::begin(conn)?;
+    let before = run_snapshot(&tx, id)?;
+    // `column` is one of the names accepted by `parse_column_value`, so it is
+    // safe to put in the statement
+    let updated = tx
+        .execute(
+            &format!(
+                "UPDATE runs SET {} = ?1 WHERE id = ?2 AND quarantine_reason IS NOT NULL",
+                column
+            ),
+            params![value, id],
+        )
+        .context(
+            "Failed to save the value (another run may already exist at that date and time)",
+        )?;
+    if updated == 0 {
+        bail!("Run is no longer quarantined");
+    }
+
+    let reason = tx.query_row(
+        &format!("SELECT {} FROM runs WHERE id = ?1", RUN_COLUMNS),
+        params![id],
+        |row| Ok(diagnose_run(row).map(|(column, reason)| describe_problem(column, &reason))),
+    )?;
+    tx.execute(
+        "UPDATE runs SET quarantine_reason = ?1 WHERE id = ?2",
+        params![reason, id],
+    )
+    .context("Failed to release run (another run may already exist at that date and time)")?;
+    record_history(&tx, id, HistoryAction::Update, before)?;
+    tx.commit()?;
+
+    Ok(())
+}
+
+/// Permanently deletes a quarantined run.
+pub fn delete_quarantined_run(conn: &Connection, id: i64) -> Result<()> {
+    let tx = WriteTransaction::begin(conn)?;
+    let before = run_snapshot(&tx, id)?;
+    let deleted = tx
+        .execute(
+            "DELETE FROM runs WHERE id = ?1 AND quarantine_reason IS NOT NULL",
+            params![id],
+        )
         .context("Failed to delete run")?;
+    if deleted == 0 {
+        bail!("Run is no longer quarantined");
+    }
+    record_history(&tx, id, HistoryAction::Delete, before)?;
+    delete_unused_tags(&tx)?;
+    tx.commit()?;
+    Ok(())
+}
+
+/// All gear with the distance and number of runs logged against each,
+/// counting only runs that are not in the trash.
+pub fn get_gear(conn: &Connection) -> Result<Vec<Gear>> {
+    let mut stmt = conn.prepare(
+        "SELECT g.id, g.name, g.retire_at_miles, g.retired,
+                COALESCE(SUM(r.distance_miles), 0), COUNT(r.id)
+         FROM gear g
+         LEFT JOIN runs r ON r.gear_id = g.id
+             AND r.deleted_at IS NULL AND r.quarantine_reason IS NULL
+         GROUP BY g.id
+         ORDER BY g.retired, g.name COLLATE NOCASE",
+    )?;
+
+    let gear = stmt
+        .query_map([], |row| {
+            Ok(Gear {
+                id: Some(row.get(0)?),
+                name: row.get(1)?,
+                retire_at_miles: row.get(2)?,
+                retired: row.get(3)?,
+                total_miles: row.get(4)?,
+                run_count: row.get(5)?,
+            })
+        })?
+        .collect::<Result<Vec<_>, _>>()
+        .context("Failed to load gear")?;
+
+    Ok(gear)
+}
+
+pub fn insert_gear(conn: &Connection, gear: &Gear) -> Result<i64> {
+    conn.execute(
+        "INSERT INTO gear (name, retire_at_miles, retired, created_at) VALUES (?1, ?2, ?3, ?4)",
+        params![
+            gear.name,
+            gear.retire_at_miles,
+            gear.retired,
+            chrono::Utc::now().to_rfc3339(),
+        ],
+    )
+    .context("Failed to add gear (the name may already be in use)")?;
+
+    Ok(conn.last_insert_rowid())
+}
+
+pub fn update_gear(conn: &Connection, gear: &Gear) -> Result<()> {
+    let id = gear.id.context("Gear must have an id to be updated")?;
+    conn.execute(
+        "UPDATE gear SET name = ?1, retire_at_miles = ?2, retired = ?3 WHERE id = ?4",
+        params![gear.name, gear.retire_at_miles, gear.retired, id],
+    )
+    .context("Failed to update gear (the name may already be in use)")?;
+    Ok(())
+}
+
+/// Deletes gear. Runs that used it are kept and simply lose their gear, which
+/// shows in their history like any other edit.
+pub fn delete_gear(conn: &Connection, id: i64) -> Result<()> {
+    let tx = WriteTransaction::begin(conn)?;
+    let run_ids = tx
+        .prepare("SELECT id FROM runs WHERE gear_id = ?1")?
+        .query_map(params![id], |row| row.get::<_, i64>(0))?
+        .collect::<Result<Vec<_>, _>>()?;
+    for run_id in run_ids {
+        let before = run_snapshot(&tx, run_id)?;
+        tx.execute(
+            "UPDATE runs SET gear_id = NULL WHERE id = ?1",
+            params![run_id],
+        )
+        .context("Failed to take the gear off its runs")?;
+        record_history(&tx, run_id, HistoryAction::Update, before)?;
+    }
+    tx.execute("DELETE FROM gear WHERE id = ?1", params![id])
+        .context("Failed to delete gear")?;
+    tx.commit()?;
+    Ok(())
+}
+
+/// All routes with how often they've been run and the best and average
+/// duration, counting only runs that are not in the trash.
+pub fn get_routes(conn: &Connection) -> Result<Vec<Route>> {
+    let mut stmt = conn.prepare(
+        "SELECT rt.id, rt.name, rt.distance_miles, rt.description,
+                COUNT(r.id), MIN(r.duration_seconds), AVG(r.duration_seconds)
+         FROM routes rt
+         LEFT JOIN runs r ON r.route_id = rt.id
+             AND r.deleted_at IS NULL AND r.quarantine_reason IS NULL
+         GROUP BY rt.id
+         ORDER BY rt.name COLLATE NOCASE",
+    )?;
+
+    let routes = stmt
+        .query_map([], |row| {
+            Ok(Route {
+                id: Some(row.get(0)?),
+                name: row.get(1)?,
+                distance_miles: row.get(2)?,
+                description: row.get(3)?,
+                run_count: row.get(4)?,
+                best_duration_seconds: row.get(5)?,
+                average_duration_seconds: row.get(6)?,
+            })
+        })?
+        .collect::<Result<Vec<_>, _>>()
+        .context("Failed to load routes")?;
+
+    Ok(routes)
+}
+
+pub fn insert_route(conn: &Connection, route: &Route) -> Result<i64> {
+    conn.execute(
+        "INSERT INTO routes (name, distance_miles, description, created_at) VALUES (?1, ?2, ?3, ?4)",
+        params![
+            route.name,
+            route.distance_miles,
+            route.description,
+            chrono::Utc::now().to_rfc3339(),
+        ],
+    )
+    .context("Failed to add route (the name may already be in use)")?;
+
+    Ok(conn.last_insert_rowid())
+}
+
+pub fn update_route(conn: &Connection, route: &Route) -> Result<()> {
+    let id = route.id.context("Route must have an id to be updated")?;
+    conn.execute(
+        "UPDATE routes SET name = ?1, distance_miles = ?2, description = ?3 WHERE id = ?4",
+        params![route.name, route.distance_miles, route.description, id],
+    )
+    .context("Failed to update route (the name may already be in use)")?;
+    Ok(())
+}
+
+/// Deletes a route. Runs on it are kept and simply lose their route, which
+/// shows in their history like any other edit.
+pub fn delete_route(conn: &Connection, id: i64) -> Result<()> {
+    let tx = WriteTransaction::begin(conn)?;
+    let run_ids = tx
+        .prepare("SELECT id FROM runs WHERE route_id = ?1")?
+        .query_map(params![id], |row| row.get::<_, i64>(0))?
+        .collect::<Result<Vec<_>, _>>()?;
+    for run_id in run_ids {
+        let before = run_snapshot(&tx, run_id)?;
+        tx.execute(
+            "UPDATE runs SET route_id = NULL WHERE id = ?1",
+            params![run_id],
+        )
+        .context("Failed to take the route off its runs")?;
+        record_history(&tx, run_id, HistoryAction::Update, before)?;
+    }
+    tx.execute("DELETE FROM routes WHERE id = ?1", params![id])
+        .context("Failed to delete route")?;
+    tx.commit()?;
+    Ok(())
+}
+
+/// All athletes with the number of runs each has logged, not counting runs
+/// in the trash.
+pub fn get_athletes(conn: &Connection) -> Result<Vec<Athlete>> {
+    let mut stmt = conn.prepare(
+        "SELECT a.id, a.name, COUNT(r.id)
+         FROM athletes a
+         LEFT JOIN runs r ON r.athlete_id = a.id
+             AND r.deleted_at IS NULL AND r.quarantine_reason IS NULL
+         GROUP BY a.id
+         ORDER BY a.id",
+    )?;
+
+    let athletes = stmt
+        .query_map([], |row| {
+            Ok(Athlete {
+                id: Some(row.get(0)?),
+                name: row.get(1)?,
+                run_count: row.get(2)?,
+            })
+        })?
+        .collect::<Result<Vec<_>, _>>()
+        .context("Failed to load athletes")?;
+
+    Ok(athletes)
+}
+
+pub fn insert_athlete(conn: &Connection, athlete: &Athlete) -> Result<i64> {
+    conn.execute(
+        "INSERT INTO athletes (name, created_at) VALUES (?1, ?2)",
+        params![athlete.name, chrono::Utc::now().to_rfc3339()],
+    )
+    .context("Failed to add athlete (the name may already be in use)")?;
+
+    Ok(conn.last_insert_rowid())
+}
+
+pub fn update_athlete(conn: &Connection, athlete: &Athlete) -> Result<()> {
+    let id = athlete
+        .id
+        .context("Athlete must have an id to be updated")?;
+    conn.execute(
+        "UPDATE athletes SET name = ?1 WHERE id = ?2",
+        params![athlete.name, id],
+    )
+    .context("Failed to rename athlete (the name may already be in use)")?;
+    Ok(())
+}
+
+pub fn get_splits(conn: &Connection, run_id: i64) -> Result<Vec<Split>> {
+    let mut stmt = conn.prepare(
+        "SELECT lap_number, distance_miles, duration_seconds
+         FROM splits
+         WHERE run_id = ?1
+         ORDER BY lap_number",
+    )?;
+
+    let splits = stmt
+        .query_map(params![run_id], |row| {
+            Ok(Split {
+                lap_number: row.get(0)?,
+                distance_miles: row.get(1)?,
+                duration_seconds: row.get(2)?,
+            })
+        })?
+        .collect::<Result<Vec<_>, _>>()
+        .context("Failed to load laps")?;
+
+    Ok(splits)
+}
+
+/// Replaces all of a run's laps.
+pub fn set_splits(conn: &Connection, run_id: i64, splits: &[Split]) -> Result<()> {
+    let tx = WriteTransaction::begin(conn)?;
+    tx.execute("DELETE FROM splits WHERE run_id = ?1", params![run_id])
+        .context("Failed to save laps")?;
+
+    for split in splits {
+        tx.execute(
+            "INSERT INTO splits (run_id, lap_number, distance_miles, duration_seconds)
+             VALUES (?1, ?2, ?3, ?4)",
+            params![
+                run_id,
+                split.lap_number,
+                split.distance_miles,
+                split.duration_seconds
+            ],
+        )
+        .context("Failed to save laps")?;
+    }
+
+    tx.commit()?;
+    Ok(())
+}
+
+/// Every day the athlete ran, oldest first. The table is kept up to date by
+/// triggers on `runs`, so this never has to scan the runs themselves.
+pub fn get_daily_totals(conn: &Connection, athlete_id: i64) -> Result<Vec<DailyTotal>> {
+    let mut stmt = conn.prepare(
+        "SELECT date, distance_miles, run_count FROM daily_totals
+         WHERE athlete_id = ?1
+         ORDER BY date",
+    )?;
+
+    let totals = stmt.query_map(params![athlete_id], |row| {
+        let date_str: String = row.get(0)?;
+        Ok(DailyTotal {
+            date: NaiveDate::parse_from_str(&date_str, "%Y-%m-%d")
+                .map_err(|_| invalid_value(0, "not a valid date (YYYY-MM-DD)"))?,
+            distance_miles: row.get(1)?,
+            run_count: row.get(2)?,
+        })
+    })?;
+    let totals = collect_readable(totals).context("Failed to load daily totals")?;
+
+    Ok(totals)
+}
+
+/// Dates whose stored total, for any athlete, doesn't match the runs logged
+/// on them, including days missing from the table and days that no longer
+/// have any runs.
+pub fn check_daily_totals(conn: &Connection) -> Result<Vec<String>> {
+    let mut stmt = conn.prepare(
+        "WITH actual AS (
+             SELECT athlete_id, date, SUM(distance_miles) AS distance_miles, COUNT(*) AS run_count
+             FROM runs
+             WHERE deleted_at IS NULL AND quarantine_reason IS NULL
+             GROUP BY athlete_id, date
+         )
+         SELECT a.date FROM actual a
+         LEFT JOIN daily_totals d ON d.athlete_id = a.athlete_id AND d.date = a.date
+         WHERE d.date IS NULL
+            OR d.run_count != a.run_count
+            OR ABS(d.distance_miles - a.distance_miles) > 1e-6
+         UNION
+         SELECT d.date FROM daily_totals d
+         WHERE NOT EXISTS (
+             SELECT 1 FROM actual a WHERE a.athlete_id = d.athlete_id AND a.date = d.date
+         )
+         ORDER BY 1",
+    )?;
+
+    let dates = stmt
+        .query_map([], |row| row.get(0))?
+        .collect::<Result<Vec<_>, _>>()
+        .context("Failed to check daily totals")?;
+
+    Ok(dates)
+}
+
+/// Recomputes the daily totals from scratch.
+pub fn rebuild_daily_totals(conn: &Connection) -> Result<()> {
+    let tx = WriteTransaction::begin(conn)?;
+    tx.execute("DELETE FROM daily_totals", [])?;
+    tx.execute(
+        "INSERT INTO daily_totals (athlete_id, date, distance_miles, run_count)
+         SELECT athlete_id, date, SUM(distance_miles), COUNT(*) FROM runs
+         WHERE deleted_at IS NULL AND quarantine_reason IS NULL
+         GROUP BY athlete_id, date",
+        [],
+    )
+    .context("Failed to rebuild daily totals")?;
+    tx.commit()?;
+    Ok(())
+}
+
+/// Run count and distance per tag for the athlete, largest distance first.
+pub fn get_tag_totals(conn: &Connection, athlete_id: i64) -> Result<Vec<TagTotal>> {
+    let mut stmt = conn.prepare(
+        "SELECT t.name, COUNT(*), SUM(r.distance_miles)
+         FROM run_tags rt
+         JOIN tags t ON t.id = rt.tag_id
+         JOIN runs r ON r.id = rt.run_id
+         WHERE r.athlete_id = ?1 AND r.deleted_at IS NULL AND r.quarantine_reason IS NULL
+         GROUP BY t.id
+         ORDER BY SUM(r.distance_miles) DESC, t.name COLLATE NOCASE",
+    )?;
+
+    let totals = stmt
+        .query_map(params![athlete_id], |row| {
+            Ok(TagTotal {
+                name: row.get(0)?,
+                run_count: row.get(1)?,
+                total_distance: row.get(2)?,
+            })
+        })?
+        .collect::<Result<Vec<_>, _>>()
+        .context("Failed to load tag totals")?;
+
+    Ok(totals)
+}
+
+pub fn get_setting(conn: &Connection, key: &str) -> Result<Option<String>> {
+    conn.query_row(
+        "SELECT value FROM settings WHERE key = ?1",
+        params![key],
+        |row| row.get(0),
+    )
+    .optional()
+    .context("Failed to read setting")
+}
+
+pub fn set_setting(conn: &Connection, key: &str, value: &str) -> Result<()> {
+    conn.execute(
+        "INSERT INTO settings (key, value) VALUES (?1, ?2)
+         ON CONFLICT(key) DO UPDATE SET value = excluded.value",
+        params![key, value],
+    )
+    .context("Failed to save setting")?;
     Ok(())
 }
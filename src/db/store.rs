@@ -0,0 +1,1224 @@
+use crate::db::queries;
+use crate::logic::search::SearchQuery;
+use crate::logic::streak;
+use crate::models::analytics::{DailyTotal, TagTotal};
+use crate::models::athlete::{Athlete, DEFAULT_ATHLETE_ID};
+use crate::models::gear::Gear;
+use crate::models::health::QuarantinedRun;
+use crate::models::history::{HistoryAction, RunVersion};
+use crate::models::route::Route;
+use crate::models::run::{Run, RunCursor};
+use crate::models::split::Split;
+use anyhow::{anyhow, bail, Context, Result};
+use chrono::{NaiveDate, NaiveTime, Utc};
+use rusqlite::Connection;
+use std::cell::RefCell;
+use std::collections::{BTreeMap, HashMap};
+
+/// Everything the UI needs to read and change runs. Screens and key handlers
+/// go through this trait rather than a `Connection` so they can run against
+/// either the SQLite database or a throwaway in-memory log.
+pub trait RunStore {
+    fn insert_run(&self, run: &Run) -> Result<i64>;
+    fn update_run(&self, run: &Run) -> Result<()>;
+    fn get_run(&self, id: i64) -> Result<Run>;
+    fn get_all_runs(&self, athlete_id: i64) -> Result<Vec<Run>>;
+    fn find_run_at(
+        &self,
+        athlete_id: i64,
+        date: NaiveDate,
+        time_started: NaiveTime,
+        ignore_id: Option<i64>,
+    ) -> Result<Option<Run>>;
+    fn get_runs_by_date_range(
+        &self,
+        athlete_id: i64,
+        start_date: NaiveDate,
+        end_date: NaiveDate,
+    ) -> Result<Vec<Run>>;
+    fn get_runs_page(
+        &self,
+        athlete_id: i64,
+        query: &SearchQuery,
+        after: Option<&RunCursor>,
+        limit: usize,
+    ) -> Result<Vec<Run>>;
+    fn count_runs(&self, athlete_id: i64, query: &SearchQuery) -> Result<usize>;
+    fn get_all_tags(&self) -> Result<Vec<String>>;
+    fn trash_run(&self, id: i64) -> Result<()>;
+    fn get_trashed_runs(&self, athlete_id: i64) -> Result<Vec<Run>>;
+    fn restore_run(&self, id: i64) -> Result<()>;
+    fn empty_trash(&self, athlete_id: i64) -> Result<usize>;
+    fn get_run_history(&self, run_id: i64) -> Result<Vec<RunVersion>>;
+    fn get_daily_totals(&self, athlete_id: i64) -> Result<Vec<DailyTotal>>;
+    fn get_tag_totals(&self, athlete_id: i64) -> Result<Vec<TagTotal>>;
+    fn get_splits(&self, run_id: i64) -> Result<Vec<Split>>;
+    fn set_splits(&self, run_id: i64, splits: &[Split]) -> Result<()>;
+    fn get_athletes(&self) -> Result<Vec<Athlete>>;
+    fn insert_athlete(&self, athlete: &Athlete) -> Result<i64>;
+    fn update_athlete(&self, athlete: &Athlete) -> Result<()>;
+    fn get_gear(&self) -> Result<Vec<Gear>>;
+    fn insert_gear(&self, gear: &Gear) -> Result<i64>;
+    fn update_gear(&self, gear: &Gear) -> Result<()>;
+    fn delete_gear(&self, id: i64) -> Result<()>;
+    fn get_routes(&self) -> Result<Vec<Route>>;
+    fn insert_route(&self, route: &Route) -> Result<i64>;
+    fn update_route(&self, route: &Route) -> Result<()>;
+    fn delete_route(&self, id: i64) -> Result<()>;
+    fn quarantine_bad_runs(&self) -> Result<usize>;
+    fn get_quarantined_runs(&self) -> Result<Vec<QuarantinedRun>>;
+    fn fix_quarantined_run(&self, id: i64, column: &str, value: &str) -> Result<()>;
+    fn delete_quarantined_run(&self, id: i64) -> Result<()>;
+    fn get_setting(&self, key: &str) -> Result<Option<String>>;
+    fn set_setting(&self, key: &str, value: &str) -> Result<()>;
+}
+
+pub struct SqliteStore {
+    conn: Connection,
+}
+
+impl SqliteStore {
+    pub fn new(conn: Connection) -> Self {
+        Self { conn }
+    }
+}
+
+impl RunStore for SqliteStore {
+    fn insert_run(&self, run: &Run) -> Result<i64> {
+        queries::insert_run(&self.conn, run)
+    }
+
+    fn update_run(&self, run: &Run) -> Result<()> {
+        queries::update_run(&self.conn, run)
+    }
+
+    fn get_run(&self, id: i64) -> Result<Run> {
+        queries::get_run(&self.conn, id)
+    }
+
+    fn get_all_runs(&self, athlete_id: i64) -> Result<Vec<Run>> {
+        queries::get_all_runs(&self.conn, athlete_id)
+    }
+
+    fn find_run_at(
+        &self,
+        athlete_id: i64,
+        date: NaiveDate,
+        time_started: NaiveTime,
+        ignore_id: Option<i64>,
+    ) -> Result<Option<Run>> {
+        queries::find_run_at(&self.conn, athlete_id, date, time_started, ignore_id)
+    }
+
+    fn get_runs_by_date_range(
+        &self,
+        athlete_id: i64,
+        start_date: NaiveDate,
+        end_date: NaiveDate,
+    ) -> Result<Vec<Run>> {
+        queries::get_runs_by_date_range(&self.conn, athlete_id, start_date, end_date)
+    }
+
+    fn get_runs_page(
+        &self,
+        athlete_id: i64,
+        query: &SearchQuery,
+        after: Option<&RunCursor>,
+        limit: usize,
+    ) -> Result<Vec<Run>> {
+        queries::get_runs_page(&self.conn, athlete_id, query, after, limit)
+    }
+
+    fn count_runs(&self, athlete_id: i64, query: &SearchQuery) -> Result<usize> {
+        queries::count_runs(&self.conn, athlete_id, query)
+    }
+
+    fn get_all_tags(&self) -> Result<Vec<String>> {
+        queries::get_all_tags(&self.conn)
+    }
+
+    fn trash_run(&self, id: i64) -> Result<()> {
+        queries::trash_run(&self.conn, id)
+    }
+
+    fn get_trashed_runs(&self, athlete_id: i64) -> Result<Vec<Run>> {
+        queries::get_trashed_runs(&self.conn, athlete_id)
+    }
+
+    fn restore_run(&self, id: i64) -> Result<()> {
+        queries::restore_run(&self.conn, id)
+    }
+
+    fn empty_trash(&self, athlete_id: i64) -> Result<usize> {
+        queries::empty_trash(&self.conn, athlete_id)
+    }
+
+    fn get_run_history(&self, run_id: i64) -> Result<Vec<RunVersion>> {
+        queries::get_run_history(&self.conn, run_id)
+    }
+
+    fn get_daily_totals(&self, athlete_id: i64) -> Result<Vec<DailyTotal>> {
+        queries::get_daily_totals(&self.conn, athlete_id)
+    }
+
+    fn get_tag_totals(&self, athlete_id: i64) -> Result<Vec<TagTotal>> {
+        queries::get_tag_totals(&self.conn, athlete_id)
+    }
+
+    fn get_splits(&self, run_id: i64) -> Result<Vec<Split>> {
+        queries::get_splits(&self.conn, run_id)
+    }
+
+    fn set_splits(&self, run_id: i64, splits: &[Split]) -> Result<()> {
+        queries::set_splits(&self.conn, run_id, splits)
+    }
+
+    fn get_athletes(&self) -> Result<Vec<Athlete>> {
+        queries::get_athletes(&self.conn)
+    }
+
+    fn insert_athlete(&self, athlete: &Athlete) -> Result<i64> {
+        queries::insert_athlete(&self.conn, athlete)
+    }
+
+    fn update_athlete(&self, athlete: &Athlete) -> Result<()> {
+        queries::update_athlete(&self.conn, athlete)
+    }
+
+    fn get_gear(&self) -> Result<Vec<Gear>> {
+        queries::get_gear(&self.conn)
+    }
+
+    fn insert_gear(&self, gear: &Gear) -> Result<i64> {
+        queries::insert_gear(&self.conn, gear)
+    }
+
+    fn update_gear(&self, gear: &Gear) -> Result<()> {
+        queries::update_gear(&self.conn, gear)
+    }
+
+    fn delete_gear(&self, id: i64) -> Result<()> {
+        queries::delete_gear(&self.conn, id)
+    }
+
+    fn get_routes(&self) -> Result<Vec<Route>> {
+        queries::get_routes(&self.conn)
+    }
+
+    fn insert_route(&self, route: &Route) -> Result<i64> {
+        queries::insert_route(&self.conn, route)
+    }
+
+    fn update_route(&self, route: &Route) -> Result<()> {
+        queries::update_route(&self.conn, route)
+    }
+
+    fn delete_route(&self, id: i64) -> Result<()> {
+        queries::delete_route(&self.conn, id)
+    }
+
+    fn quarantine_bad_runs(&self) -> Result<usize> {
+        queries::quarantine_bad_runs(&self.conn)
+    }
+
+    fn get_quarantined_runs(&self) -> Result<Vec<QuarantinedRun>> {
+        queries::get_quarantined_runs(&self.conn)
+    }
+
+    fn fix_quarantined_run(&self, id: i64, column: &str, value: &str) -> Result<()> {
+        queries::fix_quarantined_run(&self.conn, id, column, value)
+    }
+
+    fn delete_quarantined_run(&self, id: i64) -> Result<()> {
+        queries::delete_quarantined_run(&self.conn, id)
+    }
+
+    fn get_setting(&self, key: &str) -> Result<Option<String>> {
+        queries::get_setting(&self.conn, key)
+    }
+
+    fn set_setting(&self, key: &str, value: &str) -> Result<()> {
+        queries::set_setting(&self.conn, key, value)
+    }
+}
+
+#[derive(Default)]
+struct MemoryData {
+    runs: Vec<Run>,
+    next_id: i64,
+    athletes: Vec<Athlete>,
+    next_athlete_id: i64,
+    gear: Vec<Gear>,
+    next_gear_id: i64,
+    routes: Vec<Route>,
+    next_route_id: i64,
+    splits: HashMap<i64, Vec<Split>>,
+    settings: HashMap<String, String>,
+    history: Vec<RunVersion>,
+}
+
+impl MemoryData {
+    /// Mirrors the history SQLite records for each change to a run, taking
+    /// the run after the change from `runs`.
+    fn record_history(&mut self, run_id: i64, action: HistoryAction, before: Option<Run>) {
+        let after = self.runs.iter().find(|run| run.id == Some(run_id)).cloned();
+        if before == after {
+            return;
+        }
+
+        self.history.push(RunVersion {
+            run_id,
+            action,
+            changed_at: Utc::now(),
+            before,
+            after,
+        });
+    }
+
+    fn run_mut(&mut self, id: i64) -> Result<&mut Run> {
+        self.runs
+            .iter_mut()
+            .find(|run| run.id == Some(id))
+            .ok_or_else(|| anyhow!("Run no longer exists"))
+    }
+
+    /// Mirrors the SQLite unique constraint on athlete names.
+    fn check_athlete_name_free(&self, athlete: &Athlete) -> Result<()> {
+        let taken = self
+            .athletes
+            .iter()
+            .any(|other| other.id != athlete.id && other.name.eq_ignore_ascii_case(&athlete.name));
+        if taken {
+            bail!("Athlete named '{}' already exists", athlete.name);
+        }
+        Ok(())
+    }
+
+    /// Mirrors the SQLite unique constraint on gear names.
+    fn check_gear_name_free(&self, gear: &Gear) -> Result<()> {
+        let taken = self
+            .gear
+            .iter()
+            .any(|other| other.id != gear.id && other.name.eq_ignore_ascii_case(&gear.name));
+        if taken {
+            bail!("Gear named '{}' already exists", gear.name);
+        }
+        Ok(())
+    }
+
+    /// Mirrors the SQLite unique constraint on route names.
+    fn check_route_name_free(&self, route: &Route) -> Result<()> {
+        let taken = self
+            .routes
+            .iter()
+            .any(|other| other.id != route.id && other.name.eq_ignore_ascii_case(&route.name));
+        if taken {
+            bail!("Route named '{}' already exists", route.name);
+        }
+        Ok(())
+    }
+
+    /// Mirrors the SQLite unique index on (athlete_id, date, time_started)
+    /// for runs that are not in the trash.
+    fn check_slot_free(&self, run: &Run, ignore_id: Option<i64>) -> Result<()> {
+        let taken = self.runs.iter().any(|other| {
+            other.deleted_at.is_none()
+                && other.id != ignore_id
+                && other.athlete_id == run.athlete_id
+                && other.date == run.date
+                && other.time_started == run.time_started
+        });
+        if taken {
+            bail!("A run already exists at that date and time");
+        }
+        Ok(())
+    }
+}
+
+/// A `RunStore` that keeps everything in memory and is discarded when
+/// dropped. It behaves like `SqliteStore`, including trash and conflicts.
+pub struct MemoryStore {
+    data: RefCell<MemoryData>,
+}
+
+impl MemoryStore {
+    pub fn new() -> Self {
+        Self {
+            data: RefCell::new(MemoryData {
+                next_id: 1,
+                athletes: vec![Athlete {
+                    id: Some(DEFAULT_ATHLETE_ID),
+                    name: "Me".to_string(),
+                    run_count: 0,
+                }],
+                next_athlete_id: DEFAULT_ATHLETE_ID + 1,
+                next_gear_id: 1,
+                next_route_id: 1,
+                ..MemoryData::default()
+            }),
+        }
+    }
+}
+
+fn sort_newest_first(runs: &mut [Run]) {
+    runs.sort_by(|a, b| {
+        b.date
+            .cmp(&a.date)
+            .then_with(|| b.time_started.cmp(&a.time_started))
+            .then_with(|| b.id.cmp(&a.id))
+    });
+}
+
+impl RunStore for MemoryStore {
+    fn insert_run(&self, run: &Run) -> Result<i64> {
+        let mut data = self.data.borrow_mut();
+        data.check_slot_free(run, None)
+            .context("Failed to insert run")?;
+        if data.runs.iter().any(|other| other.uuid == run.uuid) {
+            bail!("Failed to insert run (a run with the same UUID already exists)");
+        }
+
+        let id = data.next_id;
+        data.next_id += 1;
+        data.runs.push(Run {
+            id: Some(id),
+            deleted_at: None,
+            ..run.clone()
+        });
+        data.record_history(id, HistoryAction::Insert, None);
+        Ok(id)
+    }
+
+    fn update_run(&self, run: &Run) -> Result<()> {
+        let id = run.id.context("Run must have an id to be updated")?;
+        let mut data = self.data.borrow_mut();
+        data.check_slot_free(run, Some(id))
+            .context("Failed to update run")?;
+
+        let existing = data.run_mut(id)?;
+        let before = existing.clone();
+        *existing = Run {
+            uuid: existing.uuid,
+            created_at: existing.created_at,
+            deleted_at: existing.deleted_at,
+            ..run.clone()
+        };
+        data.record_history(id, HistoryAction::Update, Some(before));
+        Ok(())
+    }
+
+    fn get_run(&self, id: i64) -> Result<Run> {
+        self.data.borrow_mut().run_mut(id).map(|run| run.clone())
+    }
+
+    fn get_all_runs(&self, athlete_id: i64) -> Result<Vec<Run>> {
+        let mut runs: Vec<Run> = self
+            .data
+            .borrow()
+            .runs
+            .iter()
+            .filter(|run| run.athlete_id == athlete_id && run.deleted_at.is_none())
+            .cloned()
+            .collect();
+        sort_newest_first(&mut runs);
+        Ok(runs)
+    }
+
+    fn find_run_at(
+        &self,
+        athlete_id: i64,
+        date: NaiveDate,
+        time_started: NaiveTime,
+        ignore_id: Option<i64>,
+    ) -> Result<Option<Run>> {
+        Ok(self
+            .data
+            .borrow()
+            .runs
+            .iter()
+            .find(|run| {
+                run.athlete_id == athlete_id
+                    && run.deleted_at.is_none()
+                    && run.id != ignore_id
+                    && run.date == date
+                    && run.time_started == time_started
+            })
+            .cloned())
+    }
+
+    fn get_runs_by_date_range(
+        &self,
+        athlete_id: i64,
+        start_date: NaiveDate,
+        end_date: NaiveDate,
+    ) -> Result<Vec<Run>> {
+        let mut runs = self.get_all_runs(athlete_id)?;
+        runs.retain(|run| run.date >= start_date && run.date <= end_date);
+        Ok(runs)
+    }
+
+    fn get_runs_page(
+        &self,
+        athlete_id: i64,
+        query: &SearchQuery,
+        after: Option<&RunCursor>,
+        limit: usize,
+    ) -> Result<Vec<Run>> {
+        let runs = self.get_all_runs(athlete_id)?;
+        Ok(runs
+            .into_iter()
+            .filter(|run| query.matches(run.note.as_deref(), &run.tags))
+            .filter(|run| {
+                after.is_none_or(|cursor| {
+                    (run.date, run.time_started, run.id)
+                        < (cursor.date, cursor.time_started, Some(cursor.id))
+                })
+            })
+            .take(limit)
+            .collect())
+    }
+
+    fn count_runs(&self, athlete_id: i64, query: &SearchQuery) -> Result<usize> {
+        let data = self.data.borrow();
+        Ok(data
+            .runs
+            .iter()
+            .filter(|run| run.athlete_id == athlete_id && run.deleted_at.is_none())
+            .filter(|run| query.matches(run.note.as_deref(), &run.tags))
+            .count())
+    }
+
+    fn get_all_tags(&self) -> Result<Vec<String>> {
+        let mut tags: Vec<String> = Vec::new();
+        for run in &self.data.borrow().runs {
+            for tag in &run.tags {
+                if !tags.iter().any(|t| t.eq_ignore_ascii_case(tag)) {
+                    tags.push(tag.clone());
+                }
+            }
+        }
+        tags.sort_by_key(|tag| tag.to_lowercase());
+        Ok(tags)
+    }
+
+    fn trash_run(&self, id: i64) -> Result<()> {
+        let mut data = self.data.borrow_mut();
+        let run = data.run_mut(id)?;
+        if run.deleted_at.is_some() {
+            bail!("Run no longer exists");
+        }
+        let before = run.clone();
+        run.deleted_at = Some(Utc::now());
+        data.record_history(id, HistoryAction::Trash, Some(before));
+        Ok(())
+    }
+
+    fn get_trashed_runs(&self, athlete_id: i64) -> Result<Vec<Run>> {
+        let mut runs: Vec<Run> = self
+            .data
+            .borrow()
+            .runs
+            .iter()
+            .filter(|run| run.athlete_id == athlete_id && run.deleted_at.is_some())
+            .cloned()
+            .collect();
+        runs.sort_by_key(|run| std::cmp::Reverse(run.deleted_at));
+        Ok(runs)
+    }
+
+    fn restore_run(&self, id: i64) -> Result<()> {
+        let mut data = self.data.borrow_mut();
+        let run = data.run_mut(id)?.clone();
+        if run.deleted_at.is_none() {
+            bail!("Run is no longer in the trash");
+        }
+        data.check_slot_free(&run, Some(id)).context(
+            "Failed to restore run (another run may already exist at that date and time)",
+        )?;
+        data.run_mut(id)?.deleted_at = None;
+        data.record_history(id, HistoryAction::Restore, Some(run));
+        Ok(())
+    }
+
+    fn get_run_history(&self, run_id: i64) -> Result<Vec<RunVersion>> {
+        let mut history: Vec<RunVersion> = self
+            .data
+            .borrow()
+            .history
+            .iter()
+            .filter(|version| version.run_id == run_id)
+            .cloned()
+            .collect();
+        history.reverse();
+        Ok(history)
+    }
+
+    fn empty_trash(&self, athlete_id: i64) -> Result<usize> {
+        let mut data = self.data.borrow_mut();
+        let before = data.runs.len();
+        let is_trashed = |run: &Run| run.athlete_id == athlete_id && run.deleted_at.is_some();
+        let trashed: Vec<Run> = data
+            .runs
+            .iter()
+            .filter(|run| is_trashed(run))
+            .cloned()
+            .collect();
+        data.runs.retain(|run| !is_trashed(run));
+        for run in trashed {
+            if let Some(id) = run.id {
+                data.record_history(id, HistoryAction::Delete, Some(run));
+            }
+        }
+        let MemoryData { runs, splits, .. } = &mut *data;
+        splits.retain(|run_id, _| runs.iter().any(|run| run.id == Some(*run_id)));
+        Ok(before - data.runs.len())
+    }
+
+    // Runs in memory are few enough to total on demand, so there's no table
+    // to keep in step
+    fn get_daily_totals(&self, athlete_id: i64) -> Result<Vec<DailyTotal>> {
+        let mut totals: BTreeMap<NaiveDate, DailyTotal> = BTreeMap::new();
+        for run in self.get_all_runs(athlete_id)? {
+            let total = totals.entry(run.date).or_insert(DailyTotal {
+                date: run.date,
+                distance_miles: 0.0,
+                run_count: 0,
+            });
+            total.distance_miles += run.distance_miles;
+            total.run_count += 1;
+        }
+        Ok(totals.into_values().collect())
+    }
+
+    fn get_tag_totals(&self, athlete_id: i64) -> Result<Vec<TagTotal>> {
+        Ok(streak::calculate_tag_totals(
+            &self.get_all_runs(athlete_id)?,
+        ))
+    }
+
+    fn get_splits(&self, run_id: i64) -> Result<Vec<Split>> {
+        Ok(self
+            .data
+            .borrow()
+            .splits
+            .get(&run_id)
+            .cloned()
+            .unwrap_or_default())
+    }
+
+    fn set_splits(&self, run_id: i64, splits: &[Split]) -> Result<()> {
+        let mut data = self.data.borrow_mut();
+        data.run_mut(run_id)?;
+        data.splits.insert(run_id, splits.to_vec());
+        Ok(())
+    }
+
+    fn get_athletes(&self) -> Result<Vec<Athlete>> {
+        let data = self.data.borrow();
+        Ok(data
+            .athletes
+            .iter()
+            .map(|athlete| Athlete {
+                run_count: data
+                    .runs
+                    .iter()
+                    .filter(|run| run.deleted_at.is_none() && Some(run.athlete_id) == athlete.id)
+                    .count() as u32,
+                ..athlete.clone()
+            })
+            .collect())
+    }
+
+    fn insert_athlete(&self, athlete: &Athlete) -> Result<i64> {
+        let mut data = self.data.borrow_mut();
+        data.check_athlete_name_free(athlete)
+            .context("Failed to add athlete")?;
+
+        let id = data.next_athlete_id;
+        data.next_athlete_id += 1;
+        data.athletes.push(Athlete {
+            id: Some(id),
+            ..athlete.clone()
+        });
+        Ok(id)
+    }
+
+    fn update_athlete(&self, athlete: &Athlete) -> Result<()> {
+        let mut data = self.data.borrow_mut();
+        data.check_athlete_name_free(athlete)
+            .context("Failed to rename athlete")?;
+
+        let existing = data
+            .athletes
+            .iter_mut()
+            .find(|item| item.id.is_some() && item.id == athlete.id)
+            .ok_or_else(|| anyhow!("Athlete no longer exists"))?;
+        existing.name = athlete.name.clone();
+        Ok(())
+    }
+
+    fn get_gear(&self) -> Result<Vec<Gear>> {
+        let data = self.data.borrow();
+        let mut gear: Vec<Gear> = data
+            .gear
+            .iter()
+            .map(|item| {
+                let runs = data
+                    .runs
+                    .iter()
+                    .filter(|run| run.deleted_at.is_none() && run.gear_id == item.id);
+                let (total_miles, run_count) = runs.fold((0.0, 0), |(miles, count), run| {
+                    (miles + run.distance_miles, count + 1)
+                });
+                Gear {
+                    total_miles,
+                    run_count,
+                    ..item.clone()
+                }
+            })
+            .collect();
+        gear.sort_by_key(|item| (item.retired, item.name.to_lowercase()));
+        Ok(gear)
+    }
+
+    fn insert_gear(&self, gear: &Gear) -> Result<i64> {
+        let mut data = self.data.borrow_mut();
+        data.check_gear_name_free(gear)
+            .context("Failed to add gear")?;
+
+        let id = data.next_gear_id;
+        data.next_gear_id += 1;
+        data.gear.push(Gear {
+            id: Some(id),
+            ..gear.clone()
+        });
+        Ok(id)
+    }
+
+    fn update_gear(&self, gear: &Gear) -> Result<()> {
+        let mut data = self.data.borrow_mut();
+        data.check_gear_name_free(gear)
+            .context("Failed to update gear")?;
+
+        let existing = data
+            .gear
+            .iter_mut()
+            .find(|item| item.id.is_some() && item.id == gear.id)
+            .ok_or_else(|| anyhow!("Gear no longer exists"))?;
+        *existing = gear.clone();
+        Ok(())
+    }
+
+    fn delete_gear(&self, id: i64) -> Result<()> {
+        let mut data = self.data.borrow_mut();
+        data.gear.retain(|item| item.id != Some(id));
+        let used: Vec<Run> = data
+            .runs
+            .iter()
+            .filter(|run| run.gear_id == Some(id))
+            .cloned()
+            .collect();
+        for before in used {
+            let run_id = before.id.context("Run has no id")?;
+            data.run_mut(run_id)?.gear_id = None;
+            data.record_history(run_id, HistoryAction::Update, Some(before));
+        }
+        Ok(())
+    }
+
+    fn get_routes(&self) -> Result<Vec<Route>> {
+        let data = self.data.borrow();
+        let mut routes: Vec<Route> = data
+            .routes
+            .iter()
+            .map(|route| {
+                let runs: Vec<&Run> = data
+                    .runs
+                    .iter()
+                    .filter(|run| run.deleted_at.is_none() && run.route_id == route.id)
+                    .collect();
+                let durations: Vec<u32> =
+                    runs.iter().filter_map(|run| run.duration_seconds).collect();
+                let average_duration_seconds = if durations.is_empty() {
+                    None
+                } else {
+                    Some(durations.iter().sum::<u32>() as f64 / durations.len() as f64)
+                };
+                Route {
+                    run_count: runs.len() as u32,
+                    best_duration_seconds: durations.iter().min().copied(),
+                    average_duration_seconds,
+                    ..route.clone()
+                }
+            })
+            .collect();
+        routes.sort_by_key(|route| route.name.to_lowercase());
+        Ok(routes)
+    }
+
+    fn insert_route(&self, route: &Route) -> Result<i64> {
+        let mut data = self.data.borrow_mut();
+        data.check_route_name_free(route)
+            .context("Failed to add route")?;
+
+        let id = data.next_route_id;
+        data.next_route_id += 1;
+        data.routes.push(Route {
+            id: Some(id),
+            ..route.clone()
+        });
+        Ok(id)
+    }
+
+    fn update_route(&self, route: &Route) -> Result<()> {
+        let mut data = self.data.borrow_mut();
+        data.check_route_name_free(route)
+            .context("Failed to update route")?;
+
+        let existing = data
+            .routes
+            .iter_mut()
+            .find(|item| item.id.is_some() && item.id == route.id)
+            .ok_or_else(|| anyhow!("Route no longer exists"))?;
+        *existing = route.clone();
+        Ok(())
+    }
+
+    fn delete_route(&self, id: i64) -> Result<()> {
+        let mut data = self.data.borrow_mut();
+        data.routes.retain(|route| route.id != Some(id));
+        let used: Vec<Run> = data
+            .runs
+            .iter()
+            .filter(|run| run.route_id == Some(id))
+            .cloned()
+            .collect();
+        for before in used {
+            let run_id = before.id.context("Run has no id")?;
+            data.run_mut(run_id)?.route_id = None;
+            data.record_history(run_id, HistoryAction::Update, Some(before));
+        }
+        Ok(())
+    }
+
+    // Runs in memory are always well-formed, so nothing is ever quarantined
+    fn quarantine_bad_runs(&self) -> Result<usize> {
+        Ok(0)
+    }
+
+    fn get_quarantined_runs(&self) -> Result<Vec<QuarantinedRun>> {
+        Ok(Vec::new())
+    }
+
+    fn fix_quarantined_run(&self, _id: i64, _column: &str, _value: &str) -> Result<()> {
+        bail!("Run is no longer quarantined")
+    }
+
+    fn delete_quarantined_run(&self, _id: i64) -> Result<()> {
+        bail!("Run is no longer quarantined")
+    }
+
+    fn get_setting(&self, key: &str) -> Result<Option<String>> {
+        Ok(self.data.borrow().settings.get(key).cloned())
+    }
+
+    fn set_setting(&self, key: &str, value: &str) -> Result<()> {
+        self.data
+            .borrow_mut()
+            .settings
+            .insert(key.to_string(), value.to_string());
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::migrations;
+    use crate::models::run::RunType;
+    use chrono::{Datelike, FixedOffset};
+
+    /// A SQLite store and a memory store, named for assertion messages, so
+    /// every test checks that the two behave the same.
+    fn stores() -> Vec<(&'static str, Box<dyn RunStore>)> {
+        let mut conn = Connection::open_in_memory().unwrap();
+        conn.pragma_update(None, "foreign_keys", true).unwrap();
+        migrations::init_database(&mut conn).unwrap();
+        vec![
+            ("sqlite", Box::new(SqliteStore::new(conn))),
+            ("memory", Box::new(MemoryStore::new())),
+        ]
+    }
+
+    fn run_on(day: u32) -> Run {
+        let date = NaiveDate::from_ymd_opt(2024, 5, day).unwrap();
+        let time = NaiveTime::from_hms_opt(7, 0, 0).unwrap();
+        Run::new(date, time, 5.0, Some(2400), None).unwrap()
+    }
+
+    #[test]
+    fn deleting_gear_takes_it_off_its_runs_with_a_history_entry() {
+        for (name, store) in stores() {
+            let gear_id = store
+                .insert_gear(&Gear::new("Pegasus", 400.0).unwrap())
+                .unwrap();
+            let run_id = store
+                .insert_run(&Run {
+                    gear_id: Some(gear_id),
+                    ..run_on(1)
+                })
+                .unwrap();
+
+            store.delete_gear(gear_id).unwrap();
+            assert!(store.get_gear().unwrap().is_empty(), "{}", name);
+            assert_eq!(store.get_run(run_id).unwrap().gear_id, None, "{}", name);
+            let history = store.get_run_history(run_id).unwrap();
+            assert_eq!(history.len(), 2, "{}", name);
+            assert_eq!(history[0].action, HistoryAction::Update, "{}", name);
+            assert_eq!(
+                history[0].before.as_ref().and_then(|run| run.gear_id),
+                Some(gear_id),
+                "{}",
+                name
+            );
+            assert_eq!(
+                history[0].after.as_ref().map(|run| run.gear_id),
+                Some(None),
+                "{}",
+                name
+            );
+        }
+    }
+
+    #[test]
+    fn deleting_a_route_takes_it_off_its_runs_with_a_history_entry() {
+        for (name, store) in stores() {
+            let route = Route::new("Park loop", 3.1, None).unwrap();
+            let route_id = store.insert_route(&route).unwrap();
+            let run_id = store
+                .insert_run(&Run {
+                    route_id: Some(route_id),
+                    ..run_on(1)
+                })
+                .unwrap();
+
+            store.delete_route(route_id).unwrap();
+            assert!(store.get_routes().unwrap().is_empty(), "{}", name);
+            assert_eq!(store.get_run(run_id).unwrap().route_id, None, "{}", name);
+            let history = store.get_run_history(run_id).unwrap();
+            assert_eq!(history.len(), 2, "{}", name);
+            assert_eq!(
+                history[0].before.as_ref().and_then(|run| run.route_id),
+                Some(route_id),
+                "{}",
+                name
+            );
+        }
+    }
+
+    fn run_at(day: u32, hour: u32) -> Run {
+        Run {
+            time_started: NaiveTime::from_hms_opt(hour, 0, 0).unwrap(),
+            ..run_on(day)
+        }
+    }
+
+    fn ids(runs: &[Run]) -> Vec<i64> {
+        runs.iter().filter_map(|run| run.id).collect()
+    }
+
+    #[test]
+    fn run_details_are_saved_and_loaded() {
+        for (name, store) in stores() {
+            let run = Run {
+                note: Some("Felt strong".to_string()),
+                run_type: Some(RunType::Tempo),
+                avg_heart_rate: Some(152),
+                max_heart_rate: Some(171),
+                effort: Some(7),
+                tags: vec!["hills".to_string(), "Race".to_string()],
+                utc_offset: FixedOffset::east_opt(2 * 3600),
+                ..run_on(1)
+            };
+            let id = store.insert_run(&run).unwrap();
+
+            let saved = store.get_run(id).unwrap();
+            assert_eq!(saved.uuid, run.uuid, "{}", name);
+            assert_eq!(saved.note, run.note, "{}", name);
+            assert_eq!(saved.run_type, Some(RunType::Tempo), "{}", name);
+            assert_eq!(saved.avg_heart_rate, Some(152), "{}", name);
+            assert_eq!(saved.max_heart_rate, Some(171), "{}", name);
+            assert_eq!(saved.effort, Some(7), "{}", name);
+            assert_eq!(saved.tags, run.tags, "{}", name);
+            assert_eq!(saved.utc_offset, run.utc_offset, "{}", name);
+
+            store
+                .update_run(&Run {
+                    run_type: None,
+                    avg_heart_rate: None,
+                    max_heart_rate: None,
+                    effort: None,
+                    tags: vec!["hills".to_string()],
+                    ..saved
+                })
+                .unwrap();
+            let updated = store.get_run(id).unwrap();
+            assert_eq!(updated.run_type, None, "{}", name);
+            assert_eq!(updated.avg_heart_rate, None, "{}", name);
+            assert_eq!(updated.effort, None, "{}", name);
+            assert_eq!(updated.tags, vec!["hills".to_string()], "{}", name);
+        }
+    }
+
+    #[test]
+    fn search_matches_note_prefixes_and_tags() {
+        for (name, store) in stores() {
+            let river = store
+                .insert_run(&Run {
+                    note: Some("Easy loop by the river".to_string()),
+                    ..run_on(1)
+                })
+                .unwrap();
+            let hills = store
+                .insert_run(&Run {
+                    note: Some("Hill repeats, legs heavy".to_string()),
+                    tags: vec!["hills".to_string()],
+                    ..run_on(2)
+                })
+                .unwrap();
+            let trashed = store
+                .insert_run(&Run {
+                    note: Some("River again".to_string()),
+                    ..run_on(3)
+                })
+                .unwrap();
+            store.trash_run(trashed).unwrap();
+
+            let search = |query: &str| {
+                let query = SearchQuery::parse(query);
+                let runs = store
+                    .get_runs_page(DEFAULT_ATHLETE_ID, &query, None, 10)
+                    .unwrap();
+                assert_eq!(
+                    store.count_runs(DEFAULT_ATHLETE_ID, &query).unwrap(),
+                    runs.len(),
+                    "{}",
+                    name
+                );
+                ids(&runs)
+            };
+            assert_eq!(search("riv"), vec![river], "{}", name);
+            assert_eq!(search("LOOP easy"), vec![river], "{}", name);
+            assert_eq!(search("#HILLS"), vec![hills], "{}", name);
+            assert_eq!(search("#hills heavy"), vec![hills], "{}", name);
+            assert_eq!(search("#hills river"), Vec::<i64>::new(), "{}", name);
+            assert_eq!(search(""), vec![hills, river], "{}", name);
+        }
+    }
+
+    #[test]
+    fn tags_are_listed_once_and_totalled_per_tag() {
+        for (name, store) in stores() {
+            let tagged = |day, distance, tags: &[&str]| Run {
+                distance_miles: distance,
+                tags: tags.iter().map(|tag| tag.to_string()).collect(),
+                ..run_on(day)
+            };
+            store.insert_run(&tagged(1, 3.0, &["hills"])).unwrap();
+            store
+                .insert_run(&tagged(2, 6.0, &["Race", "hills"]))
+                .unwrap();
+            let trashed = store.insert_run(&tagged(3, 9.0, &["track"])).unwrap();
+            store.trash_run(trashed).unwrap();
+
+            assert_eq!(
+                store.get_all_tags().unwrap(),
+                vec!["hills", "Race", "track"],
+                "{}",
+                name
+            );
+            let totals: Vec<(String, u32, f64)> = store
+                .get_tag_totals(DEFAULT_ATHLETE_ID)
+                .unwrap()
+                .into_iter()
+                .map(|total| (total.name, total.run_count, total.total_distance))
+                .collect();
+            assert_eq!(
+                totals,
+                vec![("hills".to_string(), 2, 9.0), ("Race".to_string(), 1, 6.0)],
+                "{}",
+                name
+            );
+        }
+    }
+
+    #[test]
+    fn routes_report_times_from_runs_not_in_the_trash() {
+        for (name, store) in stores() {
+            let loop_id = store
+                .insert_route(&Route::new("Park loop", 3.1, None).unwrap())
+                .unwrap();
+            store
+                .insert_route(&Route::new("Canal", 5.0, None).unwrap())
+                .unwrap();
+            let on_route = |day, duration| Run {
+                route_id: Some(loop_id),
+                duration_seconds: duration,
+                ..run_on(day)
+            };
+            store.insert_run(&on_route(1, Some(1500))).unwrap();
+            store.insert_run(&on_route(2, Some(1700))).unwrap();
+            store.insert_run(&on_route(3, None)).unwrap();
+            let trashed = store.insert_run(&on_route(4, Some(1000))).unwrap();
+            store.trash_run(trashed).unwrap();
+
+            let routes = store.get_routes().unwrap();
+            let names: Vec<&str> = routes.iter().map(|route| route.name.as_str()).collect();
+            assert_eq!(names, vec!["Canal", "Park loop"], "{}", name);
+            assert_eq!(routes[0].run_count, 0, "{}", name);
+            assert_eq!(routes[0].best_duration_seconds, None, "{}", name);
+            assert_eq!(routes[0].average_duration_seconds, None, "{}", name);
+            assert_eq!(routes[1].run_count, 3, "{}", name);
+            assert_eq!(routes[1].best_duration_seconds, Some(1500), "{}", name);
+            assert_eq!(routes[1].average_duration_seconds, Some(1600.0), "{}", name);
+        }
+    }
+
+    #[test]
+    fn setting_splits_replaces_the_previous_laps() {
+        for (name, store) in stores() {
+            let id = store.insert_run(&run_on(1)).unwrap();
+            assert!(store.get_splits(id).unwrap().is_empty(), "{}", name);
+
+            let lap = |lap_number, duration_seconds| Split {
+                lap_number,
+                distance_miles: 1.0,
+                duration_seconds,
+            };
+            store.set_splits(id, &[lap(1, 480), lap(2, 470)]).unwrap();
+            store.set_splits(id, &[lap(1, 500)]).unwrap();
+
+            let splits = store.get_splits(id).unwrap();
+            assert_eq!(splits.len(), 1, "{}", name);
+            assert_eq!(splits[0].duration_seconds, 500, "{}", name);
+            assert!(
+                store.set_splits(id + 1, &[lap(1, 500)]).is_err(),
+                "{}",
+                name
+            );
+        }
+    }
+
+    #[test]
+    fn pages_carry_on_after_the_cursor() {
+        for (name, store) in stores() {
+            let mut logged = Vec::new();
+            for (day, hour) in [(1, 7), (2, 7), (2, 18), (3, 7), (4, 7)] {
+                logged.push(store.insert_run(&run_at(day, hour)).unwrap());
+            }
+            let query = SearchQuery::default();
+
+            let mut pages = Vec::new();
+            let mut after = None;
+            loop {
+                let page = store
+                    .get_runs_page(DEFAULT_ATHLETE_ID, &query, after.as_ref(), 2)
+                    .unwrap();
+                let Some(last) = page.last() else {
+                    break;
+                };
+                after = last.cursor();
+                pages.push(ids(&page));
+            }
+
+            logged.reverse();
+            assert_eq!(
+                pages,
+                vec![
+                    logged[0..2].to_vec(),
+                    logged[2..4].to_vec(),
+                    logged[4..].to_vec()
+                ],
+                "{}",
+                name
+            );
+            assert_eq!(
+                store.count_runs(DEFAULT_ATHLETE_ID, &query).unwrap(),
+                5,
+                "{}",
+                name
+            );
+        }
+    }
+
+    #[test]
+    fn daily_totals_follow_edits_and_the_trash() {
+        for (name, store) in stores() {
+            let morning = store.insert_run(&run_at(1, 7)).unwrap();
+            store.insert_run(&run_at(1, 18)).unwrap();
+            let next_day = store.insert_run(&run_at(2, 7)).unwrap();
+            store
+                .update_run(&Run {
+                    distance_miles: 8.0,
+                    ..store.get_run(morning).unwrap()
+                })
+                .unwrap();
+            store.trash_run(next_day).unwrap();
+
+            let totals: Vec<(u32, f64, u32)> = store
+                .get_daily_totals(DEFAULT_ATHLETE_ID)
+                .unwrap()
+                .into_iter()
+                .map(|total| (total.date.day(), total.distance_miles, total.run_count))
+                .collect();
+            assert_eq!(totals, vec![(1, 13.0, 2)], "{}", name);
+
+            store.restore_run(next_day).unwrap();
+            let days = store.get_daily_totals(DEFAULT_ATHLETE_ID).unwrap().len();
+            assert_eq!(days, 2, "{}", name);
+        }
+    }
+
+    #[test]
+    fn history_lists_every_change_newest_first() {
+        for (name, store) in stores() {
+            let id = store.insert_run(&run_on(1)).unwrap();
+            store
+                .update_run(&Run {
+                    distance_miles: 6.0,
+                    ..store.get_run(id).unwrap()
+                })
+                .unwrap();
+            store.trash_run(id).unwrap();
+            store.restore_run(id).unwrap();
+
+            let history = store.get_run_history(id).unwrap();
+            let actions: Vec<HistoryAction> =
+                history.iter().map(|version| version.action).collect();
+            assert_eq!(
+                actions,
+                vec![
+                    HistoryAction::Restore,
+                    HistoryAction::Trash,
+                    HistoryAction::Update,
+                    HistoryAction::Insert
+                ],
+                "{}",
+                name
+            );
+            let miles = |run: &Option<Run>| run.as_ref().map(|run| run.distance_miles);
+            assert_eq!(miles(&history[2].before), Some(5.0), "{}", name);
+            assert_eq!(miles(&history[2].after), Some(6.0), "{}", name);
+            assert_eq!(miles(&history[3].before), None, "{}", name);
+            assert_eq!(
+                history[1]
+                    .after
+                    .as_ref()
+                    .map(|run| run.deleted_at.is_some()),
+                Some(true),
+                "{}",
+                name
+            );
+        }
+    }
+}
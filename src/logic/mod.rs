@@ -1,2 +1,4 @@
+pub mod search;
 pub mod streak;
+pub mod undo;
 pub mod validation;
@@ -0,0 +1,107 @@
+/// A parsed `/` search: free-text terms matched against notes, plus
+/// `#tag` filters that a run must carry.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SearchQuery {
+    pub terms: Vec<String>,
+    pub tags: Vec<String>,
+}
+
+impl SearchQuery {
+    pub fn parse(query: &str) -> Self {
+        let mut terms = Vec::new();
+        let mut tags = Vec::new();
+
+        for token in query.split_whitespace() {
+            match token.strip_prefix('#') {
+                Some(tag) if !tag.is_empty() => tags.push(tag.to_string()),
+                Some(_) => {}
+                None => terms.extend(search_terms(token)),
+            }
+        }
+
+        Self { terms, tags }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.terms.is_empty() && self.tags.is_empty()
+    }
+
+    /// Renders the query back into the form it was typed in.
+    pub fn to_input(&self) -> String {
+        self.tags
+            .iter()
+            .map(|tag| format!("#{}", tag))
+            .chain(self.terms.iter().cloned())
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    /// True if the run's note and tags satisfy the query.
+    pub fn matches(&self, note: Option<&str>, run_tags: &[String]) -> bool {
+        let terms_match =
+            self.terms.is_empty() || note.is_some_and(|note| matches_all(note, &self.terms));
+        let tags_match = self
+            .tags
+            .iter()
+            .all(|tag| run_tags.iter().any(|t| t.eq_ignore_ascii_case(tag)));
+        terms_match && tags_match
+    }
+}
+
+/// Splits a search query into lowercase terms the same way SQLite's default
+/// FTS5 tokenizer splits notes, so highlighting agrees with what matched.
+pub fn search_terms(query: &str) -> Vec<String> {
+    words(query).map(|(_, word)| word.to_lowercase()).collect()
+}
+
+/// Builds an FTS5 MATCH expression that requires every term, each as a
+/// prefix. Terms are quoted so user input can't inject FTS5 syntax.
+pub fn fts_query(terms: &[String]) -> String {
+    terms
+        .iter()
+        .map(|term| format!("\"{}\"*", term.replace('"', "\"\"")))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Byte ranges of the words in `text` that start with any of the terms.
+pub fn match_ranges(text: &str, terms: &[String]) -> Vec<(usize, usize)> {
+    words(text)
+        .filter(|(_, word)| {
+            let word = word.to_lowercase();
+            terms.iter().any(|term| word.starts_with(term.as_str()))
+        })
+        .map(|(start, word)| (start, start + word.len()))
+        .collect()
+}
+
+/// True if every term prefixes some word in `text`.
+pub fn matches_all(text: &str, terms: &[String]) -> bool {
+    let text_words: Vec<String> = words(text).map(|(_, word)| word.to_lowercase()).collect();
+    terms.iter().all(|term| {
+        text_words
+            .iter()
+            .any(|word| word.starts_with(term.as_str()))
+    })
+}
+
+fn words(text: &str) -> impl Iterator<Item = (usize, &str)> {
+    let mut words = Vec::new();
+    let mut start = None;
+
+    for (i, c) in text.char_indices() {
+        match (c.is_alphanumeric(), start) {
+            (true, None) => start = Some(i),
+            (false, Some(s)) => {
+                words.push((s, &text[s..i]));
+                start = None;
+            }
+            _ => {}
+        }
+    }
+    if let Some(s) = start {
+        words.push((s, &text[s..]));
+    }
+
+    words.into_iter()
+}
@@ -1,76 +1,85 @@
-use crate::models::analytics::{Analytics, DailyData, MonthlyData};
-use crate::models::run::Run;
-use chrono::{Datelike, Local, NaiveDate};
+use crate::models::analytics::{
+    Analytics, DailyData, DailyTotal, MonthlyData, TagTotal, TypeTotal, WeeklyData,
+};
+use crate::models::run::{Run, RunType};
+use chrono::{Datelike, Local, NaiveDate, Utc};
 use std::collections::BTreeMap;
 
-const DAILY_GOAL_MILES: f64 = 1.0;
+pub const DAILY_GOAL_MILES: f64 = 1.0;
 const MONTHS_TO_SHOW: usize = 12;
+const WEEKS_TO_SHOW: i64 = 8;
 
-pub fn calculate_analytics(runs: &[Run]) -> Analytics {
-    if runs.is_empty() {
+/// The earliest date whose runs `calculate_analytics` needs individually,
+/// for pace, run types and weekly trends. Everything older is only read
+/// through the daily totals.
+pub fn detail_window_start(today: NaiveDate) -> NaiveDate {
+    let month_start = today - chrono::Duration::days(29);
+    let year_start = NaiveDate::from_ymd_opt(today.year(), 1, 1).unwrap();
+    let oldest_week = today
+        - chrono::Duration::days(today.weekday().num_days_from_monday() as i64)
+        - chrono::Duration::weeks(WEEKS_TO_SHOW - 1);
+    month_start.min(year_start).min(oldest_week)
+}
+
+/// Today's date where the runner is: in the zone of their latest run, so a
+/// streak kept while travelling isn't cut short by this computer's clock.
+/// Without any runs it is today in this computer's zone.
+pub fn today(latest_run: Option<&Run>) -> NaiveDate {
+    match latest_run {
+        Some(run) => Utc::now().with_timezone(&run.offset()).date_naive(),
+        None => Local::now().date_naive(),
+    }
+}
+
+/// Builds the analytics from the stored daily totals, plus the runs since
+/// `detail_window_start` for the figures that need more than a day's total.
+/// Each run's date is the day in the zone it was run in, and `today` comes
+/// from `today`.
+pub fn calculate_analytics(
+    daily_totals: &[DailyTotal],
+    recent_runs: &[Run],
+    tag_totals: Vec<TagTotal>,
+    today: NaiveDate,
+) -> Analytics {
+    if daily_totals.is_empty() {
         return Analytics::empty();
     }
 
-    let daily_totals = group_by_date(runs);
-    let current_streak = calculate_current_streak(&daily_totals);
-    let longest_streak = calculate_longest_streak(&daily_totals);
+    let distance_by_date = distance_by_date(daily_totals);
+    let current_streak = calculate_current_streak(&distance_by_date, today);
+    let longest_streak = calculate_longest_streak(&distance_by_date);
 
-    let total_runs = runs.len() as u32;
-    let total_distance: f64 = runs.iter().map(|r| r.distance_miles).sum();
+    let total_runs: u32 = daily_totals.iter().map(|day| day.run_count).sum();
+    let total_distance: f64 = daily_totals.iter().map(|day| day.distance_miles).sum();
     let average_distance = if total_runs > 0 {
         total_distance / total_runs as f64
     } else {
         0.0
     };
 
-    let today = Local::now().naive_local().date();
     let week_start = today - chrono::Duration::days(6); // Last 7 days including today
     let month_start = today - chrono::Duration::days(29); // Last 30 days including today
     let year_start = NaiveDate::from_ymd_opt(today.year(), 1, 1).unwrap();
 
-    let runs_this_week = runs.iter().filter(|r| r.date >= week_start).count() as u32;
-    let runs_this_month = runs.iter().filter(|r| r.date >= month_start).count() as u32;
-    let runs_this_year = runs.iter().filter(|r| r.date >= year_start).count() as u32;
-
-    // Calculate average distances for different time periods
-    let distance_this_week: f64 = runs
-        .iter()
-        .filter(|r| r.date >= week_start)
-        .map(|r| r.distance_miles)
-        .sum();
-    let average_distance_this_week = if runs_this_week > 0 {
-        distance_this_week / runs_this_week as f64
-    } else {
-        0.0
-    };
-
-    let distance_this_month: f64 = runs
-        .iter()
-        .filter(|r| r.date >= month_start)
-        .map(|r| r.distance_miles)
-        .sum();
-    let average_distance_this_month = if runs_this_month > 0 {
-        distance_this_month / runs_this_month as f64
-    } else {
-        0.0
-    };
+    let (runs_this_week, average_distance_this_week) = period_totals(daily_totals, week_start);
+    let (runs_this_month, average_distance_this_month) = period_totals(daily_totals, month_start);
+    let (runs_this_year, average_distance_this_year) = period_totals(daily_totals, year_start);
 
-    let distance_this_year: f64 = runs
-        .iter()
-        .filter(|r| r.date >= year_start)
-        .map(|r| r.distance_miles)
-        .sum();
-    let average_distance_this_year = if runs_this_year > 0 {
-        distance_this_year / runs_this_year as f64
-    } else {
-        0.0
-    };
+    let average_pace_this_week = average_pace(recent_runs.iter().filter(|r| r.date >= week_start));
+    let average_pace_this_month =
+        average_pace(recent_runs.iter().filter(|r| r.date >= month_start));
+    let average_pace_this_year = average_pace(recent_runs.iter().filter(|r| r.date >= year_start));
 
-    let recent_trend = calculate_recent_trend(&daily_totals, 30);
-    let monthly_breakdown = calculate_monthly_breakdown(runs, MONTHS_TO_SHOW);
+    let recent_trend = calculate_recent_trend(&distance_by_date, 30, today);
+    let monthly_breakdown = calculate_monthly_breakdown(daily_totals, MONTHS_TO_SHOW);
+    let weekly_trends = calculate_weekly_trends(recent_runs, today, WEEKS_TO_SHOW);
+    let type_breakdown_this_week =
+        calculate_type_breakdown(recent_runs.iter().filter(|r| r.date >= week_start));
+    let type_breakdown_this_month =
+        calculate_type_breakdown(recent_runs.iter().filter(|r| r.date >= month_start));
 
     // Calculate days remaining to year goal (365 days with at least 1 mile each)
-    let days_with_goal_met_this_year = daily_totals
+    let days_with_goal_met_this_year = distance_by_date
         .iter()
         .filter(|(&date, &distance)| date >= year_start && distance >= DAILY_GOAL_MILES)
         .count() as i32;
@@ -92,39 +101,73 @@ pub fn calculate_analytics(runs: &[Run]) -> Analytics {
         average_distance_this_week,
         average_distance_this_month,
         average_distance_this_year,
+        average_pace_this_week,
+        average_pace_this_month,
+        average_pace_this_year,
         monthly_breakdown,
+        tag_totals,
+        type_breakdown_this_week,
+        type_breakdown_this_month,
+        weekly_trends,
     }
 }
 
-fn group_by_date(runs: &[Run]) -> BTreeMap<NaiveDate, f64> {
-    let mut daily_totals: BTreeMap<NaiveDate, f64> = BTreeMap::new();
+/// Number of runs on or after `start`, and their average distance.
+fn period_totals(daily_totals: &[DailyTotal], start: NaiveDate) -> (u32, f64) {
+    let (run_count, distance) = daily_totals
+        .iter()
+        .filter(|day| day.date >= start)
+        .fold((0, 0.0), |(count, distance), day| {
+            (count + day.run_count, distance + day.distance_miles)
+        });
 
-    for run in runs {
-        *daily_totals.entry(run.date).or_insert(0.0) += run.distance_miles;
+    let average = if run_count > 0 {
+        distance / run_count as f64
+    } else {
+        0.0
+    };
+    (run_count, average)
+}
+
+/// Average pace in seconds per mile across the runs that have a duration,
+/// weighted by distance so long runs count proportionally.
+fn average_pace<'a>(runs: impl Iterator<Item = &'a Run>) -> Option<f64> {
+    let (seconds, miles) = runs
+        .filter_map(|r| r.duration_seconds.map(|d| (d as f64, r.distance_miles)))
+        .fold(
+            (0.0, 0.0),
+            |(total_seconds, total_miles), (seconds, miles)| {
+                (total_seconds + seconds, total_miles + miles)
+            },
+        );
+
+    if miles > 0.0 {
+        Some(seconds / miles)
+    } else {
+        None
     }
+}
 
+fn distance_by_date(daily_totals: &[DailyTotal]) -> BTreeMap<NaiveDate, f64> {
     daily_totals
+        .iter()
+        .map(|day| (day.date, day.distance_miles))
+        .collect()
 }
 
-fn calculate_current_streak(daily_totals: &BTreeMap<NaiveDate, f64>) -> u32 {
+fn calculate_current_streak(daily_totals: &BTreeMap<NaiveDate, f64>, today: NaiveDate) -> u32 {
     let mut streak = 0;
-    let mut current_date = Local::now().naive_local().date();
-
-    loop {
-        if let Some(&distance) = daily_totals.get(&current_date) {
-            if distance >= DAILY_GOAL_MILES {
-                streak += 1;
-                if let Some(prev_date) = current_date.pred_opt() {
-                    current_date = prev_date;
-                } else {
-                    break;
-                }
-            } else {
-                break;
-            }
-        } else {
+    let mut current_date = today;
+
+    while let Some(&distance) = daily_totals.get(&current_date) {
+        if distance < DAILY_GOAL_MILES {
             break;
         }
+        streak += 1;
+        match current_date.pred_opt() {
+            Some(prev_date) => current_date = prev_date,
+            None => break,
+        }
     }
 
     streak
@@ -162,8 +205,11 @@ fn calculate_longest_streak(daily_totals: &BTreeMap<NaiveDate, f64>) -> u32 {
     longest.max(current)
 }
 
-fn calculate_recent_trend(daily_totals: &BTreeMap<NaiveDate, f64>, days: i64) -> Vec<DailyData> {
-    let today = Local::now().naive_local().date();
+fn calculate_recent_trend(
+    daily_totals: &BTreeMap<NaiveDate, f64>,
+    days: i64,
+    today: NaiveDate,
+) -> Vec<DailyData> {
     let start_date = today - chrono::Duration::days(days - 1);
 
     let mut trend = Vec::new();
@@ -177,21 +223,21 @@ fn calculate_recent_trend(daily_totals: &BTreeMap<NaiveDate, f64>, days: i64) ->
             distance,
         });
 
-        current_date = current_date + chrono::Duration::days(1);
+        current_date += chrono::Duration::days(1);
     }
 
     trend
 }
 
-fn calculate_monthly_breakdown(runs: &[Run], months: usize) -> Vec<MonthlyData> {
-    // Group runs by (year, month)
+fn calculate_monthly_breakdown(daily_totals: &[DailyTotal], months: usize) -> Vec<MonthlyData> {
+    // Group days by (year, month)
     let mut monthly_totals: BTreeMap<(i32, u32), (f64, u32)> = BTreeMap::new();
 
-    for run in runs {
-        let key = (run.date.year(), run.date.month());
+    for day in daily_totals {
+        let key = (day.date.year(), day.date.month());
         let entry = monthly_totals.entry(key).or_insert((0.0, 0));
-        entry.0 += run.distance_miles;
-        entry.1 += 1;
+        entry.0 += day.distance_miles;
+        entry.1 += day.run_count;
     }
 
     // Convert to Vec<MonthlyData> and sort descending (newest first)
@@ -221,3 +267,96 @@ fn calculate_monthly_breakdown(runs: &[Run], months: usize) -> Vec<MonthlyData>
 
     result
 }
+
+/// Run count and distance per tag, largest distance first. Tags are matched
+/// case-insensitively; the first spelling seen is the one shown.
+pub fn calculate_tag_totals(runs: &[Run]) -> Vec<TagTotal> {
+    let mut totals: Vec<TagTotal> = Vec::new();
+
+    for run in runs {
+        for tag in &run.tags {
+            match totals
+                .iter_mut()
+                .find(|total| total.name.eq_ignore_ascii_case(tag))
+            {
+                Some(total) => {
+                    total.run_count += 1;
+                    total.total_distance += run.distance_miles;
+                }
+                None => totals.push(TagTotal {
+                    name: tag.clone(),
+                    run_count: 1,
+                    total_distance: run.distance_miles,
+                }),
+            }
+        }
+    }
+
+    totals.sort_by(|a, b| {
+        b.total_distance
+            .total_cmp(&a.total_distance)
+            .then_with(|| a.name.to_lowercase().cmp(&b.name.to_lowercase()))
+    });
+    totals
+}
+
+/// Run count and distance per run type, in `RunType` order with untyped runs
+/// last. Types with no runs are left out.
+fn calculate_type_breakdown<'a>(runs: impl Iterator<Item = &'a Run>) -> Vec<TypeTotal> {
+    // `None` sorts before `Some`, so untyped runs are moved to the end below
+    let mut totals: BTreeMap<Option<RunType>, (u32, f64)> = BTreeMap::new();
+    for run in runs {
+        let entry = totals.entry(run.run_type).or_insert((0, 0.0));
+        entry.0 += 1;
+        entry.1 += run.distance_miles;
+    }
+
+    let untyped = totals.remove(&None);
+    totals
+        .into_iter()
+        .chain(untyped.map(|total| (None, total)))
+        .map(|(run_type, (run_count, total_distance))| TypeTotal {
+            run_type,
+            run_count,
+            total_distance,
+        })
+        .collect()
+}
+
+/// Mileage, effort and heart rate for the last `weeks` Monday-to-Sunday
+/// weeks, newest first. Weeks without runs are included so gaps show up.
+fn calculate_weekly_trends(runs: &[Run], today: NaiveDate, weeks: i64) -> Vec<WeeklyData> {
+    let this_week = today - chrono::Duration::days(today.weekday().num_days_from_monday() as i64);
+
+    (0..weeks)
+        .map(|offset| {
+            let week_start = this_week - chrono::Duration::weeks(offset);
+            let week_end = week_start + chrono::Duration::days(6);
+            let week_runs: Vec<&Run> = runs
+                .iter()
+                .filter(|r| r.date >= week_start && r.date <= week_end)
+                .collect();
+
+            WeeklyData {
+                week_start,
+                total_distance: week_runs.iter().map(|r| r.distance_miles).sum(),
+                run_count: week_runs.len() as u32,
+                average_effort: mean(week_runs.iter().filter_map(|r| r.effort.map(f64::from))),
+                average_heart_rate: mean(
+                    week_runs
+                        .iter()
+                        .filter_map(|r| r.avg_heart_rate.map(f64::from)),
+                ),
+            }
+        })
+        .collect()
+}
+
+fn mean(values: impl Iterator<Item = f64>) -> Option<f64> {
+    let (sum, count) = values.fold((0.0, 0), |(sum, count), value| (sum + value, count + 1));
+    if count > 0 {
+        Some(sum / count as f64)
+    } else {
+        None
+    }
+}
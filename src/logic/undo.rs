@@ -0,0 +1,81 @@
+use crate::models::run::Run;
+
+/// A data-changing operation that can be reversed. Each variant stores the
+/// full run (including its id) so it can be replayed against the database.
+#[derive(Debug, Clone)]
+pub enum Change {
+    Insert(Run),
+    Update {
+        before: Run,
+        after: Run,
+    },
+    Delete(Run),
+    /// Several changes made by one action, such as replacing another run
+    /// with an edited one. They are undone in reverse order.
+    Batch(Vec<Change>),
+}
+
+impl Change {
+    pub fn description(&self) -> String {
+        let (verb, run) = match self {
+            Change::Insert(run) => ("insert", run),
+            Change::Update { after, .. } => ("edit", after),
+            Change::Delete(run) => ("delete", run),
+            // Describe a batch by the run it edited
+            Change::Batch(changes) => {
+                return changes
+                    .iter()
+                    .find(|change| matches!(change, Change::Update { .. }))
+                    .or(changes.first())
+                    .map(Change::description)
+                    .unwrap_or_default();
+            }
+        };
+        format!(
+            "{} of {} {} run",
+            verb,
+            run.date.format("%Y-%m-%d"),
+            run.time_started.format("%H:%M")
+        )
+    }
+}
+
+/// Session-wide undo/redo stacks. Recording a new change discards anything
+/// that could have been redone, as in most editors.
+pub struct UndoHistory {
+    undo_stack: Vec<Change>,
+    redo_stack: Vec<Change>,
+}
+
+impl UndoHistory {
+    pub fn new() -> Self {
+        Self {
+            undo_stack: Vec::new(),
+            redo_stack: Vec::new(),
+        }
+    }
+
+    pub fn record(&mut self, change: Change) {
+        self.undo_stack.push(change);
+        self.redo_stack.clear();
+    }
+
+    pub fn pop_undo(&mut self) -> Option<Change> {
+        self.undo_stack.pop()
+    }
+
+    pub fn pop_redo(&mut self) -> Option<Change> {
+        self.redo_stack.pop()
+    }
+
+    /// Pushes a change that has just been undone so it can be redone.
+    pub fn push_redo(&mut self, change: Change) {
+        self.redo_stack.push(change);
+    }
+
+    /// Pushes a change that has just been redone (or failed to undo) back
+    /// onto the undo stack without clearing the redo stack.
+    pub fn push_undo(&mut self, change: Change) {
+        self.undo_stack.push(change);
+    }
+}
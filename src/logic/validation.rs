@@ -1,5 +1,6 @@
+use crate::models::units::DistanceUnit;
 use anyhow::{anyhow, Result};
-use chrono::{Local, NaiveDate, NaiveTime};
+use chrono::{FixedOffset, Local, NaiveDate, NaiveTime};
 
 pub fn parse_date(date_str: &str) -> Result<NaiveDate> {
     if date_str.is_empty() {
@@ -24,7 +25,9 @@ pub fn parse_time(time_str: &str) -> Result<NaiveTime> {
         .map_err(|_| anyhow!("Invalid time format. Use HH:MM or HH:MM:SS"))
 }
 
-pub fn parse_distance(distance_str: &str) -> Result<f64> {
+/// Parses a distance entered in `unit` and returns it in miles, the
+/// canonical storage unit.
+pub fn parse_distance(distance_str: &str, unit: DistanceUnit) -> Result<f64> {
     if distance_str.is_empty() {
         return Err(anyhow!("Distance is required"));
     }
@@ -32,9 +35,28 @@ pub fn parse_distance(distance_str: &str) -> Result<f64> {
     distance_str
         .trim()
         .parse::<f64>()
+        .map(|value| unit.to_miles(value))
         .map_err(|_| anyhow!("Invalid distance. Enter a number (e.g., 3.5)"))
 }
 
+/// Formats a stored distance for editing in `unit`.
+pub fn format_distance_input(miles: f64, unit: DistanceUnit) -> String {
+    match unit {
+        DistanceUnit::Miles => miles.to_string(),
+        DistanceUnit::Kilometers => {
+            let formatted = format!("{:.3}", unit.convert_miles(miles));
+            formatted
+                .trim_end_matches('0')
+                .trim_end_matches('.')
+                .to_string()
+        }
+    }
+}
+
+pub fn format_distance(miles: f64, unit: DistanceUnit) -> String {
+    format!("{:.2} {}", unit.convert_miles(miles), unit.label())
+}
+
 pub fn format_date(date: &NaiveDate) -> String {
     date.format("%Y-%m-%d").to_string()
 }
@@ -42,3 +64,229 @@ pub fn format_date(date: &NaiveDate) -> String {
 pub fn format_time(time: &NaiveTime) -> String {
     time.format("%H:%M:%S").to_string()
 }
+
+/// Parses a UTC offset such as `+09:00`, `-0530`, `+2` or `UTC+1`. Empty
+/// means no offset was given.
+pub fn parse_utc_offset(offset_str: &str) -> Result<Option<FixedOffset>> {
+    let offset_str = offset_str.trim();
+    if offset_str.is_empty() {
+        return Ok(None);
+    }
+
+    let invalid = || anyhow!("Invalid zone. Use a UTC offset like +09:00 or -05:00");
+    // The splits below are by byte position
+    if !offset_str.is_ascii() {
+        return Err(invalid());
+    }
+
+    let upper = offset_str.to_uppercase();
+    let rest = upper
+        .strip_prefix("UTC")
+        .or_else(|| upper.strip_prefix("GMT"))
+        .unwrap_or(&upper);
+    if rest.is_empty() || rest == "Z" {
+        return Ok(FixedOffset::east_opt(0));
+    }
+
+    let (sign, digits) = if let Some(digits) = rest.strip_prefix('+') {
+        (1, digits)
+    } else if let Some(digits) = rest.strip_prefix('-') {
+        (-1, digits)
+    } else {
+        return Err(invalid());
+    };
+    let (hours, minutes) = match digits.split_once(':') {
+        Some((hours, minutes)) => (hours, minutes),
+        None if digits.len() > 2 => digits.split_at(digits.len() - 2),
+        None => (digits, "0"),
+    };
+    if !hours
+        .chars()
+        .chain(minutes.chars())
+        .all(|c| c.is_ascii_digit())
+    {
+        return Err(invalid());
+    }
+    let hours: i32 = hours.parse().map_err(|_| invalid())?;
+    let minutes: i32 = minutes.parse().map_err(|_| invalid())?;
+    if minutes > 59 || hours * 60 + minutes > 14 * 60 {
+        return Err(anyhow!("Zone must be between UTC-14:00 and UTC+14:00"));
+    }
+
+    FixedOffset::east_opt(sign * (hours * 3600 + minutes * 60))
+        .map(Some)
+        .ok_or_else(invalid)
+}
+
+pub fn format_utc_offset(offset: &FixedOffset) -> String {
+    format!("UTC{}", offset)
+}
+
+pub fn parse_duration(duration_str: &str) -> Result<Option<u32>> {
+    let duration_str = duration_str.trim();
+    if duration_str.is_empty() {
+        return Ok(None);
+    }
+
+    let invalid = || anyhow!("Invalid duration. Use MM:SS or H:MM:SS");
+
+    let parts = duration_str
+        .split(':')
+        .map(|part| part.parse::<u32>().map_err(|_| invalid()))
+        .collect::<Result<Vec<_>>>()?;
+
+    // Checked, since a long enough value doesn't fit in a u32
+    let seconds = match parts.as_slice() {
+        [minutes, seconds] if *seconds < 60 => minutes
+            .checked_mul(60)
+            .and_then(|total| total.checked_add(*seconds)),
+        [hours, minutes, seconds] if *minutes < 60 && *seconds < 60 => hours
+            .checked_mul(3600)
+            .and_then(|total| total.checked_add(minutes * 60 + seconds)),
+        _ => None,
+    };
+
+    seconds.map(Some).ok_or_else(invalid)
+}
+
+pub fn format_duration(total_seconds: u32) -> String {
+    let hours = total_seconds / 3600;
+    let minutes = (total_seconds % 3600) / 60;
+    let seconds = total_seconds % 60;
+
+    if hours > 0 {
+        format!("{}:{:02}:{:02}", hours, minutes, seconds)
+    } else {
+        format!("{}:{:02}", minutes, seconds)
+    }
+}
+
+/// Parses an optional heart rate in beats per minute.
+pub fn parse_heart_rate(heart_rate_str: &str) -> Result<Option<u32>> {
+    let heart_rate_str = heart_rate_str.trim();
+    if heart_rate_str.is_empty() {
+        return Ok(None);
+    }
+
+    match heart_rate_str.parse::<u32>() {
+        Ok(bpm) if (30..=250).contains(&bpm) => Ok(Some(bpm)),
+        _ => Err(anyhow!(
+            "Heart rate must be a whole number between 30 and 250 bpm"
+        )),
+    }
+}
+
+/// Parses an optional perceived effort on a 1-10 scale.
+pub fn parse_effort(effort_str: &str) -> Result<Option<u8>> {
+    let effort_str = effort_str.trim();
+    if effort_str.is_empty() {
+        return Ok(None);
+    }
+
+    match effort_str.parse::<u8>() {
+        Ok(effort) if (1..=10).contains(&effort) => Ok(Some(effort)),
+        _ => Err(anyhow!("Effort must be a whole number from 1 to 10")),
+    }
+}
+
+pub fn format_pace(seconds_per_unit: f64) -> String {
+    let rounded = seconds_per_unit.round() as u32;
+    format!("{}:{:02}", rounded / 60, rounded % 60)
+}
+
+/// Parses a comma-separated tag list. Tags may not contain whitespace or `#`,
+/// and duplicates (ignoring case) are dropped.
+pub fn parse_tags(tags_str: &str) -> Result<Vec<String>> {
+    let mut tags: Vec<String> = Vec::new();
+
+    for tag in tags_str.split(',').map(str::trim).filter(|t| !t.is_empty()) {
+        if tag.chars().any(|c| c.is_whitespace() || c == '#') {
+            return Err(anyhow!(
+                "Invalid tag '{}'. Tags are single words separated by commas",
+                tag
+            ));
+        }
+        if !tags.iter().any(|t| t.eq_ignore_ascii_case(tag)) {
+            tags.push(tag.to_string());
+        }
+    }
+
+    Ok(tags)
+}
+
+pub fn format_tags(tags: &[String]) -> String {
+    tags.join(", ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn offset(hours: i32, minutes: i32) -> Option<FixedOffset> {
+        FixedOffset::east_opt(hours.signum() * (hours.abs() * 3600 + minutes * 60))
+    }
+
+    #[test]
+    fn parses_utc_offsets() {
+        assert_eq!(parse_utc_offset("").unwrap(), None);
+        assert_eq!(parse_utc_offset("Z").unwrap(), offset(0, 0));
+        assert_eq!(parse_utc_offset("utc").unwrap(), offset(0, 0));
+        assert_eq!(parse_utc_offset("+09:00").unwrap(), offset(9, 0));
+        assert_eq!(parse_utc_offset("-0530").unwrap(), offset(-5, 30));
+        assert_eq!(parse_utc_offset("GMT+1").unwrap(), offset(1, 0));
+        assert!(parse_utc_offset("+15:00").is_err());
+        assert!(parse_utc_offset("+-5").is_err());
+    }
+
+    #[test]
+    fn limits_utc_offsets_to_fourteen_hours() {
+        assert_eq!(parse_utc_offset("+14:00").unwrap(), offset(14, 0));
+        assert_eq!(parse_utc_offset("-14:00").unwrap(), offset(-14, 0));
+        for input in ["+14:01", "-14:01", "+1459"] {
+            let error = parse_utc_offset(input).unwrap_err().to_string();
+            assert_eq!(
+                error, "Zone must be between UTC-14:00 and UTC+14:00",
+                "{}",
+                input
+            );
+        }
+    }
+
+    #[test]
+    fn rejects_non_ascii_utc_offsets() {
+        for input in ["é", "+éa", "+0é", "-1é30", "UTC+日本"] {
+            let error = parse_utc_offset(input).unwrap_err().to_string();
+            assert!(error.starts_with("Invalid zone"), "{}: {}", input, error);
+        }
+    }
+
+    #[test]
+    fn parses_durations() {
+        assert_eq!(parse_duration("").unwrap(), None);
+        assert_eq!(parse_duration("25:30").unwrap(), Some(1530));
+        assert_eq!(parse_duration("1:02:03").unwrap(), Some(3723));
+        assert!(parse_duration("25:60").is_err());
+        assert!(parse_duration("1:60:00").is_err());
+        assert!(parse_duration("abc").is_err());
+    }
+
+    #[test]
+    fn rejects_durations_that_overflow() {
+        for input in [
+            "99999999:00",
+            "9999999:00:00",
+            "71582788:16",
+            "1193046:28:16",
+        ] {
+            let error = parse_duration(input).unwrap_err().to_string();
+            assert!(
+                error.starts_with("Invalid duration"),
+                "{}: {}",
+                input,
+                error
+            );
+        }
+        // The largest duration that fits
+        assert_eq!(parse_duration("1193046:28:15").unwrap(), Some(u32::MAX));
+    }
+}
@@ -1,38 +1,500 @@
 mod app;
+mod cli;
 mod db;
 mod logic;
 mod models;
 mod ui;
 
-use anyhow::Result;
-use app::{App, InputField, Screen};
+use anyhow::{bail, Context, Result};
+use app::{
+    App, AthletePicker, GearForm, InputField, LapEditor, RouteForm, RunConflict, Screen,
+    RUN_PAGE_SIZE,
+};
+use cli::{Command, DbLocation};
 use crossterm::{
     event::{self, Event, KeyCode, KeyEvent, KeyModifiers},
     execute,
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
 };
+use db::merge::MergePolicy;
+use db::store::{MemoryStore, RunStore, SqliteStore};
+use logic::search::SearchQuery;
+use logic::undo::Change;
+use logic::validation;
+use models::athlete::Athlete;
+use models::run::Run;
+use models::units::DistanceUnit;
 use ratatui::{backend::CrosstermBackend, Terminal};
-use rusqlite::Connection;
 use std::io;
 use std::panic;
+use std::path::{Path, PathBuf};
 use std::time::Duration;
 
+const PASSPHRASE_ENV: &str = "RUNLOGGER_PASSPHRASE";
+const UNITS_SETTING: &str = "distance_unit";
+const ATHLETE_SETTING: &str = "active_athlete_id";
+const DEFAULT_GEAR_SETTING: &str = "default_gear_id";
+
 fn main() -> Result<()> {
-    setup_panic_hook();
+    let cli = cli::parse_args(std::env::args().skip(1))?;
+
+    if cli.command == Command::Help {
+        println!("{}", cli::USAGE);
+        return Ok(());
+    }
+
+    // An in-memory log has no file, so there is nothing to back up or restore
+    if cli.location == DbLocation::Memory {
+        if cli.command != Command::Run {
+            bail!("--in-memory can only be used to start the run logger");
+        }
+        return start(&MemoryStore::new(), None, None);
+    }
+
+    let db_path = db::connection::get_db_path(&cli.location)?;
+
+    match cli.command {
+        Command::Run | Command::CheckTotals | Command::Help => {}
+        Command::Encrypt => return encrypt(&db_path),
+        Command::Decrypt => return decrypt(&db_path),
+        Command::ListBackups => return list_backups(&db_path),
+        Command::RestoreBackup(selector) => return restore_backup(&db_path, &selector),
+        Command::Merge { path, policy } => return merge(&db_path, &path, policy),
+    }
+
+    let passphrase = passphrase_for(&db_path)?;
+    let passphrase = passphrase.as_deref();
+    let conn = db::connection::init_db(&db_path, passphrase)?;
+    if cli.command == Command::CheckTotals {
+        return check_totals(&conn);
+    }
+
+    let backup_error = db::backup::backups_to_keep()
+        .and_then(|keep| db::backup::backup_if_due(&conn, &db_path, passphrase, keep))
+        .err()
+        .map(|e| format!("Backup failed: {:#}", e));
+
+    start(&SqliteStore::new(conn), Some(db_path), backup_error)
+}
 
-    let db_path = db::connection::get_db_path()?;
-    let conn = db::connection::init_db(&db_path)?;
+fn start(
+    store: &dyn RunStore,
+    db_path: Option<PathBuf>,
+    startup_message: Option<String>,
+) -> Result<()> {
+    setup_panic_hook();
 
+    let mut app = load_app(store, db_path)?;
     let mut terminal = setup_terminal()?;
-    let mut app = App::new();
 
-    let result = run_app(&mut terminal, &mut app, &conn);
+    // Unreadable runs are set aside rather than breaking every screen
+    let quarantined = store.quarantine_bad_runs()?;
+    let quarantine_message = (quarantined > 0).then(|| {
+        format!(
+            "{} {} could not be read and {} set aside; see Data Health (Esc → 7)",
+            quarantined,
+            if quarantined == 1 { "run" } else { "runs" },
+            if quarantined == 1 { "was" } else { "were" }
+        )
+    });
+    app.status_message = match (startup_message, quarantine_message) {
+        (Some(backup), Some(quarantine)) => Some(format!("{}. {}", backup, quarantine)),
+        (backup, quarantine) => backup.or(quarantine),
+    };
+
+    let result = run_app(&mut terminal, &mut app, store);
 
     restore_terminal(&mut terminal)?;
 
     result
 }
 
+/// The app as it starts: saved settings applied and the athletes, gear and
+/// routes that Quick Entry offers loaded.
+fn load_app(store: &dyn RunStore, db_path: Option<PathBuf>) -> Result<App> {
+    let mut app = App::new(db_path);
+    if let Some(units) = store
+        .get_setting(UNITS_SETTING)?
+        .as_deref()
+        .and_then(DistanceUnit::from_setting)
+    {
+        app.units = units;
+    }
+    app.quick_entry_state.known_tags = store.get_all_tags()?;
+    load_athletes(&mut app, store)?;
+    if let Some(athlete_id) = store
+        .get_setting(ATHLETE_SETTING)?
+        .and_then(|value| value.parse::<i64>().ok())
+        .filter(|&id| app.athletes.iter().any(|athlete| athlete.id == Some(id)))
+    {
+        app.athlete_id = athlete_id;
+        app.quick_entry_state.athlete_id = athlete_id;
+    }
+    load_gear(&mut app, store)?;
+    load_routes(&mut app, store)?;
+    app.quick_entry_state.gear_id = app.quick_entry_state.default_gear_id;
+
+    Ok(app)
+}
+
+fn list_backups(db_path: &Path) -> Result<()> {
+    let passphrase = passphrase_for(db_path)?;
+    let backups = db::backup::list_backups(db_path, passphrase.as_deref())?;
+    if backups.is_empty() {
+        println!(
+            "No backups found in {}",
+            db::backup::backup_dir(db_path).display()
+        );
+        return Ok(());
+    }
+
+    println!("Backups in {}:", db::backup::backup_dir(db_path).display());
+    for (i, backup) in backups.iter().enumerate() {
+        let runs = backup
+            .run_count
+            .map(|count| format!("{} runs", count))
+            .unwrap_or_else(|| "unreadable".to_string());
+        println!(
+            "{:>3}. {}  {:<12} {}",
+            i + 1,
+            backup.taken_at.format("%Y-%m-%d %H:%M:%S"),
+            runs,
+            backup.file_name()
+        );
+    }
+    Ok(())
+}
+
+fn restore_backup(db_path: &Path, selector: &str) -> Result<()> {
+    let backup_path = db::backup::find_backup(db_path, selector)?;
+    let passphrase = passphrase_for(db_path)?;
+    let passphrase = passphrase.as_deref();
+    let mut conn = db::connection::open_connection(db_path, passphrase)?;
+    let keep = db::backup::backups_to_keep()?;
+    db::backup::restore_backup(&mut conn, db_path, &backup_path, passphrase, keep)?;
+    // The backup may predate the current schema
+    db::migrations::init_database(&mut conn).context("Failed to initialize database")?;
+    println!("Restored {}", backup_path.display());
+    Ok(())
+}
+
+/// The passphrase of an encrypted database, from `RUNLOGGER_PASSPHRASE` or
+/// asked for before the TUI starts. Plain databases need none.
+fn passphrase_for(db_path: &Path) -> Result<Option<String>> {
+    if !db::encryption::is_encrypted(db_path)? {
+        return Ok(None);
+    }
+    db::encryption::ensure_supported()?;
+
+    if let Some(passphrase) = env_passphrase() {
+        return Ok(Some(passphrase));
+    }
+    read_passphrase(&format!("Passphrase for {}: ", db_path.display())).map(Some)
+}
+
+fn env_passphrase() -> Option<String> {
+    std::env::var(PASSPHRASE_ENV)
+        .ok()
+        .filter(|passphrase| !passphrase.is_empty())
+}
+
+fn encrypt(db_path: &Path) -> Result<()> {
+    db::encryption::ensure_supported()?;
+    if db::encryption::is_encrypted(db_path)? {
+        bail!("{} is already encrypted", db_path.display());
+    }
+    // Plain backups would leave the runs readable, so they're encrypted too
+    let backups = db::backup::plain_backups(db_path)?;
+    confirm_backups(db_path, &backups, "unencrypted", "Encrypt")?;
+    // Create the database, or bring it up to date, before converting it
+    drop(db::connection::init_db(db_path, None)?);
+
+    let passphrase = match env_passphrase() {
+        Some(passphrase) => passphrase,
+        None => {
+            let passphrase = read_passphrase("New passphrase: ")?;
+            if passphrase.is_empty() {
+                bail!("The passphrase can't be empty");
+            }
+            if read_passphrase("Repeat the passphrase: ")? != passphrase {
+                bail!("The passphrases didn't match; nothing was changed");
+            }
+            passphrase
+        }
+    };
+
+    db::encryption::encrypt_database(db_path, &passphrase)?;
+    println!("Encrypted {}", db_path.display());
+    for backup in &backups {
+        db::encryption::encrypt_database(backup, &passphrase)
+            .with_context(|| format!("Failed to encrypt backup {}", backup.display()))?;
+    }
+    if !backups.is_empty() {
+        println!("Encrypted {}", count_backups(backups.len()));
+    }
+    Ok(())
+}
+
+fn decrypt(db_path: &Path) -> Result<()> {
+    if !db::encryption::is_encrypted(db_path)? {
+        bail!("{} is not encrypted", db_path.display());
+    }
+    // Encrypted backups of a plain database couldn't be listed or restored
+    let backups = db::backup::encrypted_backups(db_path)?;
+    confirm_backups(db_path, &backups, "encrypted", "Decrypt")?;
+    let passphrase = passphrase_for(db_path)?.unwrap_or_default();
+
+    db::encryption::decrypt_database(db_path, &passphrase)?;
+    println!("Decrypted {}", db_path.display());
+    for backup in &backups {
+        db::encryption::decrypt_database(backup, &passphrase)
+            .with_context(|| format!("Failed to decrypt backup {}", backup.display()))?;
+    }
+    if !backups.is_empty() {
+        println!("Decrypted {}", count_backups(backups.len()));
+    }
+    Ok(())
+}
+
+/// Asks whether to convert `backups` along with the database. Anything but
+/// yes stops the command before anything is changed.
+fn confirm_backups(db_path: &Path, backups: &[PathBuf], state: &str, action: &str) -> Result<()> {
+    if backups.is_empty() {
+        return Ok(());
+    }
+
+    let backup_dir = db::backup::backup_dir(db_path);
+    let prompt = format!(
+        "{} in {} {} {}. {} {} with the same passphrase? [y/N] ",
+        count_backups(backups.len()),
+        backup_dir.display(),
+        if backups.len() == 1 { "is" } else { "are" },
+        state,
+        action,
+        if backups.len() == 1 { "it" } else { "them" }
+    );
+    if !confirm(&prompt)? {
+        bail!(
+            "Nothing was changed. Delete or move the {} backups in {} first, \
+             or answer y to {} them too",
+            state,
+            backup_dir.display(),
+            action.to_lowercase()
+        );
+    }
+    Ok(())
+}
+
+fn count_backups(count: usize) -> String {
+    format!(
+        "{} {}",
+        count,
+        if count == 1 { "backup" } else { "backups" }
+    )
+}
+
+/// Brings the runs of another database into this one and reports what
+/// happened. This database is backed up first so the merge can be undone;
+/// the other one is only read.
+fn merge(db_path: &Path, other_path: &Path, policy: MergePolicy) -> Result<()> {
+    if !other_path.exists() {
+        bail!("{} does not exist", other_path.display());
+    }
+    if db_path.exists() && db_path.canonicalize()? == other_path.canonicalize()? {
+        bail!("Can't merge {} with itself", db_path.display());
+    }
+
+    let passphrase = passphrase_for(db_path)?;
+    let conn = db::connection::init_db(db_path, passphrase.as_deref())?;
+    let other = db::connection::open_read_only(other_path, passphrase_for(other_path)?.as_deref())?;
+
+    let backup_path = db::backup::create_backup(&conn, db_path, passphrase.as_deref())?;
+    db::backup::rotate_backups(db_path, db::backup::backups_to_keep()?.max(1))?;
+    println!(
+        "Backed up {} to {}",
+        db_path.display(),
+        backup_path.display()
+    );
+
+    let report = db::merge::merge(&conn, &other, policy)?;
+    let units = db::queries::get_setting(&conn, UNITS_SETTING)?
+        .as_deref()
+        .and_then(DistanceUnit::from_setting)
+        .unwrap_or(DistanceUnit::Miles);
+    let gear = db::queries::get_gear(&conn)?;
+    let routes = db::queries::get_routes(&conn)?;
+    let describe = |run: &Run| {
+        format!(
+            "{} {}  {}",
+            validation::format_date(&run.date),
+            validation::format_time(&run.time_started),
+            validation::format_distance(run.distance_miles, units)
+        )
+    };
+
+    println!(
+        "Merged {}: {} imported, {} updated, {} {}, {} unchanged, {} skipped",
+        other_path.display(),
+        report.imported.len(),
+        report.updated.len(),
+        report.conflicts.len(),
+        if report.conflicts.len() == 1 {
+            "conflict"
+        } else {
+            "conflicts"
+        },
+        report.unchanged,
+        report.skipped.len()
+    );
+
+    if !report.conflicts.is_empty() {
+        println!();
+        println!("Changed in both databases (--prefer {}):", policy.as_str());
+    }
+    for conflict in &report.conflicts {
+        let here = ui::screens::run_history::fields(&conflict.here, &gear, &routes, units);
+        let other = ui::screens::run_history::fields(&conflict.other, &gear, &routes, units);
+        let (mut mine, mut theirs): (Vec<String>, Vec<String>) = here
+            .into_iter()
+            .zip(other)
+            .filter(|((_, mine), (_, theirs))| mine != theirs)
+            .map(|((name, mine), (_, theirs))| {
+                (format!("{} {}", name, mine), format!("{} {}", name, theirs))
+            })
+            .unzip();
+        if conflict.here.deleted_at.is_some() != conflict.other.deleted_at.is_some() {
+            let state = |run: &Run| {
+                if run.deleted_at.is_some() {
+                    "in the trash"
+                } else {
+                    "not in the trash"
+                }
+            };
+            mine.push(state(&conflict.here).to_string());
+            theirs.push(state(&conflict.other).to_string());
+        }
+        let changed_at = |at: chrono::DateTime<chrono::Utc>| {
+            at.with_timezone(&chrono::Local)
+                .format("%Y-%m-%d %H:%M")
+                .to_string()
+        };
+
+        println!(
+            "  {}: {}",
+            describe(&conflict.here),
+            if conflict.took_other {
+                "took the other version"
+            } else {
+                "kept this version"
+            }
+        );
+        println!(
+            "    here  (changed {}): {}",
+            changed_at(conflict.here_changed_at),
+            mine.join(", ")
+        );
+        println!(
+            "    there (changed {}): {}",
+            changed_at(conflict.other_changed_at),
+            theirs.join(", ")
+        );
+    }
+
+    if !report.skipped.is_empty() {
+        println!();
+        println!("Not merged:");
+    }
+    for skipped in &report.skipped {
+        println!("  {}: {}", describe(&skipped.run), skipped.reason);
+    }
+
+    println!();
+    println!(
+        "To undo the merge, run `runlogger restore 1` (or pick {} from `runlogger backups`)",
+        backup_path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default()
+    );
+    Ok(())
+}
+
+/// Reads a line from the terminal without echoing it.
+fn read_passphrase(prompt: &str) -> Result<String> {
+    use std::io::Write;
+
+    let mut stdout = io::stdout();
+    write!(stdout, "{}", prompt)?;
+    stdout.flush()?;
+
+    enable_raw_mode()?;
+    let mut passphrase = String::new();
+    let result = loop {
+        match event::read() {
+            Ok(Event::Key(key)) => match key.code {
+                KeyCode::Enter => break Ok(()),
+                KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                    break Err(anyhow::anyhow!("Cancelled"))
+                }
+                KeyCode::Esc => break Err(anyhow::anyhow!("Cancelled")),
+                KeyCode::Char(c) => passphrase.push(c),
+                KeyCode::Backspace => {
+                    passphrase.pop();
+                }
+                _ => {}
+            },
+            Ok(_) => {}
+            Err(e) => break Err(e.into()),
+        }
+    };
+    disable_raw_mode()?;
+    writeln!(stdout)?;
+
+    result.map(|()| passphrase)
+}
+
+/// Asks a yes/no question on the terminal. Anything but y or yes, including
+/// no input at all, is a no.
+fn confirm(prompt: &str) -> Result<bool> {
+    use std::io::Write;
+
+    let mut stdout = io::stdout();
+    write!(stdout, "{}", prompt)?;
+    stdout.flush()?;
+
+    let mut answer = String::new();
+    io::stdin().read_line(&mut answer)?;
+    Ok(matches!(answer.trim().to_lowercase().as_str(), "y" | "yes"))
+}
+
+/// Compares the stored daily totals with the runs and rebuilds them if any
+/// day disagrees.
+fn check_totals(conn: &rusqlite::Connection) -> Result<()> {
+    let bad_dates = db::queries::check_daily_totals(conn)?;
+    if bad_dates.is_empty() {
+        println!("Daily totals match the logged runs");
+        return Ok(());
+    }
+
+    let shown: Vec<String> = bad_dates.iter().take(10).cloned().collect();
+    let more = if bad_dates.len() > shown.len() {
+        format!(" and {} more", bad_dates.len() - shown.len())
+    } else {
+        String::new()
+    };
+    println!(
+        "Daily totals were wrong for {} {}: {}{}",
+        bad_dates.len(),
+        if bad_dates.len() == 1 { "day" } else { "days" },
+        shown.join(", "),
+        more
+    );
+
+    db::queries::rebuild_daily_totals(conn)?;
+    println!("Rebuilt daily totals");
+    Ok(())
+}
+
 fn setup_panic_hook() {
     let original_hook = panic::take_hook();
     panic::set_hook(Box::new(move |panic_info| {
@@ -66,14 +528,14 @@ fn restore_terminal(terminal: &mut Terminal<CrosstermBackend<io::Stdout>>) -> Re
 fn run_app(
     terminal: &mut Terminal<CrosstermBackend<io::Stdout>>,
     app: &mut App,
-    conn: &Connection,
+    store: &dyn RunStore,
 ) -> Result<()> {
     loop {
         terminal.draw(|f| ui::render(f, app))?;
 
         if event::poll(Duration::from_millis(100))? {
             if let Event::Key(key) = event::read()? {
-                handle_key_event(app, key, conn)?;
+                handle_key_event(app, key, store)?;
             }
         }
 
@@ -85,15 +547,58 @@ fn run_app(
     Ok(())
 }
 
-fn handle_key_event(app: &mut App, key: KeyEvent, conn: &Connection) -> Result<()> {
+fn handle_key_event(app: &mut App, key: KeyEvent, store: &dyn RunStore) -> Result<()> {
     // Ctrl+Q or Ctrl+C to quit from anywhere
+    if key.modifiers.contains(KeyModifiers::CONTROL)
+        && matches!(key.code, KeyCode::Char('q') | KeyCode::Char('c'))
+    {
+        app.quit();
+        return Ok(());
+    }
+
+    app.status_message = None;
+
+    // Ctrl+Z undoes and Ctrl+R redoes from anywhere, including Quick Entry
     if key.modifiers.contains(KeyModifiers::CONTROL) {
-        if matches!(key.code, KeyCode::Char('q') | KeyCode::Char('c')) {
-            app.quit();
-            return Ok(());
+        match key.code {
+            KeyCode::Char('z') => return undo(app, store),
+            KeyCode::Char('r') => return redo(app, store),
+            _ => {}
         }
     }
 
+    // The athlete switcher captures all typing, including Esc to close it
+    if app.athlete_picker.is_some() {
+        return handle_athlete_picker_input(app, key, store);
+    }
+
+    // The search prompt captures all typing, including Esc to cancel it
+    if app.screen == Screen::RunList && app.run_list_state.is_searching() {
+        return handle_search_input(app, key, store);
+    }
+
+    // The duplicate date/time dialog waits for a choice, and Esc cancels it
+    if app.screen == Screen::QuickEntry && app.quick_entry_state.conflict.is_some() {
+        return handle_conflict_input(app, key, store);
+    }
+
+    // The gear form captures all typing, including Esc to cancel it
+    if app.screen == Screen::Gear && app.gear_state.form.is_some() {
+        return handle_gear_form_input(app, key, store);
+    }
+
+    if app.screen == Screen::RunDetail && app.run_detail_state.editor.is_some() {
+        return handle_lap_editor_input(app, key, store);
+    }
+
+    if app.screen == Screen::Routes && app.routes_state.form.is_some() {
+        return handle_route_form_input(app, key, store);
+    }
+
+    if app.screen == Screen::DataHealth && app.data_health_state.fix_input.is_some() {
+        return handle_fix_input(app, key, store);
+    }
+
     // If waiting for navigation command after Escape
     if app.waiting_for_nav {
         app.waiting_for_nav = false;
@@ -103,15 +608,42 @@ fn handle_key_event(app: &mut App, key: KeyEvent, conn: &Connection) -> Result<(
                 return Ok(());
             }
             KeyCode::Char('2') => {
-                load_runs(app, conn)?;
+                app.run_list_state.search = SearchQuery::default();
+                load_runs(app, store)?;
                 app.switch_to_screen(Screen::RunList);
                 return Ok(());
             }
             KeyCode::Char('3') => {
-                load_analytics(app, conn)?;
+                load_analytics(app, store)?;
                 app.switch_to_screen(Screen::Analytics);
                 return Ok(());
             }
+            KeyCode::Char('4') => {
+                load_trash(app, store)?;
+                app.switch_to_screen(Screen::Trash);
+                return Ok(());
+            }
+            KeyCode::Char('5') => {
+                load_gear(app, store)?;
+                app.gear_state.message = None;
+                app.switch_to_screen(Screen::Gear);
+                return Ok(());
+            }
+            KeyCode::Char('6') => {
+                load_routes(app, store)?;
+                app.routes_state.message = None;
+                app.switch_to_screen(Screen::Routes);
+                return Ok(());
+            }
+            KeyCode::Char('7') => {
+                load_data_health(app, store)?;
+                app.switch_to_screen(Screen::DataHealth);
+                return Ok(());
+            }
+            KeyCode::Char('A') => {
+                open_athlete_picker(app, store)?;
+                return Ok(());
+            }
             KeyCode::Esc => {
                 // Second Escape press - clear Quick Entry fields if on that screen
                 if app.screen == Screen::QuickEntry {
@@ -140,7 +672,7 @@ fn handle_key_event(app: &mut App, key: KeyEvent, conn: &Connection) -> Result<(
 
     // Handle screen-specific input first for Quick Entry to allow typing numbers and letters
     if app.screen == Screen::QuickEntry {
-        handle_quick_entry_input(app, key, conn)?;
+        handle_quick_entry_input(app, key, store)?;
         return Ok(());
     }
 
@@ -148,29 +680,63 @@ fn handle_key_event(app: &mut App, key: KeyEvent, conn: &Connection) -> Result<(
     match key.code {
         KeyCode::Char('1') => app.switch_to_screen(Screen::QuickEntry),
         KeyCode::Char('2') => {
-            load_runs(app, conn)?;
+            app.run_list_state.search = SearchQuery::default();
+            load_runs(app, store)?;
             app.switch_to_screen(Screen::RunList);
         }
         KeyCode::Char('3') => {
-            load_analytics(app, conn)?;
+            load_analytics(app, store)?;
             app.switch_to_screen(Screen::Analytics);
         }
+        KeyCode::Char('4') => {
+            load_trash(app, store)?;
+            app.switch_to_screen(Screen::Trash);
+        }
+        KeyCode::Char('5') => {
+            load_gear(app, store)?;
+            app.gear_state.message = None;
+            app.switch_to_screen(Screen::Gear);
+        }
+        KeyCode::Char('6') => {
+            load_routes(app, store)?;
+            app.routes_state.message = None;
+            app.switch_to_screen(Screen::Routes);
+        }
+        KeyCode::Char('7') => {
+            load_data_health(app, store)?;
+            app.switch_to_screen(Screen::DataHealth);
+        }
         KeyCode::Char('h') | KeyCode::Char('?') => app.switch_to_screen(Screen::Help),
+        KeyCode::Char('A') => {
+            open_athlete_picker(app, store)?;
+            return Ok(());
+        }
+        KeyCode::Char('u') => undo(app, store)?,
+        KeyCode::Char('m') => {
+            app.units = app.units.toggle();
+            store.set_setting(UNITS_SETTING, app.units.as_setting())?;
+        }
         _ => {}
     }
 
     // Handle other screens
     match app.screen {
         Screen::QuickEntry => {} // Already handled above
-        Screen::RunList => handle_run_list_input(app, key, conn)?,
+        Screen::RunList => handle_run_list_input(app, key, store)?,
+        Screen::RunDetail => handle_run_detail_input(app, key, store)?,
+        Screen::RunHistory => handle_run_history_input(app, key, store)?,
         Screen::Analytics => {}
+        Screen::Trash => handle_trash_input(app, key, store)?,
+        Screen::Gear => handle_gear_input(app, key, store)?,
+        Screen::Routes => handle_routes_input(app, key, store)?,
+        Screen::DataHealth => handle_data_health_input(app, key, store)?,
         Screen::Help => {}
     }
 
     Ok(())
 }
 
-fn handle_quick_entry_input(app: &mut App, key: KeyEvent, conn: &Connection) -> Result<()> {
+fn handle_quick_entry_input(app: &mut App, key: KeyEvent, store: &dyn RunStore) -> Result<()> {
     let state = &mut app.quick_entry_state;
 
     match key.code {
@@ -188,90 +754,55 @@ fn handle_quick_entry_input(app: &mut App, key: KeyEvent, conn: &Connection) ->
             state.error_message = None;
             state.success_message = None;
 
-            match logic::validation::parse_date(&state.date) {
-                Ok(date) => match logic::validation::parse_time(&state.time) {
-                    Ok(time) => match logic::validation::parse_distance(&state.distance) {
-                        Ok(distance) => {
-                            let note = if state.note.is_empty() {
-                                None
-                            } else {
-                                Some(state.note.clone())
-                            };
-
-                            match models::run::Run::new(date, time, distance, note) {
-                                Ok(mut run) => {
-                                    let result = if let Some(id) = state.editing_run_id {
-                                        // Update existing run
-                                        run.id = Some(id);
-                                        db::queries::update_run(conn, &run)
-                                            .map(|_| "Run updated successfully!".to_string())
-                                    } else {
-                                        // Insert new run
-                                        db::queries::insert_run(conn, &run)
-                                            .map(|_| "Run logged successfully!".to_string())
-                                    };
-
-                                    match result {
-                                        Ok(success_msg) => {
-                                            if state.editing_run_id.is_some() {
-                                                // After edit, clear edit mode
-                                                state.editing_run_id = None;
-                                            } else {
-                                                // After insert, clear fields for next entry
-                                                state.distance.clear();
-                                                state.note.clear();
-                                                state.focused_field = InputField::Distance;
-                                            }
-                                            state.success_message = Some(success_msg);
-                                        }
-                                        Err(e) => {
-                                            state.error_message =
-                                                Some(format!("Database error: {}", e));
-                                        }
-                                    }
-                                }
-                                Err(e) => {
-                                    state.error_message = Some(e.to_string());
-                                }
-                            }
-                        }
-                        Err(e) => {
-                            state.error_message = Some(e.to_string());
-                        }
-                    },
-                    Err(e) => {
-                        state.error_message = Some(e.to_string());
-                    }
-                },
+            let mut run = match state.build_run(app.units) {
+                Ok(run) => run,
                 Err(e) => {
                     state.error_message = Some(e.to_string());
+                    return Ok(());
                 }
-            }
+            };
+            run.id = state.editing_run_id;
 
-            // After handling Enter key, check if we need to reload and switch screens
-            if state.editing_run_id.is_none() && state.success_message.is_some() {
-                // Check if we just saved an edit (success message is set but not in edit mode anymore)
-                // We need to check if the success message indicates an edit
-                if let Some(ref msg) = state.success_message {
-                    if msg.contains("updated") {
-                        // Drop the state reference to allow borrowing app mutably
-                        let _ = state;
-                        if let Err(e) = load_runs(app, conn) {
-                            app.quick_entry_state.error_message =
-                                Some(format!("Failed to reload runs: {}", e));
-                        } else {
-                            app.switch_to_screen(Screen::RunList);
-                        }
-                        return Ok(());
-                    }
+            // Another run at the same date and time needs the user to decide
+            match store.find_run_at(run.athlete_id, run.date, run.time_started, run.id) {
+                Ok(Some(existing)) => state.conflict = Some(RunConflict { run, existing }),
+                Ok(None) => {
+                    let saved = save_run(store, run);
+                    finish_save(app, store, saved)?;
                 }
+                Err(e) => state.error_message = Some(format!("Database error: {}", e)),
             }
         }
+        KeyCode::Right | KeyCode::Char(' ') if state.focused_field == InputField::Route => {
+            state.cycle_route(true, app.units);
+        }
+        KeyCode::Left if state.focused_field == InputField::Route => {
+            state.cycle_route(false, app.units);
+        }
+        KeyCode::Right | KeyCode::Char(' ') if state.focused_field == InputField::Type => {
+            state.cycle_run_type(true);
+        }
+        KeyCode::Left if state.focused_field == InputField::Type => {
+            state.cycle_run_type(false);
+        }
+        KeyCode::Right | KeyCode::Char(' ') if state.focused_field == InputField::Shoe => {
+            state.cycle_gear(true);
+        }
+        KeyCode::Left if state.focused_field == InputField::Shoe => {
+            state.cycle_gear(false);
+        }
+        KeyCode::Right => {
+            state.accept_tag_suggestion();
+        }
         KeyCode::Char(c) => {
-            state.current_input_mut().push(c);
+            if let Some(input) = state.current_input_mut() {
+                input.push(c);
+            }
         }
         KeyCode::Backspace => {
-            state.current_input_mut().pop();
+            if let Some(input) = state.current_input_mut() {
+                input.pop();
+            }
         }
         _ => {}
     }
@@ -279,8 +810,12 @@ fn handle_quick_entry_input(app: &mut App, key: KeyEvent, conn: &Connection) ->
     Ok(())
 }
 
-fn handle_run_list_input(app: &mut App, key: KeyEvent, conn: &Connection) -> Result<()> {
+fn handle_run_list_input(app: &mut App, key: KeyEvent, store: &dyn RunStore) -> Result<()> {
     match key.code {
+        KeyCode::Char('/') => {
+            let state = &mut app.run_list_state;
+            state.search_input = Some(state.search.to_input());
+        }
         KeyCode::Up => {
             let state = &mut app.run_list_state;
             if state.selected_index > 0 {
@@ -298,55 +833,1595 @@ fn handle_run_list_input(app: &mut App, key: KeyEvent, conn: &Connection) -> Res
                     state.scroll_offset = state.selected_index - 9;
                 }
             }
+            if state.needs_more() {
+                load_more_runs(app, store)?;
+            }
+        }
+        KeyCode::Enter | KeyCode::Char('l') => {
+            // Open the selected run's details, straight into the lap editor for 'l'
+            let run = {
+                let state = &app.run_list_state;
+                state.runs.get(state.selected_index).cloned()
+            };
+            if let Some(run) = run {
+                open_run_detail(app, store, run, key.code == KeyCode::Char('l'))?;
+            }
         }
         KeyCode::Char('e') => {
             // Edit selected run
             let state = &app.run_list_state;
             if !state.runs.is_empty() && state.selected_index < state.runs.len() {
                 let run = state.runs[state.selected_index].clone();
-                app.quick_entry_state.load_run(&run);
+                app.quick_entry_state.load_run(&run, app.units);
                 app.switch_to_screen(Screen::QuickEntry);
             }
         }
+        KeyCode::Char('H') => {
+            let run = {
+                let state = &app.run_list_state;
+                state.runs.get(state.selected_index).cloned()
+            };
+            if let Some(run) = run {
+                open_run_history(app, store, run)?;
+            }
+        }
         KeyCode::Char('d') => {
-            // Delete selected run
-            let run_id = {
+            // Move selected run to the trash
+            let run = {
                 let state = &app.run_list_state;
-                if !state.runs.is_empty() && state.selected_index < state.runs.len() {
-                    state.runs[state.selected_index].id
-                } else {
-                    None
-                }
+                state.runs.get(state.selected_index).cloned()
             };
 
-            if let Some(id) = run_id {
-                db::queries::delete_run(conn, id)?;
-                // Reload the run list
-                load_runs(app, conn)?;
-                // Adjust selected index if needed
-                let state = &mut app.run_list_state;
-                if state.selected_index >= state.runs.len() && !state.runs.is_empty() {
-                    state.selected_index = state.runs.len() - 1;
+            if let Some((id, run)) = run.and_then(|run| run.id.map(|id| (id, run))) {
+                if let Err(e) = store.trash_run(id) {
+                    app.status_message = Some(format!("Cannot move run to the trash: {}", e));
+                    return Ok(());
                 }
+                app.history.record(Change::Delete(run));
+                // Reload the run list, leaving the selection on the next run
+                reload_runs_keeping_selection(app, store)?;
+            }
+        }
+        _ => {}
+    }
+
+    Ok(())
+}
+
+fn handle_run_detail_input(app: &mut App, key: KeyEvent, store: &dyn RunStore) -> Result<()> {
+    let Some(run) = app.run_detail_state.run.clone() else {
+        return Ok(());
+    };
+
+    match key.code {
+        KeyCode::Char('l') => {
+            let state = &mut app.run_detail_state;
+            state.editor = Some(LapEditor::new(&state.splits, app.units));
+            state.message = None;
+        }
+        KeyCode::Char('e') => {
+            app.quick_entry_state.load_run(&run, app.units);
+            app.switch_to_screen(Screen::QuickEntry);
+        }
+        KeyCode::Backspace | KeyCode::Char('b') => {
+            reload_runs_keeping_selection(app, store)?;
+            app.switch_to_screen(Screen::RunList);
+        }
+        _ => {}
+    }
+
+    Ok(())
+}
+
+fn handle_run_history_input(app: &mut App, key: KeyEvent, store: &dyn RunStore) -> Result<()> {
+    match key.code {
+        KeyCode::Up => {
+            let state = &mut app.run_history_state;
+            state.selected_index = state.selected_index.saturating_sub(1);
+        }
+        KeyCode::Down => {
+            let state = &mut app.run_history_state;
+            if state.selected_index + 1 < state.versions.len() {
+                state.selected_index += 1;
             }
         }
+        KeyCode::Char('r') => revert_run(app, store)?,
+        KeyCode::Backspace | KeyCode::Char('b') => {
+            reload_runs_keeping_selection(app, store)?;
+            app.switch_to_screen(Screen::RunList);
+        }
         _ => {}
     }
 
     Ok(())
 }
 
-fn load_runs(app: &mut App, conn: &Connection) -> Result<()> {
-    let runs = db::queries::get_all_runs(conn)?;
-    app.run_list_state.runs = runs;
-    app.run_list_state.selected_index = 0;
-    app.run_list_state.scroll_offset = 0;
+/// Puts the run back the way it was after the selected change. The revert is
+/// an ordinary edit, so it can be undone and shows up in the history itself.
+fn revert_run(app: &mut App, store: &dyn RunStore) -> Result<()> {
+    let state = &app.run_history_state;
+    let (Some(current), Some(version)) = (state.run.clone(), state.selected().cloned()) else {
+        return Ok(());
+    };
+    let Some(ref after) = version.after else {
+        app.run_history_state.message =
+            Some("That change has no version to go back to".to_string());
+        return Ok(());
+    };
+    if version.matches(&current) {
+        app.run_history_state.message = Some("The run already matches this version".to_string());
+        return Ok(());
+    }
+
+    let mut reverted = Run {
+        id: current.id,
+        uuid: current.uuid,
+        created_at: current.created_at,
+        deleted_at: current.deleted_at,
+        ..after.clone()
+    };
+    // Shoes and routes deleted since then can't be linked again
+    if app::gear_name(&app.gear_state.gear, reverted.gear_id).is_none() {
+        reverted.gear_id = None;
+    }
+    if !app
+        .routes_state
+        .routes
+        .iter()
+        .any(|route| route.id.is_some() && route.id == reverted.route_id)
+    {
+        reverted.route_id = None;
+    }
+    if store
+        .find_run_at(
+            reverted.athlete_id,
+            reverted.date,
+            reverted.time_started,
+            reverted.id,
+        )?
+        .is_some()
+    {
+        app.run_history_state.message = Some(format!(
+            "Can't revert: another run already starts at {} {}",
+            validation::format_date(&reverted.date),
+            validation::format_time(&reverted.time_started)
+        ));
+        return Ok(());
+    }
+
+    store.update_run(&reverted)?;
+    app.history.record(Change::Update {
+        before: current,
+        after: reverted.clone(),
+    });
+    refresh_after_change(app, store)?;
+    app.run_history_state.message = Some(format!(
+        "Reverted to the version from {}",
+        version
+            .changed_at
+            .with_timezone(&chrono::Local)
+            .format("%Y-%m-%d %H:%M")
+    ));
+    Ok(())
+}
+
+fn handle_lap_editor_input(app: &mut App, key: KeyEvent, store: &dyn RunStore) -> Result<()> {
+    let state = &mut app.run_detail_state;
+    let (Some(editor), Some(run_id)) = (
+        state.editor.as_mut(),
+        state.run.as_ref().and_then(|run| run.id),
+    ) else {
+        return Ok(());
+    };
+
+    match key.code {
+        KeyCode::Enter => match editor.build_splits(app.units) {
+            Ok(splits) => {
+                store.set_splits(run_id, &splits)?;
+                state.message = Some(format!("Saved {} laps", splits.len()));
+                state.splits = splits;
+                state.editor = None;
+            }
+            Err(e) => editor.error_message = Some(e.to_string()),
+        },
+        KeyCode::Esc => {
+            state.editor = None;
+        }
+        KeyCode::Char('d') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+            editor.remove_selected();
+        }
+        KeyCode::Tab if key.modifiers.contains(KeyModifiers::SHIFT) => editor.prev_field(),
+        KeyCode::Tab => editor.next_field(),
+        KeyCode::BackTab => editor.prev_field(),
+        KeyCode::Up => editor.select_previous(),
+        KeyCode::Down => editor.select_next(),
+        KeyCode::Char(c) => editor.current_input_mut().push(c),
+        KeyCode::Backspace => {
+            editor.current_input_mut().pop();
+        }
+        _ => {}
+    }
+
     Ok(())
 }
 
-fn load_analytics(app: &mut App, conn: &Connection) -> Result<()> {
-    let runs = db::queries::get_all_runs(conn)?;
-    let analytics = logic::streak::calculate_analytics(&runs);
-    app.analytics_state.analytics = analytics;
+fn handle_search_input(app: &mut App, key: KeyEvent, store: &dyn RunStore) -> Result<()> {
+    let state = &mut app.run_list_state;
+    let Some(input) = state.search_input.as_mut() else {
+        return Ok(());
+    };
+
+    match key.code {
+        KeyCode::Enter => {
+            // An empty search clears the filter
+            state.search = SearchQuery::parse(input);
+            state.search_input = None;
+            load_runs(app, store)?;
+        }
+        KeyCode::Esc => {
+            state.search_input = None;
+        }
+        KeyCode::Char(c) => input.push(c),
+        KeyCode::Backspace => {
+            input.pop();
+        }
+        _ => {}
+    }
+
     Ok(())
 }
+
+fn handle_trash_input(app: &mut App, key: KeyEvent, store: &dyn RunStore) -> Result<()> {
+    // Emptying the trash is permanent, so it needs a second keypress to confirm
+    if app.trash_state.confirm_empty {
+        app.trash_state.confirm_empty = false;
+        if key.code == KeyCode::Char('y') {
+            let removed = store.empty_trash(app.athlete_id)?;
+            load_trash(app, store)?;
+            app.trash_state.message = Some(format!("Permanently deleted {} runs", removed));
+        } else {
+            app.trash_state.message = Some("Empty trash cancelled".to_string());
+        }
+        return Ok(());
+    }
+
+    match key.code {
+        KeyCode::Up => {
+            let state = &mut app.trash_state;
+            if state.selected_index > 0 {
+                state.selected_index -= 1;
+                if state.selected_index < state.scroll_offset {
+                    state.scroll_offset = state.selected_index;
+                }
+            }
+        }
+        KeyCode::Down => {
+            let state = &mut app.trash_state;
+            if state.selected_index + 1 < state.runs.len() {
+                state.selected_index += 1;
+                if state.selected_index >= state.scroll_offset + 10 {
+                    state.scroll_offset = state.selected_index - 9;
+                }
+            }
+        }
+        KeyCode::Char('r') => {
+            // Restore selected run
+            let run_id = {
+                let state = &app.trash_state;
+                state.runs.get(state.selected_index).and_then(|run| run.id)
+            };
+
+            if let Some(id) = run_id {
+                let message = match store.restore_run(id) {
+                    Ok(()) => "Run restored".to_string(),
+                    Err(e) => e.to_string(),
+                };
+                load_trash(app, store)?;
+                let state = &mut app.trash_state;
+                if state.selected_index >= state.runs.len() && !state.runs.is_empty() {
+                    state.selected_index = state.runs.len() - 1;
+                }
+                state.message = Some(message);
+            }
+        }
+        KeyCode::Char('X') if !app.trash_state.runs.is_empty() => {
+            app.trash_state.confirm_empty = true;
+            app.trash_state.message = Some(format!(
+                "Permanently delete {} runs? Press [y] to confirm, any other key to cancel",
+                app.trash_state.runs.len()
+            ));
+        }
+        _ => {}
+    }
+
+    Ok(())
+}
+
+fn handle_data_health_input(app: &mut App, key: KeyEvent, store: &dyn RunStore) -> Result<()> {
+    // Deleting a run needs a second keypress to confirm
+    if app.data_health_state.confirm_delete {
+        app.data_health_state.confirm_delete = false;
+        let selected = app.data_health_state.selected().map(|run| run.id);
+        match (key.code, selected) {
+            (KeyCode::Char('y'), Some(id)) => {
+                if let Err(e) = store.delete_quarantined_run(id) {
+                    app.data_health_state.message =
+                        Some(format!("Cannot delete run #{}: {}", id, e));
+                    return Ok(());
+                }
+                load_data_health(app, store)?;
+                refresh_after_change(app, store)?;
+                app.data_health_state.message = Some(format!("Deleted run #{}", id));
+            }
+            _ => app.data_health_state.message = Some("Delete cancelled".to_string()),
+        }
+        return Ok(());
+    }
+
+    match key.code {
+        KeyCode::Up => {
+            let state = &mut app.data_health_state;
+            state.selected_index = state.selected_index.saturating_sub(1);
+        }
+        KeyCode::Down => {
+            let state = &mut app.data_health_state;
+            if state.selected_index + 1 < state.runs.len() {
+                state.selected_index += 1;
+            }
+        }
+        KeyCode::Enter | KeyCode::Char('f') => {
+            let state = &mut app.data_health_state;
+            match state.selected() {
+                Some(run) if run.is_fixable() => {
+                    let value = if run.value == "(empty)" {
+                        String::new()
+                    } else {
+                        run.value.clone()
+                    };
+                    state.fix_input = Some(value);
+                    state.message = None;
+                }
+                Some(_) => {
+                    state.message =
+                        Some("This problem can't be fixed here; delete the run instead".to_string())
+                }
+                None => {}
+            }
+        }
+        KeyCode::Char('d') => {
+            if let Some(run) = app.data_health_state.selected() {
+                let message = format!(
+                    "Permanently delete run #{}? This can't be undone. Press [y] to confirm, any other key to cancel",
+                    run.id
+                );
+                app.data_health_state.confirm_delete = true;
+                app.data_health_state.message = Some(message);
+            }
+        }
+        KeyCode::Char('r') => {
+            load_data_health(app, store)?;
+            refresh_after_change(app, store)?;
+            let count = app.data_health_state.runs.len();
+            app.data_health_state.message =
+                Some(format!("Checked every run: {} unreadable", count));
+        }
+        _ => {}
+    }
+
+    Ok(())
+}
+
+fn handle_fix_input(app: &mut App, key: KeyEvent, store: &dyn RunStore) -> Result<()> {
+    let state = &mut app.data_health_state;
+    let (Some(input), Some(run)) = (
+        state.fix_input.as_mut(),
+        state.runs.get(state.selected_index),
+    ) else {
+        return Ok(());
+    };
+
+    match key.code {
+        KeyCode::Enter => {
+            let (id, column) = (run.id, run.column.unwrap_or_default());
+            match store.fix_quarantined_run(id, column, input) {
+                Ok(()) => {
+                    load_data_health(app, store)?;
+                    refresh_after_change(app, store)?;
+                    let still_bad = app.data_health_state.runs.iter().any(|run| run.id == id);
+                    app.data_health_state.message = Some(if still_bad {
+                        format!("Saved {}; run #{} still has a problem", column, id)
+                    } else {
+                        format!("Fixed run #{}; it's back in the Run List", id)
+                    });
+                }
+                Err(e) => state.message = Some(format!("{:#}", e)),
+            }
+        }
+        KeyCode::Esc => {
+            state.fix_input = None;
+            state.message = None;
+        }
+        KeyCode::Char(c) => input.push(c),
+        KeyCode::Backspace => {
+            input.pop();
+        }
+        _ => {}
+    }
+
+    Ok(())
+}
+
+fn handle_gear_input(app: &mut App, key: KeyEvent, store: &dyn RunStore) -> Result<()> {
+    // Deleting gear needs a second keypress to confirm
+    if app.gear_state.confirm_delete {
+        app.gear_state.confirm_delete = false;
+        let selected = app.gear_state.selected().cloned();
+        match (
+            key.code,
+            selected.and_then(|gear| gear.id.map(|id| (id, gear))),
+        ) {
+            (KeyCode::Char('y'), Some((id, gear))) => {
+                let deleted = store.delete_gear(id).and_then(|()| {
+                    if app.gear_state.default_gear_id == Some(id) {
+                        store.set_setting(DEFAULT_GEAR_SETTING, "")?;
+                    }
+                    Ok(())
+                });
+                if let Err(e) = deleted {
+                    app.gear_state.message = Some(format!("Cannot delete {}: {}", gear.name, e));
+                    return Ok(());
+                }
+                // The runs it was on have lost it
+                refresh_after_change(app, store)?;
+                app.gear_state.message = Some(format!("Deleted {}", gear.name));
+            }
+            _ => app.gear_state.message = Some("Delete cancelled".to_string()),
+        }
+        return Ok(());
+    }
+
+    match key.code {
+        KeyCode::Up => {
+            let state = &mut app.gear_state;
+            state.selected_index = state.selected_index.saturating_sub(1);
+        }
+        KeyCode::Down => {
+            let state = &mut app.gear_state;
+            if state.selected_index + 1 < state.gear.len() {
+                state.selected_index += 1;
+            }
+        }
+        KeyCode::Char('a') => {
+            app.gear_state.form = Some(GearForm::new(app.units));
+        }
+        KeyCode::Char('e') => {
+            if let Some(gear) = app.gear_state.selected() {
+                app.gear_state.form = Some(GearForm::edit(gear, app.units));
+            }
+        }
+        KeyCode::Char('d') => {
+            if let Some(gear) = app.gear_state.selected() {
+                let message = format!(
+                    "Delete {}? Its {} runs are kept without a shoe. Press [y] to confirm, any other key to cancel",
+                    gear.name, gear.run_count
+                );
+                app.gear_state.confirm_delete = true;
+                app.gear_state.message = Some(message);
+            }
+        }
+        KeyCode::Char('s') => {
+            if let Some(gear) = app.gear_state.selected().cloned() {
+                let message = if gear.retired {
+                    Ok(format!("{} is retired and can't be the default", gear.name))
+                } else if app.gear_state.default_gear_id == gear.id {
+                    store
+                        .set_setting(DEFAULT_GEAR_SETTING, "")
+                        .map(|()| format!("{} is no longer the default", gear.name))
+                } else {
+                    let id = gear.id.map(|id| id.to_string()).unwrap_or_default();
+                    store
+                        .set_setting(DEFAULT_GEAR_SETTING, &id)
+                        .map(|()| format!("{} is now the default for new runs", gear.name))
+                };
+                let message = match message {
+                    Ok(message) => message,
+                    Err(e) => {
+                        app.gear_state.message =
+                            Some(format!("Cannot change the default shoe: {}", e));
+                        return Ok(());
+                    }
+                };
+                load_gear(app, store)?;
+                app.quick_entry_state.gear_id = app.quick_entry_state.default_gear_id;
+                app.gear_state.message = Some(message);
+            }
+        }
+        KeyCode::Char('r') => {
+            if let Some(mut gear) = app.gear_state.selected().cloned() {
+                gear.retired = !gear.retired;
+                let updated = store.update_gear(&gear).and_then(|()| {
+                    if gear.retired && app.gear_state.default_gear_id == gear.id {
+                        store.set_setting(DEFAULT_GEAR_SETTING, "")?;
+                    }
+                    Ok(())
+                });
+                if let Err(e) = updated {
+                    app.gear_state.message = Some(format!("Cannot update {}: {}", gear.name, e));
+                    return Ok(());
+                }
+                load_gear(app, store)?;
+                app.gear_state.message = Some(if gear.retired {
+                    format!("Retired {}", gear.name)
+                } else {
+                    format!("{} is back in use", gear.name)
+                });
+            }
+        }
+        _ => {}
+    }
+
+    Ok(())
+}
+
+fn handle_gear_form_input(app: &mut App, key: KeyEvent, store: &dyn RunStore) -> Result<()> {
+    let Some(form) = app.gear_state.form.as_mut() else {
+        return Ok(());
+    };
+
+    match key.code {
+        KeyCode::Enter => {
+            let saved = form.build_gear(app.units).and_then(|gear| {
+                if gear.id.is_some() {
+                    store.update_gear(&gear)?;
+                } else {
+                    store.insert_gear(&gear)?;
+                }
+                Ok(gear)
+            });
+            match saved {
+                Ok(gear) => {
+                    app.gear_state.form = None;
+                    load_gear(app, store)?;
+                    app.gear_state.message = Some(format!("Saved {}", gear.name));
+                }
+                Err(e) => form.error_message = Some(e.to_string()),
+            }
+        }
+        KeyCode::Esc => {
+            app.gear_state.form = None;
+        }
+        KeyCode::Tab | KeyCode::BackTab => form.toggle_field(),
+        KeyCode::Char(c) => form.current_input_mut().push(c),
+        KeyCode::Backspace => {
+            form.current_input_mut().pop();
+        }
+        _ => {}
+    }
+
+    Ok(())
+}
+
+fn open_athlete_picker(app: &mut App, store: &dyn RunStore) -> Result<()> {
+    load_athletes(app, store)?;
+    let selected_index = app
+        .athletes
+        .iter()
+        .position(|athlete| athlete.id == Some(app.athlete_id))
+        .unwrap_or(0);
+    app.athlete_picker = Some(AthletePicker::new(selected_index));
+    Ok(())
+}
+
+fn handle_athlete_picker_input(app: &mut App, key: KeyEvent, store: &dyn RunStore) -> Result<()> {
+    let Some(picker) = app.athlete_picker.as_mut() else {
+        return Ok(());
+    };
+    let selected = app.athletes.get(picker.selected_index).cloned();
+
+    if let Some(ref mut name) = picker.name_input {
+        match key.code {
+            KeyCode::Enter => {
+                let renaming = picker.renaming;
+                let saved = Athlete::new(name).and_then(|mut athlete| {
+                    if renaming {
+                        athlete.id = selected.and_then(|selected| selected.id);
+                        store.update_athlete(&athlete)?;
+                    } else {
+                        athlete.id = Some(store.insert_athlete(&athlete)?);
+                    }
+                    Ok(athlete)
+                });
+                match saved {
+                    Ok(athlete) => {
+                        load_athletes(app, store)?;
+                        let selected_index = app
+                            .athletes
+                            .iter()
+                            .position(|other| other.id == athlete.id)
+                            .unwrap_or(0);
+                        let picker = app
+                            .athlete_picker
+                            .insert(AthletePicker::new(selected_index));
+                        picker.message = Some(if renaming {
+                            format!("Renamed to {}", athlete.name)
+                        } else {
+                            format!("Added {}; press Enter to switch to them", athlete.name)
+                        });
+                    }
+                    Err(e) => picker.message = Some(e.to_string()),
+                }
+            }
+            KeyCode::Esc => {
+                picker.name_input = None;
+                picker.message = None;
+            }
+            KeyCode::Char(c) => name.push(c),
+            KeyCode::Backspace => {
+                name.pop();
+            }
+            _ => {}
+        }
+        return Ok(());
+    }
+
+    match key.code {
+        KeyCode::Up => picker.selected_index = picker.selected_index.saturating_sub(1),
+        KeyCode::Down if picker.selected_index + 1 < app.athletes.len() => {
+            picker.selected_index += 1;
+        }
+        KeyCode::Char('a') => {
+            picker.name_input = Some(String::new());
+            picker.renaming = false;
+            picker.message = None;
+        }
+        KeyCode::Char('r') => {
+            if let Some(athlete) = selected {
+                picker.name_input = Some(athlete.name);
+                picker.renaming = true;
+                picker.message = None;
+            }
+        }
+        KeyCode::Enter => {
+            app.athlete_picker = None;
+            if let Some(Athlete {
+                id: Some(id), name, ..
+            }) = selected
+            {
+                if id != app.athlete_id {
+                    switch_athlete(app, store, id)?;
+                    app.status_message = Some(format!("Showing runs for {}", name));
+                }
+            }
+        }
+        KeyCode::Esc | KeyCode::Char('A') => app.athlete_picker = None,
+        _ => {}
+    }
+
+    Ok(())
+}
+
+/// Shows another athlete's runs and analytics, and logs new runs for them.
+/// The choice is remembered for next time.
+fn switch_athlete(app: &mut App, store: &dyn RunStore, athlete_id: i64) -> Result<()> {
+    store.set_setting(ATHLETE_SETTING, &athlete_id.to_string())?;
+    app.athlete_id = athlete_id;
+
+    // A run being edited or viewed belongs to the previous athlete
+    if app.quick_entry_state.is_editing() {
+        app.quick_entry_state.clear();
+    }
+    app.quick_entry_state.athlete_id = athlete_id;
+    if matches!(app.screen, Screen::RunDetail | Screen::RunHistory) {
+        app.switch_to_screen(Screen::RunList);
+    }
+
+    app.run_list_state.search = SearchQuery::default();
+    load_runs(app, store)?;
+    load_analytics(app, store)?;
+    load_trash(app, store)?;
+    Ok(())
+}
+
+fn handle_routes_input(app: &mut App, key: KeyEvent, store: &dyn RunStore) -> Result<()> {
+    // Deleting a route needs a second keypress to confirm
+    if app.routes_state.confirm_delete {
+        app.routes_state.confirm_delete = false;
+        let selected = app.routes_state.selected().cloned();
+        match (
+            key.code,
+            selected.and_then(|route| route.id.map(|id| (id, route))),
+        ) {
+            (KeyCode::Char('y'), Some((id, route))) => {
+                if let Err(e) = store.delete_route(id) {
+                    app.routes_state.message = Some(format!("Cannot delete {}: {}", route.name, e));
+                    return Ok(());
+                }
+                // The runs on it have lost it
+                refresh_after_change(app, store)?;
+                app.routes_state.message = Some(format!("Deleted {}", route.name));
+            }
+            _ => app.routes_state.message = Some("Delete cancelled".to_string()),
+        }
+        return Ok(());
+    }
+
+    match key.code {
+        KeyCode::Up => {
+            let state = &mut app.routes_state;
+            state.selected_index = state.selected_index.saturating_sub(1);
+        }
+        KeyCode::Down => {
+            let state = &mut app.routes_state;
+            if state.selected_index + 1 < state.routes.len() {
+                state.selected_index += 1;
+            }
+        }
+        KeyCode::Char('a') => {
+            app.routes_state.form = Some(RouteForm::new());
+        }
+        KeyCode::Char('e') => {
+            if let Some(route) = app.routes_state.selected() {
+                app.routes_state.form = Some(RouteForm::edit(route, app.units));
+            }
+        }
+        KeyCode::Char('d') => {
+            if let Some(route) = app.routes_state.selected() {
+                let message = format!(
+                    "Delete {}? Its {} runs are kept without a route. Press [y] to confirm, any other key to cancel",
+                    route.name, route.run_count
+                );
+                app.routes_state.confirm_delete = true;
+                app.routes_state.message = Some(message);
+            }
+        }
+        _ => {}
+    }
+
+    Ok(())
+}
+
+fn handle_route_form_input(app: &mut App, key: KeyEvent, store: &dyn RunStore) -> Result<()> {
+    let Some(form) = app.routes_state.form.as_mut() else {
+        return Ok(());
+    };
+
+    match key.code {
+        KeyCode::Enter => {
+            let saved = form.build_route(app.units).and_then(|route| {
+                if route.id.is_some() {
+                    store.update_route(&route)?;
+                } else {
+                    store.insert_route(&route)?;
+                }
+                Ok(route)
+            });
+            match saved {
+                Ok(route) => {
+                    app.routes_state.form = None;
+                    load_routes(app, store)?;
+                    app.routes_state.message = Some(format!("Saved {}", route.name));
+                }
+                Err(e) => form.error_message = Some(e.to_string()),
+            }
+        }
+        KeyCode::Esc => {
+            app.routes_state.form = None;
+        }
+        KeyCode::Tab if key.modifiers.contains(KeyModifiers::SHIFT) => form.prev_field(),
+        KeyCode::Tab => form.next_field(),
+        KeyCode::BackTab => form.prev_field(),
+        KeyCode::Char(c) => form.current_input_mut().push(c),
+        KeyCode::Backspace => {
+            form.current_input_mut().pop();
+        }
+        _ => {}
+    }
+
+    Ok(())
+}
+
+/// What saving from Quick Entry did, for the undo history and the message.
+struct SavedRun {
+    change: Change,
+    run: Run,
+    message: String,
+}
+
+/// Inserts a new run, or updates the run being edited.
+fn save_run(store: &dyn RunStore, mut run: Run) -> Result<SavedRun> {
+    match run.id {
+        Some(id) => {
+            // Keep the previous version for undo
+            let before = store.get_run(id)?;
+            store.update_run(&run)?;
+            Ok(SavedRun {
+                change: Change::Update {
+                    before,
+                    after: run.clone(),
+                },
+                run,
+                message: "Run updated successfully!".to_string(),
+            })
+        }
+        None => {
+            run.id = Some(store.insert_run(&run)?);
+            Ok(SavedRun {
+                change: Change::Insert(run.clone()),
+                run,
+                message: "Run logged successfully!".to_string(),
+            })
+        }
+    }
+}
+
+/// Records a save from Quick Entry. A new run clears the fields for the next
+/// entry; a finished edit returns to the Run List.
+fn finish_save(app: &mut App, store: &dyn RunStore, saved: Result<SavedRun>) -> Result<()> {
+    let state = &mut app.quick_entry_state;
+    let saved = match saved {
+        Ok(saved) => saved,
+        Err(e) => {
+            state.error_message = Some(format!("Database error: {}", e));
+            return Ok(());
+        }
+    };
+
+    app.history.record(saved.change);
+    let was_editing = state.editing_run_id.take().is_some();
+    if !was_editing {
+        // After insert, clear fields for next entry
+        state.route_id = None;
+        state.distance.clear();
+        state.duration.clear();
+        state.avg_heart_rate.clear();
+        state.max_heart_rate.clear();
+        state.effort.clear();
+        state.run_type = None;
+        state.gear_id = state.default_gear_id;
+        state.tags.clear();
+        state.note.clear();
+        state.focused_field = InputField::Distance;
+    }
+    if let Ok(tags) = store.get_all_tags() {
+        state.known_tags = tags;
+    }
+
+    // Shoe mileage changed, so refresh it and warn once a shoe is worn out
+    let mut message = saved.message;
+    load_gear(app, store)?;
+    load_routes(app, store)?;
+    if let Some(alert) = retirement_alert(app, saved.run.gear_id) {
+        message.push(' ');
+        message.push_str(&alert);
+    }
+    app.quick_entry_state.success_message = Some(message);
+
+    if was_editing {
+        if let Err(e) = load_runs(app, store) {
+            app.quick_entry_state.error_message = Some(format!("Failed to reload runs: {}", e));
+        } else {
+            app.switch_to_screen(Screen::RunList);
+        }
+    }
+
+    Ok(())
+}
+
+/// Keys for the duplicate date/time dialog: overwrite the existing run, merge
+/// the distance into it, shift the new run's start time, or cancel.
+fn handle_conflict_input(app: &mut App, key: KeyEvent, store: &dyn RunStore) -> Result<()> {
+    let state = &mut app.quick_entry_state;
+    let Some(conflict) = state.conflict.take() else {
+        return Ok(());
+    };
+
+    let saved = match key.code {
+        KeyCode::Char('o') => overwrite_run(store, &conflict),
+        KeyCode::Char('m') => merge_run(store, &conflict, app.units),
+        KeyCode::Char('s') => match next_free_start(store, &conflict.run) {
+            Ok(Some(time)) => shift_run(store, &conflict, time),
+            Ok(None) => {
+                state.error_message = Some(
+                    "No free start time is left on that day; change the date instead".to_string(),
+                );
+                return Ok(());
+            }
+            Err(e) => Err(e),
+        },
+        // Cancel returns to the form with everything still filled in
+        KeyCode::Char('c') | KeyCode::Esc => return Ok(()),
+        _ => {
+            state.conflict = Some(conflict);
+            return Ok(());
+        }
+    };
+
+    finish_save(app, store, saved)
+}
+
+/// Moves the existing run to the trash and saves the new one in its place.
+fn overwrite_run(store: &dyn RunStore, conflict: &RunConflict) -> Result<SavedRun> {
+    let existing_id = run_id(&conflict.existing)?;
+    store.trash_run(existing_id)?;
+
+    let saved = match save_run(store, conflict.run.clone()) {
+        Ok(saved) => saved,
+        Err(e) => {
+            // Put things back as they were
+            let _ = store.restore_run(existing_id);
+            return Err(e);
+        }
+    };
+
+    Ok(SavedRun {
+        change: Change::Batch(vec![
+            Change::Delete(conflict.existing.clone()),
+            saved.change,
+        ]),
+        message: format!(
+            "Replaced the run at {}",
+            conflict.existing.time_started.format("%H:%M:%S")
+        ),
+        run: saved.run,
+    })
+}
+
+/// Adds the new run's distance to the existing run. An edited run that was
+/// merged away goes to the trash.
+fn merge_run(
+    store: &dyn RunStore,
+    conflict: &RunConflict,
+    units: DistanceUnit,
+) -> Result<SavedRun> {
+    let merged = conflict.merged();
+    store.update_run(&merged)?;
+    let mut changes = vec![Change::Update {
+        before: conflict.existing.clone(),
+        after: merged.clone(),
+    }];
+
+    if let Some(id) = conflict.run.id {
+        let trashed = store
+            .get_run(id)
+            .and_then(|edited| store.trash_run(id).map(|()| edited));
+        match trashed {
+            Ok(edited) => changes.push(Change::Delete(edited)),
+            Err(e) => {
+                // Put things back as they were
+                let _ = store.update_run(&conflict.existing);
+                return Err(e);
+            }
+        }
+    }
+
+    Ok(SavedRun {
+        change: Change::Batch(changes),
+        message: format!(
+            "Added {} to the run at {}",
+            validation::format_distance(conflict.run.distance_miles, units),
+            merged.time_started.format("%H:%M:%S")
+        ),
+        run: merged,
+    })
+}
+
+/// Saves the new run at `time` instead of the taken start time.
+fn shift_run(
+    store: &dyn RunStore,
+    conflict: &RunConflict,
+    time: chrono::NaiveTime,
+) -> Result<SavedRun> {
+    let mut run = conflict.run.clone();
+    run.time_started = time;
+    let saved = save_run(store, run)?;
+    Ok(SavedRun {
+        message: format!(
+            "{} at {} ({} was taken)",
+            saved.message.trim_end_matches(" successfully!"),
+            time.format("%H:%M:%S"),
+            conflict.existing.time_started.format("%H:%M:%S")
+        ),
+        ..saved
+    })
+}
+
+/// The first minute after the run's start time with no other run, if there
+/// is one before midnight.
+fn next_free_start(store: &dyn RunStore, run: &Run) -> Result<Option<chrono::NaiveTime>> {
+    let mut time = run.time_started;
+    loop {
+        let (next, wrapped) = time.overflowing_add_signed(chrono::Duration::minutes(1));
+        if wrapped != 0 {
+            return Ok(None);
+        }
+        if store
+            .find_run_at(run.athlete_id, run.date, next, run.id)?
+            .is_none()
+        {
+            return Ok(Some(next));
+        }
+        time = next;
+    }
+}
+
+/// A warning for when the shoe a run was just logged against has reached
+/// its retirement distance.
+fn retirement_alert(app: &App, gear_id: Option<i64>) -> Option<String> {
+    let gear = app
+        .gear_state
+        .gear
+        .iter()
+        .find(|gear| gear.id.is_some() && gear.id == gear_id)?;
+    if !gear.is_due_for_retirement() {
+        return None;
+    }
+
+    Some(format!(
+        "{} has reached {} (retire at {}).",
+        gear.name,
+        validation::format_distance(gear.total_miles, app.units),
+        validation::format_distance(gear.retire_at_miles, app.units)
+    ))
+}
+
+fn undo(app: &mut App, store: &dyn RunStore) -> Result<()> {
+    let Some(change) = app.history.pop_undo() else {
+        app.status_message = Some("Nothing to undo".to_string());
+        return Ok(());
+    };
+
+    let result = undo_change(store, &change);
+
+    match result {
+        Ok(()) => {
+            app.status_message = Some(format!("Undid {}", change.description()));
+            app.history.push_redo(change);
+        }
+        Err(e) => {
+            app.status_message = Some(format!("Cannot undo {}: {}", change.description(), e));
+            app.history.push_undo(change);
+        }
+    }
+
+    refresh_after_change(app, store)
+}
+
+fn redo(app: &mut App, store: &dyn RunStore) -> Result<()> {
+    let Some(change) = app.history.pop_redo() else {
+        app.status_message = Some("Nothing to redo".to_string());
+        return Ok(());
+    };
+
+    let result = redo_change(store, &change);
+
+    match result {
+        Ok(()) => {
+            app.status_message = Some(format!("Redid {}", change.description()));
+            app.history.push_undo(change);
+        }
+        Err(e) => {
+            app.status_message = Some(format!("Cannot redo {}: {}", change.description(), e));
+            app.history.push_redo(change);
+        }
+    }
+
+    refresh_after_change(app, store)
+}
+
+/// Replays the inverse of a change against the store.
+fn undo_change(store: &dyn RunStore, change: &Change) -> Result<()> {
+    match change {
+        Change::Insert(run) => run_id(run).and_then(|id| store.trash_run(id)),
+        Change::Update { before, .. } => store.update_run(before),
+        Change::Delete(run) => run_id(run).and_then(|id| store.restore_run(id)),
+        Change::Batch(changes) => {
+            for (done, change) in changes.iter().rev().enumerate() {
+                if let Err(e) = undo_change(store, change) {
+                    // Redo the steps already undone so the batch is all or nothing
+                    let undone = &changes[changes.len() - done..];
+                    undone
+                        .iter()
+                        .try_for_each(|change| redo_change(store, change))
+                        .context("The batch was left partly undone")?;
+                    return Err(e);
+                }
+            }
+            Ok(())
+        }
+    }
+}
+
+/// Applies a change that was undone again.
+fn redo_change(store: &dyn RunStore, change: &Change) -> Result<()> {
+    match change {
+        Change::Insert(run) => run_id(run).and_then(|id| store.restore_run(id)),
+        Change::Update { after, .. } => store.update_run(after),
+        Change::Delete(run) => run_id(run).and_then(|id| store.trash_run(id)),
+        Change::Batch(changes) => {
+            for (done, change) in changes.iter().enumerate() {
+                if let Err(e) = redo_change(store, change) {
+                    // Undo the steps already redone so the batch is all or nothing
+                    changes[..done]
+                        .iter()
+                        .rev()
+                        .try_for_each(|change| undo_change(store, change))
+                        .context("The batch was left partly redone")?;
+                    return Err(e);
+                }
+            }
+            Ok(())
+        }
+    }
+}
+
+fn run_id(run: &Run) -> Result<i64> {
+    run.id.context("Run has no id")
+}
+
+/// Reloads every screen's data after an undo or redo so nothing shows stale runs.
+fn refresh_after_change(app: &mut App, store: &dyn RunStore) -> Result<()> {
+    reload_runs_keeping_selection(app, store)?;
+
+    load_analytics(app, store)?;
+    app.quick_entry_state.known_tags = store.get_all_tags()?;
+    load_gear(app, store)?;
+    load_routes(app, store)?;
+
+    if app.screen == Screen::RunDetail {
+        // The run may have been trashed or changed by the undo
+        let run = app
+            .run_detail_state
+            .run
+            .as_ref()
+            .and_then(|run| run.id)
+            .and_then(|id| store.get_run(id).ok())
+            .filter(|run| run.deleted_at.is_none());
+        match run {
+            Some(run) => open_run_detail(app, store, run, false)?,
+            None => app.switch_to_screen(Screen::RunList),
+        }
+    }
+
+    if app.screen == Screen::RunHistory {
+        // Undo and revert both change the run and add to its history
+        let run = app
+            .run_history_state
+            .run
+            .as_ref()
+            .and_then(|run| run.id)
+            .and_then(|id| store.get_run(id).ok());
+        match run {
+            Some(run) => {
+                let selected_index = app.run_history_state.selected_index;
+                let message = app.run_history_state.message.take();
+                open_run_history(app, store, run)?;
+                let state = &mut app.run_history_state;
+                state.selected_index = selected_index.min(state.versions.len().saturating_sub(1));
+                state.message = message;
+            }
+            None => app.switch_to_screen(Screen::RunList),
+        }
+    }
+
+    if app.screen == Screen::Trash {
+        let message = app.trash_state.message.take();
+        load_trash(app, store)?;
+        app.trash_state.message = message;
+    }
+
+    Ok(())
+}
+
+/// Loads the first page of the Run List, and counts every matching run so
+/// the total is right before the rest are fetched.
+fn load_runs(app: &mut App, store: &dyn RunStore) -> Result<()> {
+    let state = &mut app.run_list_state;
+    state.total_runs = store.count_runs(app.athlete_id, &state.search)?;
+    state.runs = store.get_runs_page(app.athlete_id, &state.search, None, RUN_PAGE_SIZE)?;
+    state.selected_index = 0;
+    state.scroll_offset = 0;
+    Ok(())
+}
+
+/// Appends the page after the last loaded run.
+fn load_more_runs(app: &mut App, store: &dyn RunStore) -> Result<()> {
+    let state = &mut app.run_list_state;
+    let after = state.runs.last().and_then(|run| run.cursor());
+    let page = store.get_runs_page(app.athlete_id, &state.search, after.as_ref(), RUN_PAGE_SIZE)?;
+    if page.is_empty() {
+        // Runs were removed since they were counted
+        state.total_runs = state.runs.len();
+    }
+    state.runs.extend(page);
+    Ok(())
+}
+
+/// Reloads the Run List, fetching as many pages as it takes to keep the
+/// selection where it was.
+fn reload_runs_keeping_selection(app: &mut App, store: &dyn RunStore) -> Result<()> {
+    let selected_index = app.run_list_state.selected_index;
+    let scroll_offset = app.run_list_state.scroll_offset;
+    load_runs(app, store)?;
+    while app.run_list_state.runs.len() <= selected_index && app.run_list_state.has_more() {
+        load_more_runs(app, store)?;
+    }
+
+    let state = &mut app.run_list_state;
+    state.selected_index = selected_index.min(state.runs.len().saturating_sub(1));
+    state.scroll_offset = scroll_offset.min(state.selected_index);
+    Ok(())
+}
+
+fn load_analytics(app: &mut App, store: &dyn RunStore) -> Result<()> {
+    let athlete_id = app.athlete_id;
+    let daily_totals = store.get_daily_totals(athlete_id)?;
+    // Today is taken in the zone of the latest run, so it moves with the
+    // runner when they travel
+    let latest_run = store
+        .get_runs_page(athlete_id, &SearchQuery::default(), None, 1)?
+        .pop();
+    let today = logic::streak::today(latest_run.as_ref());
+    // Only recent runs are read one by one; runs logged ahead of today are
+    // included so they count the same way as in the totals
+    let last_day = daily_totals.last().map_or(today, |day| day.date.max(today));
+    let recent_runs = store.get_runs_by_date_range(
+        athlete_id,
+        logic::streak::detail_window_start(today),
+        last_day,
+    )?;
+    let analytics = logic::streak::calculate_analytics(
+        &daily_totals,
+        &recent_runs,
+        store.get_tag_totals(athlete_id)?,
+        today,
+    );
+    app.analytics_state.analytics = analytics;
+    Ok(())
+}
+
+/// Checks every run again and lists the ones that can't be read.
+fn load_data_health(app: &mut App, store: &dyn RunStore) -> Result<()> {
+    store.quarantine_bad_runs()?;
+    let state = &mut app.data_health_state;
+    state.runs = store.get_quarantined_runs()?;
+    state.selected_index = state.selected_index.min(state.runs.len().saturating_sub(1));
+    state.fix_input = None;
+    state.confirm_delete = false;
+    state.message = None;
+    Ok(())
+}
+
+fn load_trash(app: &mut App, store: &dyn RunStore) -> Result<()> {
+    let runs = store.get_trashed_runs(app.athlete_id)?;
+    app.trash_state.runs = runs;
+    app.trash_state.selected_index = 0;
+    app.trash_state.scroll_offset = 0;
+    app.trash_state.confirm_empty = false;
+    app.trash_state.message = None;
+    Ok(())
+}
+
+fn load_athletes(app: &mut App, store: &dyn RunStore) -> Result<()> {
+    app.athletes = store.get_athletes()?;
+    Ok(())
+}
+
+/// Loads gear with its mileage, and the default shoe if it still exists and
+/// is in use. Keeps the selection where it was.
+fn load_gear(app: &mut App, store: &dyn RunStore) -> Result<()> {
+    let gear = store.get_gear()?;
+    let default_gear_id = store
+        .get_setting(DEFAULT_GEAR_SETTING)?
+        .and_then(|value| value.parse::<i64>().ok())
+        .filter(|&id| gear.iter().any(|g| g.id == Some(id) && !g.retired));
+
+    let state = &mut app.gear_state;
+    state.selected_index = state.selected_index.min(gear.len().saturating_sub(1));
+    state.default_gear_id = default_gear_id;
+    state.confirm_delete = false;
+    state.gear = gear.clone();
+
+    app.quick_entry_state.gear_options = gear;
+    app.quick_entry_state.default_gear_id = default_gear_id;
+    Ok(())
+}
+
+/// Loads routes with their statistics, keeping the selection where it was.
+fn load_routes(app: &mut App, store: &dyn RunStore) -> Result<()> {
+    let routes = store.get_routes()?;
+
+    let state = &mut app.routes_state;
+    state.selected_index = state.selected_index.min(routes.len().saturating_sub(1));
+    state.confirm_delete = false;
+    state.routes = routes.clone();
+
+    app.quick_entry_state.route_options = routes;
+    Ok(())
+}
+
+fn open_run_history(app: &mut App, store: &dyn RunStore, run: Run) -> Result<()> {
+    let state = &mut app.run_history_state;
+    state.versions = store.get_run_history(run_id(&run)?)?;
+    state.selected_index = 0;
+    state.run = Some(run);
+    state.message = None;
+
+    app.switch_to_screen(Screen::RunHistory);
+    Ok(())
+}
+
+fn open_run_detail(app: &mut App, store: &dyn RunStore, run: Run, edit_laps: bool) -> Result<()> {
+    let splits = store.get_splits(run_id(&run)?)?;
+
+    let state = &mut app.run_detail_state;
+    state.gear_name = app::gear_name(&app.gear_state.gear, run.gear_id).map(str::to_string);
+    state.route_name = run.route_id.and_then(|id| {
+        app.routes_state
+            .routes
+            .iter()
+            .find(|route| route.id == Some(id))
+            .map(|route| route.name.clone())
+    });
+    state.editor = edit_laps.then(|| LapEditor::new(&splits, app.units));
+    state.splits = splits;
+    state.run = Some(run);
+    state.message = None;
+
+    app.switch_to_screen(Screen::RunDetail);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use models::athlete::DEFAULT_ATHLETE_ID;
+    use models::gear::Gear;
+    use models::health::QuarantinedRun;
+
+    fn press(app: &mut App, store: &MemoryStore, code: KeyCode) {
+        handle_key_event(app, KeyEvent::new(code, KeyModifiers::NONE), store).unwrap();
+    }
+
+    fn press_ctrl(app: &mut App, store: &MemoryStore, c: char) {
+        let key = KeyEvent::new(KeyCode::Char(c), KeyModifiers::CONTROL);
+        handle_key_event(app, key, store).unwrap();
+    }
+
+    fn type_text(app: &mut App, store: &MemoryStore, text: &str) {
+        for c in text.chars() {
+            press(app, store, KeyCode::Char(c));
+        }
+    }
+
+    /// Logs a run of `distance` miles from Quick Entry, starting now.
+    fn log_run(app: &mut App, store: &MemoryStore, distance: &str) {
+        type_text(app, store, distance);
+        press(app, store, KeyCode::Enter);
+        assert_eq!(app.quick_entry_state.error_message, None);
+    }
+
+    fn distances(store: &MemoryStore) -> Vec<f64> {
+        let runs = store.get_all_runs(DEFAULT_ATHLETE_ID).unwrap();
+        runs.iter().map(|run| run.distance_miles).collect()
+    }
+
+    fn trashed(store: &MemoryStore) -> usize {
+        store.get_trashed_runs(DEFAULT_ATHLETE_ID).unwrap().len()
+    }
+
+    /// Stores a 3 mile run at 07:00 and leaves a 2 mile run for the same
+    /// start waiting on the conflict dialog.
+    fn open_conflict(app: &mut App, store: &MemoryStore) {
+        let date = chrono::NaiveDate::from_ymd_opt(2024, 5, 1).unwrap();
+        let time = chrono::NaiveTime::from_hms_opt(7, 0, 0).unwrap();
+        let mut existing = Run::new(date, time, 3.0, Some(1800), None).unwrap();
+        existing.id = Some(store.insert_run(&existing).unwrap());
+        let run = Run::new(date, time, 2.0, Some(1200), None).unwrap();
+        app.quick_entry_state.conflict = Some(RunConflict { run, existing });
+    }
+
+    #[test]
+    fn undo_and_redo_an_insert() {
+        let store = MemoryStore::new();
+        let mut app = load_app(&store, None).unwrap();
+
+        log_run(&mut app, &store, "5");
+        assert_eq!(distances(&store), vec![5.0]);
+
+        press_ctrl(&mut app, &store, 'z');
+        assert_eq!(distances(&store), Vec::<f64>::new());
+        assert_eq!(trashed(&store), 1);
+
+        press_ctrl(&mut app, &store, 'r');
+        assert_eq!(distances(&store), vec![5.0]);
+        assert_eq!(trashed(&store), 0);
+        assert_eq!(app.run_list_state.runs.len(), 1);
+    }
+
+    #[test]
+    fn trash_and_restore_from_the_run_list() {
+        let store = MemoryStore::new();
+        let mut app = load_app(&store, None).unwrap();
+        log_run(&mut app, &store, "3.1");
+
+        press(&mut app, &store, KeyCode::Esc);
+        press(&mut app, &store, KeyCode::Char('2'));
+        assert_eq!(app.screen, Screen::RunList);
+        press(&mut app, &store, KeyCode::Char('d'));
+        assert_eq!(distances(&store), Vec::<f64>::new());
+        assert!(app.run_list_state.runs.is_empty());
+
+        press(&mut app, &store, KeyCode::Char('4'));
+        assert_eq!(app.screen, Screen::Trash);
+        assert_eq!(app.trash_state.runs.len(), 1);
+        press(&mut app, &store, KeyCode::Char('r'));
+        assert_eq!(app.trash_state.message.as_deref(), Some("Run restored"));
+        assert_eq!(distances(&store), vec![3.1]);
+        assert_eq!(trashed(&store), 0);
+    }
+
+    #[test]
+    fn a_batch_that_fails_part_way_is_not_undone_at_all() {
+        let store = MemoryStore::new();
+        let mut app = load_app(&store, None).unwrap();
+        log_run(&mut app, &store, "4");
+        let before = store.get_all_runs(DEFAULT_ATHLETE_ID).unwrap().remove(0);
+        let after = Run {
+            distance_miles: 6.0,
+            ..before.clone()
+        };
+        store.update_run(&after).unwrap();
+
+        // Undone in reverse: the edit goes back, then restoring a run that
+        // doesn't exist fails
+        let missing = Run {
+            id: Some(999),
+            ..before.clone()
+        };
+        app.history.record(Change::Batch(vec![
+            Change::Delete(missing),
+            Change::Update { before, after },
+        ]));
+        press_ctrl(&mut app, &store, 'z');
+
+        assert!(app
+            .status_message
+            .as_deref()
+            .is_some_and(|message| message.starts_with("Cannot undo")));
+        assert_eq!(distances(&store), vec![6.0]);
+
+        // Still on the undo stack, so the next undo tries the whole batch again
+        press_ctrl(&mut app, &store, 'z');
+        assert_eq!(distances(&store), vec![6.0]);
+    }
+
+    #[test]
+    fn a_failed_trash_is_reported_without_leaving_the_run_list() {
+        let store = MemoryStore::new();
+        let mut app = load_app(&store, None).unwrap();
+        log_run(&mut app, &store, "3.1");
+        press(&mut app, &store, KeyCode::Esc);
+        press(&mut app, &store, KeyCode::Char('2'));
+
+        // Deleted behind the list's back, so trashing it fails
+        let id = app.run_list_state.runs[0].id.unwrap();
+        store.trash_run(id).unwrap();
+        store.empty_trash(DEFAULT_ATHLETE_ID).unwrap();
+        press(&mut app, &store, KeyCode::Char('d'));
+
+        assert_eq!(app.screen, Screen::RunList);
+        assert!(app
+            .status_message
+            .as_deref()
+            .is_some_and(|message| message.starts_with("Cannot move run to the trash")));
+        // Only the insert is there to undo
+        assert!(matches!(app.history.pop_undo(), Some(Change::Insert(_))));
+    }
+
+    #[test]
+    fn a_failed_delete_is_shown_on_the_data_health_screen() {
+        let store = MemoryStore::new();
+        let mut app = load_app(&store, None).unwrap();
+        app.switch_to_screen(Screen::DataHealth);
+        // Listed when the screen opened, but no longer quarantined
+        app.data_health_state.runs = vec![QuarantinedRun {
+            id: 7,
+            column: Some("date"),
+            value: "yesterday".to_string(),
+            reason: "not a valid date".to_string(),
+        }];
+        app.data_health_state.confirm_delete = true;
+        press(&mut app, &store, KeyCode::Char('y'));
+
+        assert_eq!(app.screen, Screen::DataHealth);
+        assert_eq!(
+            app.data_health_state.message.as_deref(),
+            Some("Cannot delete run #7: Run is no longer quarantined")
+        );
+    }
+
+    #[test]
+    fn a_failed_gear_change_is_shown_on_the_gear_screen() {
+        let store = MemoryStore::new();
+        let mut app = load_app(&store, None).unwrap();
+        let id = store
+            .insert_gear(&Gear::new("Pegasus", 400.0).unwrap())
+            .unwrap();
+        load_gear(&mut app, &store).unwrap();
+        app.switch_to_screen(Screen::Gear);
+
+        // Deleted behind the screen's back, so retiring it fails
+        store.delete_gear(id).unwrap();
+        press(&mut app, &store, KeyCode::Char('r'));
+
+        assert_eq!(app.screen, Screen::Gear);
+        assert_eq!(
+            app.gear_state.message.as_deref(),
+            Some("Cannot update Pegasus: Gear no longer exists")
+        );
+    }
+
+    #[test]
+    fn overwriting_a_conflict_replaces_the_other_run() {
+        let store = MemoryStore::new();
+        let mut app = load_app(&store, None).unwrap();
+        open_conflict(&mut app, &store);
+        press(&mut app, &store, KeyCode::Char('o'));
+
+        assert_eq!(distances(&store), vec![2.0]);
+        assert_eq!(trashed(&store), 1);
+        press_ctrl(&mut app, &store, 'z');
+        assert_eq!(distances(&store), vec![3.0]);
+    }
+
+    #[test]
+    fn merging_a_conflict_adds_to_the_other_run() {
+        let store = MemoryStore::new();
+        let mut app = load_app(&store, None).unwrap();
+        open_conflict(&mut app, &store);
+        press(&mut app, &store, KeyCode::Char('m'));
+
+        let runs = store.get_all_runs(DEFAULT_ATHLETE_ID).unwrap();
+        assert_eq!(runs.len(), 1);
+        assert_eq!(runs[0].distance_miles, 5.0);
+        assert_eq!(runs[0].duration_seconds, Some(3000));
+        press_ctrl(&mut app, &store, 'z');
+        assert_eq!(distances(&store), vec![3.0]);
+    }
+
+    #[test]
+    fn shifting_a_conflict_saves_at_the_next_free_minute() {
+        let store = MemoryStore::new();
+        let mut app = load_app(&store, None).unwrap();
+        open_conflict(&mut app, &store);
+        press(&mut app, &store, KeyCode::Char('s'));
+
+        let mut starts: Vec<_> = store
+            .get_all_runs(DEFAULT_ATHLETE_ID)
+            .unwrap()
+            .iter()
+            .map(|run| {
+                (
+                    run.time_started.format("%H:%M").to_string(),
+                    run.distance_miles,
+                )
+            })
+            .collect();
+        starts.sort_by(|a, b| a.0.cmp(&b.0));
+        assert_eq!(
+            starts,
+            vec![("07:00".to_string(), 3.0), ("07:01".to_string(), 2.0)]
+        );
+    }
+
+    #[test]
+    fn cancelling_a_conflict_saves_nothing() {
+        let store = MemoryStore::new();
+        let mut app = load_app(&store, None).unwrap();
+        open_conflict(&mut app, &store);
+        press(&mut app, &store, KeyCode::Char('c'));
+
+        assert!(app.quick_entry_state.conflict.is_none());
+        assert_eq!(distances(&store), vec![3.0]);
+    }
+}
@@ -1,3 +1,4 @@
+use crate::models::run::RunType;
 use chrono::NaiveDate;
 
 #[derive(Debug, Clone)]
@@ -16,7 +17,23 @@ pub struct Analytics {
     pub average_distance_this_week: f64,
     pub average_distance_this_month: f64,
     pub average_distance_this_year: f64,
+    pub average_pace_this_week: Option<f64>,
+    pub average_pace_this_month: Option<f64>,
+    pub average_pace_this_year: Option<f64>,
     pub monthly_breakdown: Vec<MonthlyData>,
+    pub tag_totals: Vec<TagTotal>,
+    pub type_breakdown_this_week: Vec<TypeTotal>,
+    pub type_breakdown_this_month: Vec<TypeTotal>,
+    pub weekly_trends: Vec<WeeklyData>,
+}
+
+/// Distance and number of runs on one day, as kept in the `daily_totals`
+/// table. Trashed runs are not included.
+#[derive(Debug, Clone)]
+pub struct DailyTotal {
+    pub date: NaiveDate,
+    pub distance_miles: f64,
+    pub run_count: u32,
 }
 
 #[derive(Debug, Clone)]
@@ -34,6 +51,32 @@ pub struct MonthlyData {
     pub average_distance: f64,
 }
 
+#[derive(Debug, Clone)]
+pub struct TagTotal {
+    pub name: String,
+    pub run_count: u32,
+    pub total_distance: f64,
+}
+
+/// Mileage, average perceived effort and average heart rate for a week
+/// starting on Monday. The averages only cover runs that recorded them.
+#[derive(Debug, Clone)]
+pub struct WeeklyData {
+    pub week_start: NaiveDate,
+    pub total_distance: f64,
+    pub run_count: u32,
+    pub average_effort: Option<f64>,
+    pub average_heart_rate: Option<f64>,
+}
+
+/// Runs of one type in a period. `run_type` is `None` for untyped runs.
+#[derive(Debug, Clone)]
+pub struct TypeTotal {
+    pub run_type: Option<RunType>,
+    pub run_count: u32,
+    pub total_distance: f64,
+}
+
 impl Analytics {
     pub fn empty() -> Self {
         Self {
@@ -51,7 +94,14 @@ impl Analytics {
             average_distance_this_week: 0.0,
             average_distance_this_month: 0.0,
             average_distance_this_year: 0.0,
+            average_pace_this_week: None,
+            average_pace_this_month: None,
+            average_pace_this_year: None,
             monthly_breakdown: Vec::new(),
+            tag_totals: Vec::new(),
+            type_breakdown_this_week: Vec::new(),
+            type_breakdown_this_month: Vec::new(),
+            weekly_trends: Vec::new(),
         }
     }
 }
@@ -0,0 +1,30 @@
+use anyhow::{anyhow, Result};
+
+/// The athlete every run logged before athletes were added belongs to,
+/// created by the migration that introduced them.
+pub const DEFAULT_ATHLETE_ID: i64 = 1;
+
+/// A runner whose runs are kept in the log. Runs, the trash and analytics are
+/// shown for one athlete at a time; gear and routes are shared. The run count
+/// is computed from the runs table when athletes are loaded.
+#[derive(Debug, Clone)]
+pub struct Athlete {
+    pub id: Option<i64>,
+    pub name: String,
+    pub run_count: u32,
+}
+
+impl Athlete {
+    pub fn new(name: &str) -> Result<Self> {
+        let name = name.trim();
+        if name.is_empty() {
+            return Err(anyhow!("Name is required"));
+        }
+
+        Ok(Self {
+            id: None,
+            name: name.to_string(),
+            run_count: 0,
+        })
+    }
+}
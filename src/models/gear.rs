@@ -0,0 +1,43 @@
+use anyhow::{anyhow, Result};
+
+/// Most running shoes are good for 300-500 miles.
+pub const DEFAULT_RETIRE_MILES: f64 = 400.0;
+
+/// A pair of shoes (or other gear) that runs can be logged against. The
+/// totals are computed from the runs table when gear is loaded.
+#[derive(Debug, Clone)]
+pub struct Gear {
+    pub id: Option<i64>,
+    pub name: String,
+    pub retire_at_miles: f64,
+    pub retired: bool,
+    pub total_miles: f64,
+    pub run_count: u32,
+}
+
+impl Gear {
+    pub fn new(name: &str, retire_at_miles: f64) -> Result<Self> {
+        let name = name.trim();
+        if name.is_empty() {
+            return Err(anyhow!("Name is required"));
+        }
+
+        if retire_at_miles <= 0.0 {
+            return Err(anyhow!("Retirement distance must be positive"));
+        }
+
+        Ok(Self {
+            id: None,
+            name: name.to_string(),
+            retire_at_miles,
+            retired: false,
+            total_miles: 0.0,
+            run_count: 0,
+        })
+    }
+
+    /// True once gear still in use has reached its retirement distance.
+    pub fn is_due_for_retirement(&self) -> bool {
+        !self.retired && self.total_miles >= self.retire_at_miles
+    }
+}
@@ -0,0 +1,19 @@
+/// A run whose row couldn't be read. It is kept out of the Run List and
+/// Analytics until the bad value is fixed or the row is deleted.
+#[derive(Debug, Clone)]
+pub struct QuarantinedRun {
+    pub id: i64,
+    /// The column holding the unreadable value, when it's known.
+    pub column: Option<&'static str>,
+    /// The stored value, shown as text.
+    pub value: String,
+    pub reason: String,
+}
+
+impl QuarantinedRun {
+    /// Only plain run columns can be corrected by hand.
+    pub fn is_fixable(&self) -> bool {
+        self.column
+            .is_some_and(|column| column != "id" && column != "tags")
+    }
+}
@@ -0,0 +1,73 @@
+use crate::models::run::Run;
+use chrono::{DateTime, Utc};
+
+/// What a recorded change did to a run. Stored as its lowercase name.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HistoryAction {
+    Insert,
+    Update,
+    Trash,
+    Restore,
+    Delete,
+}
+
+impl HistoryAction {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            HistoryAction::Insert => "insert",
+            HistoryAction::Update => "update",
+            HistoryAction::Trash => "trash",
+            HistoryAction::Restore => "restore",
+            HistoryAction::Delete => "delete",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        [
+            HistoryAction::Insert,
+            HistoryAction::Update,
+            HistoryAction::Trash,
+            HistoryAction::Restore,
+            HistoryAction::Delete,
+        ]
+        .into_iter()
+        .find(|action| action.as_str() == value)
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            HistoryAction::Insert => "Logged",
+            HistoryAction::Update => "Edited",
+            HistoryAction::Trash => "Trashed",
+            HistoryAction::Restore => "Restored",
+            HistoryAction::Delete => "Deleted",
+        }
+    }
+}
+
+/// One entry in a run's change history, with the run as it was before and
+/// after the change. `before` is empty for an insert and `after` for a
+/// permanent delete.
+#[derive(Debug, Clone)]
+pub struct RunVersion {
+    pub run_id: i64,
+    pub action: HistoryAction,
+    pub changed_at: DateTime<Utc>,
+    pub before: Option<Run>,
+    pub after: Option<Run>,
+}
+
+impl RunVersion {
+    /// True if the run's values are the same as after this change, whether
+    /// or not it has since been trashed or restored.
+    pub fn matches(&self, run: &Run) -> bool {
+        self.after.as_ref().is_some_and(|after| {
+            *run == Run {
+                uuid: run.uuid,
+                created_at: run.created_at,
+                deleted_at: run.deleted_at,
+                ..after.clone()
+            }
+        })
+    }
+}
@@ -1,2 +1,9 @@
 pub mod analytics;
+pub mod athlete;
+pub mod gear;
+pub mod health;
+pub mod history;
+pub mod route;
 pub mod run;
+pub mod split;
+pub mod units;
@@ -0,0 +1,37 @@
+use anyhow::{anyhow, Result};
+
+/// A regular route with its usual distance. The statistics are computed from
+/// the runs logged on the route when routes are loaded.
+#[derive(Debug, Clone)]
+pub struct Route {
+    pub id: Option<i64>,
+    pub name: String,
+    pub distance_miles: f64,
+    pub description: Option<String>,
+    pub run_count: u32,
+    pub best_duration_seconds: Option<u32>,
+    pub average_duration_seconds: Option<f64>,
+}
+
+impl Route {
+    pub fn new(name: &str, distance_miles: f64, description: Option<String>) -> Result<Self> {
+        let name = name.trim();
+        if name.is_empty() {
+            return Err(anyhow!("Name is required"));
+        }
+
+        if distance_miles <= 0.0 {
+            return Err(anyhow!("Distance must be positive"));
+        }
+
+        Ok(Self {
+            id: None,
+            name: name.to_string(),
+            distance_miles,
+            description,
+            run_count: 0,
+            best_duration_seconds: None,
+            average_duration_seconds: None,
+        })
+    }
+}
@@ -1,15 +1,110 @@
+use crate::models::athlete::DEFAULT_ATHLETE_ID;
+use crate::models::units::KM_PER_MILE;
 use anyhow::{anyhow, Result};
-use chrono::{DateTime, NaiveDate, NaiveTime, Utc};
+use chrono::{DateTime, FixedOffset, Local, NaiveDate, NaiveTime, Offset, TimeZone, Utc};
+use uuid::Uuid;
 
-#[derive(Debug, Clone)]
+const MAX_DISTANCE_MILES: f64 = 200.0;
+
+/// The kind of workout a run was. Stored as its lowercase name.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum RunType {
+    Easy,
+    Tempo,
+    Intervals,
+    Long,
+    Race,
+    Recovery,
+}
+
+impl RunType {
+    pub const ALL: [RunType; 6] = [
+        RunType::Easy,
+        RunType::Tempo,
+        RunType::Intervals,
+        RunType::Long,
+        RunType::Race,
+        RunType::Recovery,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            RunType::Easy => "easy",
+            RunType::Tempo => "tempo",
+            RunType::Intervals => "intervals",
+            RunType::Long => "long",
+            RunType::Race => "race",
+            RunType::Recovery => "recovery",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|t| t.as_str() == value)
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            RunType::Easy => "Easy",
+            RunType::Tempo => "Tempo",
+            RunType::Intervals => "Intervals",
+            RunType::Long => "Long",
+            RunType::Race => "Race",
+            RunType::Recovery => "Recovery",
+        }
+    }
+
+    /// Steps through the types for a selector, with `None` (untyped) between
+    /// the last type and the first.
+    pub fn cycle(current: Option<Self>, forward: bool) -> Option<Self> {
+        let position = current.and_then(|t| Self::ALL.iter().position(|&other| other == t));
+        let count = Self::ALL.len();
+        match (position, forward) {
+            (None, true) => Some(Self::ALL[0]),
+            (None, false) => Some(Self::ALL[count - 1]),
+            (Some(i), true) if i + 1 == count => None,
+            (Some(0), false) => None,
+            (Some(i), true) => Some(Self::ALL[i + 1]),
+            (Some(i), false) => Some(Self::ALL[i - 1]),
+        }
+    }
+}
+
+/// Where a run sits in the newest-first ordering of the Run List. The next
+/// page starts after it, so pages stay correct while runs are added.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RunCursor {
+    pub date: NaiveDate,
+    pub time_started: NaiveTime,
+    pub id: i64,
+}
+
+#[derive(Debug, Clone, PartialEq)]
 pub struct Run {
     #[allow(dead_code)]
     pub id: Option<i64>,
+    /// Identifies the run across databases and machines, unlike `id`. Given
+    /// when the run is first logged and never changed.
+    pub uuid: Uuid,
+    pub athlete_id: i64,
     pub date: NaiveDate,
     pub time_started: NaiveTime,
     pub distance_miles: f64,
+    pub duration_seconds: Option<u32>,
     pub note: Option<String>,
+    pub run_type: Option<RunType>,
+    pub gear_id: Option<i64>,
+    pub route_id: Option<i64>,
+    /// Beats per minute.
+    pub avg_heart_rate: Option<u32>,
+    pub max_heart_rate: Option<u32>,
+    /// Perceived effort from 1 (very easy) to 10 (all out).
+    pub effort: Option<u8>,
+    pub tags: Vec<String>,
+    /// The UTC offset where the run happened; `date` and `time_started` are
+    /// local to it. Runs logged before offsets were recorded have none.
+    pub utc_offset: Option<FixedOffset>,
     pub created_at: DateTime<Utc>,
+    pub deleted_at: Option<DateTime<Utc>>,
 }
 
 impl Run {
@@ -17,23 +112,78 @@ impl Run {
         date: NaiveDate,
         time: NaiveTime,
         distance: f64,
+        duration_seconds: Option<u32>,
         note: Option<String>,
     ) -> Result<Self> {
         if distance <= 0.0 {
             return Err(anyhow!("Distance must be positive"));
         }
 
-        if distance > 200.0 {
-            return Err(anyhow!("Distance seems unrealistic (>200 miles)"));
+        if distance > MAX_DISTANCE_MILES {
+            return Err(anyhow!(
+                "Distance seems unrealistic (>{:.0} miles / {:.0} km)",
+                MAX_DISTANCE_MILES,
+                MAX_DISTANCE_MILES * KM_PER_MILE
+            ));
+        }
+
+        if duration_seconds == Some(0) {
+            return Err(anyhow!("Duration must be positive"));
         }
 
         Ok(Self {
             id: None,
+            uuid: Uuid::new_v4(),
+            athlete_id: DEFAULT_ATHLETE_ID,
             date,
             time_started: time,
             distance_miles: distance,
+            duration_seconds,
             note,
+            run_type: None,
+            gear_id: None,
+            route_id: None,
+            avg_heart_rate: None,
+            max_heart_rate: None,
+            effort: None,
+            tags: Vec::new(),
+            utc_offset: None,
             created_at: Utc::now(),
+            deleted_at: None,
         })
     }
+
+    /// The cursor for fetching the runs listed after this one. Unsaved runs
+    /// have none.
+    pub fn cursor(&self) -> Option<RunCursor> {
+        self.id.map(|id| RunCursor {
+            date: self.date,
+            time_started: self.time_started,
+            id,
+        })
+    }
+
+    /// The UTC offset the run's date and time are in, taking this computer's
+    /// zone for runs that didn't record one.
+    pub fn offset(&self) -> FixedOffset {
+        self.utc_offset
+            .unwrap_or_else(|| local_offset(self.date, self.time_started))
+    }
+
+    /// Pace in seconds per mile, if the run has a recorded duration.
+    pub fn pace_seconds_per_mile(&self) -> Option<f64> {
+        self.duration_seconds
+            .map(|seconds| seconds as f64 / self.distance_miles)
+    }
+}
+
+/// The offset this computer's zone has at a local date and time. Times
+/// skipped by a daylight saving change take the offset from before it.
+pub fn local_offset(date: NaiveDate, time: NaiveTime) -> FixedOffset {
+    let local = date.and_time(time);
+    Local
+        .from_local_datetime(&local)
+        .earliest()
+        .map(|dt| dt.offset().fix())
+        .unwrap_or_else(|| Local.offset_from_utc_datetime(&local).fix())
 }
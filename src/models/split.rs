@@ -0,0 +1,37 @@
+/// One lap of a run. Laps are numbered from 1 in the order they were run.
+#[derive(Debug, Clone)]
+pub struct Split {
+    pub lap_number: u32,
+    pub distance_miles: f64,
+    pub duration_seconds: u32,
+}
+
+impl Split {
+    pub fn pace_seconds_per_mile(&self) -> f64 {
+        self.duration_seconds as f64 / self.distance_miles
+    }
+}
+
+/// Indexes of the fastest and slowest laps by pace, when there are at least
+/// two laps to compare.
+pub fn fastest_and_slowest(splits: &[Split]) -> Option<(usize, usize)> {
+    if splits.len() < 2 {
+        return None;
+    }
+
+    let by_pace = |a: &&Split, b: &&Split| {
+        a.pace_seconds_per_mile()
+            .total_cmp(&b.pace_seconds_per_mile())
+    };
+    let fastest = splits
+        .iter()
+        .enumerate()
+        .min_by(|a, b| by_pace(&a.1, &b.1))?
+        .0;
+    let slowest = splits
+        .iter()
+        .enumerate()
+        .max_by(|a, b| by_pace(&a.1, &b.1))?
+        .0;
+    Some((fastest, slowest))
+}
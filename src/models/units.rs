@@ -0,0 +1,66 @@
+pub const KM_PER_MILE: f64 = 1.609344;
+
+/// The unit distances are entered and displayed in. Distances are always
+/// stored in miles, so switching units never rewrites existing runs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DistanceUnit {
+    Miles,
+    Kilometers,
+}
+
+impl DistanceUnit {
+    pub fn label(self) -> &'static str {
+        match self {
+            DistanceUnit::Miles => "mi",
+            DistanceUnit::Kilometers => "km",
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            DistanceUnit::Miles => "miles",
+            DistanceUnit::Kilometers => "kilometres",
+        }
+    }
+
+    pub fn convert_miles(self, miles: f64) -> f64 {
+        match self {
+            DistanceUnit::Miles => miles,
+            DistanceUnit::Kilometers => miles * KM_PER_MILE,
+        }
+    }
+
+    pub fn to_miles(self, value: f64) -> f64 {
+        match self {
+            DistanceUnit::Miles => value,
+            DistanceUnit::Kilometers => value / KM_PER_MILE,
+        }
+    }
+
+    /// Converts a pace in seconds per mile to seconds per this unit.
+    pub fn pace_from_seconds_per_mile(self, seconds_per_mile: f64) -> f64 {
+        match self {
+            DistanceUnit::Miles => seconds_per_mile,
+            DistanceUnit::Kilometers => seconds_per_mile / KM_PER_MILE,
+        }
+    }
+
+    pub fn toggle(self) -> Self {
+        match self {
+            DistanceUnit::Miles => DistanceUnit::Kilometers,
+            DistanceUnit::Kilometers => DistanceUnit::Miles,
+        }
+    }
+
+    pub fn as_setting(self) -> &'static str {
+        self.label()
+    }
+
+    pub fn from_setting(value: &str) -> Option<Self> {
+        match value {
+            "mi" => Some(DistanceUnit::Miles),
+            "km" => Some(DistanceUnit::Kilometers),
+            _ => None,
+        }
+    }
+}
@@ -21,16 +21,32 @@ pub fn render(f: &mut Frame, app: &App) {
         ])
         .split(f.area());
 
-    render_header(f, chunks[0], app.screen);
+    render_header(f, chunks[0], app);
     render_screen(f, chunks[1], app);
-    render_footer(f, chunks[2], app.screen);
+    render_footer(f, chunks[2], app);
+
+    if let Some(ref picker) = app.athlete_picker {
+        screens::athlete_picker::render(f, chunks[1], picker, &app.athletes, app.athlete_id);
+    }
 }
 
-fn render_header(f: &mut Frame, area: Rect, current_screen: Screen) {
-    let tabs = vec![
+fn render_header(f: &mut Frame, area: Rect, app: &App) {
+    let current_screen = app.screen;
+    let tabs = [
         ("1", "Quick Entry", current_screen == Screen::QuickEntry),
-        ("2", "Run List", current_screen == Screen::RunList),
+        (
+            "2",
+            "Run List",
+            matches!(
+                current_screen,
+                Screen::RunList | Screen::RunDetail | Screen::RunHistory
+            ),
+        ),
         ("3", "Analytics", current_screen == Screen::Analytics),
+        ("4", "Trash", current_screen == Screen::Trash),
+        ("5", "Gear", current_screen == Screen::Gear),
+        ("6", "Routes", current_screen == Screen::Routes),
+        ("7", "Data Health", current_screen == Screen::DataHealth),
         ("h", "Help", current_screen == Screen::Help),
     ];
 
@@ -54,29 +70,100 @@ fn render_header(f: &mut Frame, area: Rect, current_screen: Screen) {
         })
         .collect();
 
-    let header = Paragraph::new(Line::from(spans))
-        .block(Block::default().borders(Borders::ALL).title("Run Logger"));
+    let mut block = Block::default()
+        .borders(Borders::ALL)
+        .title(header_title(app));
+    // Whose runs are shown, and how to switch
+    if let Some(name) = app.athlete_name() {
+        block = block.title(
+            Line::from(vec![
+                Span::styled(" Athlete: ", Style::default().fg(Color::Gray)),
+                Span::styled(
+                    name,
+                    Style::default()
+                        .fg(Color::Green)
+                        .add_modifier(Modifier::BOLD),
+                ),
+                Span::styled(" [A] Switch ", Style::default().fg(Color::Gray)),
+            ])
+            .right_aligned(),
+        );
+    }
+    let header = Paragraph::new(Line::from(spans)).block(block);
 
     f.render_widget(header, area);
 }
 
+/// Names the database in the title when it isn't the default `runs.db`, so
+/// it's obvious which profile or log is open.
+fn header_title(app: &App) -> String {
+    let Some(ref db_path) = app.db_path else {
+        return "Run Logger - in-memory (not saved)".to_string();
+    };
+
+    match db_path.file_stem().and_then(|stem| stem.to_str()) {
+        Some(stem) if stem != "runs" => format!("Run Logger - {}", stem),
+        _ => "Run Logger".to_string(),
+    }
+}
+
 fn render_screen(f: &mut Frame, area: Rect, app: &App) {
     match app.screen {
-        Screen::QuickEntry => screens::quick_entry::render(f, area, &app.quick_entry_state),
-        Screen::RunList => screens::run_list::render(f, area, &app.run_list_state),
-        Screen::Analytics => screens::analytics::render(f, area, &app.analytics_state),
-        Screen::Help => screens::help::render(f, area),
+        Screen::QuickEntry => {
+            screens::quick_entry::render(f, area, &app.quick_entry_state, app.units)
+        }
+        Screen::RunList => screens::run_list::render(
+            f,
+            area,
+            &app.run_list_state,
+            &app.gear_state.gear,
+            app.units,
+        ),
+        Screen::RunDetail => screens::run_detail::render(f, area, &app.run_detail_state, app.units),
+        Screen::RunHistory => screens::run_history::render(
+            f,
+            area,
+            &app.run_history_state,
+            &app.gear_state.gear,
+            &app.routes_state.routes,
+            app.units,
+        ),
+        Screen::Analytics => screens::analytics::render(f, area, &app.analytics_state, app.units),
+        Screen::Trash => screens::trash::render(f, area, &app.trash_state, app.units),
+        Screen::Gear => screens::gear::render(f, area, &app.gear_state, app.units),
+        Screen::Routes => screens::routes::render(f, area, &app.routes_state, app.units),
+        Screen::DataHealth => screens::data_health::render(f, area, &app.data_health_state),
+        Screen::Help => screens::help::render(f, area, app.db_path.as_deref()),
     }
 }
 
-fn render_footer(f: &mut Frame, area: Rect, current_screen: Screen) {
-    let footer_text = match current_screen {
+fn render_footer(f: &mut Frame, area: Rect, app: &App) {
+    // Transient status (e.g. the result of an undo) replaces the key hints
+    if let Some(ref status) = app.status_message {
+        let footer = Paragraph::new(status.as_str())
+            .style(Style::default().fg(Color::Yellow))
+            .block(Block::default().borders(Borders::ALL));
+        f.render_widget(footer, area);
+        return;
+    }
+
+    let footer_text = match app.screen {
         Screen::QuickEntry => {
-            "[Tab] Next  [Shift+Tab] Prev  [Enter] Submit  [Esc] Clear  [h] Help  [Ctrl+Q] Quit"
+            "[Tab] Next  [Shift+Tab] Prev  [Enter] Submit  [Esc] Clear  [Ctrl+Z] Undo  [Ctrl+Q] Quit"
+        }
+        Screen::RunList => "[↑↓] Navigate  [u] Undo  [Ctrl+R] Redo  [1-7] Switch screen  [m] Units  [h] Help  [q] Quit",
+        Screen::RunDetail => "[l] Laps  [e] Edit  [b] Back  [u] Undo  [1-7] Switch screen  [m] Units  [h] Help  [q] Quit",
+        Screen::RunHistory => "[↑↓] Navigate  [r] Revert  [b] Back  [u] Undo  [1-7] Switch screen  [m] Units  [h] Help  [q] Quit",
+        Screen::Analytics => "[u] Undo  [Ctrl+R] Redo  [1-7] Switch screen  [m] Units  [h] Help  [q] Quit",
+        Screen::Trash => {
+            "[↑↓] Navigate  [r] Restore  [X] Empty trash  [1-7] Switch screen  [q] Quit"
+        }
+        Screen::Gear => "[↑↓] Navigate  [a] Add  [s] Default  [r] Retire  [1-7] Switch screen  [m] Units  [q] Quit",
+        Screen::Routes => "[↑↓] Navigate  [a] Add  [e] Edit  [d] Delete  [1-7] Switch screen  [m] Units  [q] Quit",
+        Screen::DataHealth => {
+            "[↑↓] Navigate  [f] Fix  [d] Delete  [r] Re-check  [1-7] Switch screen  [q] Quit"
         }
-        Screen::RunList => "[↑↓] Navigate  [1-3] Switch screen  [h] Help  [q] Quit",
-        Screen::Analytics => "[1-3] Switch screen  [h] Help  [q] Quit",
-        Screen::Help => "[1-3] Switch screen  [q] Quit",
+        Screen::Help => "[1-7] Switch screen  [q] Quit",
     };
 
     let footer = Paragraph::new(footer_text)
@@ -1,4 +1,7 @@
 use crate::app::AnalyticsState;
+use crate::logic::streak::DAILY_GOAL_MILES;
+use crate::logic::validation;
+use crate::models::units::DistanceUnit;
 use crate::ui::theme;
 use ratatui::{
     layout::{Constraint, Direction, Layout, Rect},
@@ -8,7 +11,10 @@ use ratatui::{
     Frame,
 };
 
-pub fn render(f: &mut Frame, area: Rect, state: &AnalyticsState) {
+const MONTHLY_GREEN_MILES: f64 = 50.0;
+const MONTHLY_YELLOW_MILES: f64 = 25.0;
+
+pub fn render(f: &mut Frame, area: Rect, state: &AnalyticsState, units: DistanceUnit) {
     let chunks = Layout::default()
         .direction(Direction::Vertical)
         .margin(1)
@@ -16,17 +22,34 @@ pub fn render(f: &mut Frame, area: Rect, state: &AnalyticsState) {
             Constraint::Length(6),  // Streaks
             Constraint::Length(8),  // Stats
             Constraint::Length(12), // Last 7 Days
-            Constraint::Min(0),     // Monthly Totals
+            Constraint::Min(0),     // Monthly and tag totals
         ])
         .split(area);
 
-    render_streak(f, chunks[0], state);
-    render_stats(f, chunks[1], state);
-    render_chart(f, chunks[2], state);
-    render_monthly_breakdown(f, chunks[3], state);
+    let totals = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([
+            Constraint::Percentage(35),
+            Constraint::Percentage(40),
+            Constraint::Percentage(25),
+        ])
+        .split(chunks[3]);
+
+    render_streak(f, chunks[0], state, units);
+    render_stats(f, chunks[1], state, units);
+    let recent = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(60), Constraint::Percentage(40)])
+        .split(chunks[2]);
+
+    render_chart(f, recent[0], state, units);
+    render_type_breakdown(f, recent[1], state, units);
+    render_monthly_breakdown(f, totals[0], state, units);
+    render_weekly_trends(f, totals[1], state, units);
+    render_tag_totals(f, totals[2], state, units);
 }
 
-fn render_streak(f: &mut Frame, area: Rect, state: &AnalyticsState) {
+fn render_streak(f: &mut Frame, area: Rect, state: &AnalyticsState, units: DistanceUnit) {
     let streak_style = if state.analytics.current_streak > 0 {
         theme::goal_met_style()
     } else {
@@ -76,7 +99,7 @@ fn render_streak(f: &mut Frame, area: Rect, state: &AnalyticsState) {
         ]),
         Line::from(vec![
             Span::styled(
-                "Year Goal (365 days @ 1mi): ",
+                format!("Year Goal (365 days @ {}): ", goal_text(units)),
                 Style::default().fg(Color::Cyan),
             ),
             Span::styled(days_remaining_text, days_remaining_style),
@@ -89,7 +112,7 @@ fn render_streak(f: &mut Frame, area: Rect, state: &AnalyticsState) {
     f.render_widget(streak_block, area);
 }
 
-fn render_stats(f: &mut Frame, area: Rect, state: &AnalyticsState) {
+fn render_stats(f: &mut Frame, area: Rect, state: &AnalyticsState, units: DistanceUnit) {
     let chunks = Layout::default()
         .direction(Direction::Horizontal)
         .constraints([
@@ -107,14 +130,14 @@ fn render_stats(f: &mut Frame, area: Rect, state: &AnalyticsState) {
         Line::from(vec![
             Span::styled("Total Distance: ", Style::default().fg(Color::Gray)),
             Span::styled(
-                format!("{:.2} mi", state.analytics.total_distance),
+                validation::format_distance(state.analytics.total_distance, units),
                 theme::stat_style(),
             ),
         ]),
         Line::from(vec![
             Span::styled("Average: ", Style::default().fg(Color::Gray)),
             Span::styled(
-                format!("{:.2} mi", state.analytics.average_distance),
+                validation::format_distance(state.analytics.average_distance, units),
                 theme::stat_style(),
             ),
         ]),
@@ -135,7 +158,14 @@ fn render_stats(f: &mut Frame, area: Rect, state: &AnalyticsState) {
         Line::from(vec![
             Span::styled("Avg Distance: ", Style::default().fg(Color::Gray)),
             Span::styled(
-                format!("{:.2} mi", state.analytics.average_distance_this_week),
+                validation::format_distance(state.analytics.average_distance_this_week, units),
+                theme::stat_style(),
+            ),
+        ]),
+        Line::from(vec![
+            Span::styled("Avg Pace: ", Style::default().fg(Color::Gray)),
+            Span::styled(
+                pace_text(state.analytics.average_pace_this_week, units),
                 theme::stat_style(),
             ),
         ]),
@@ -156,7 +186,12 @@ fn render_stats(f: &mut Frame, area: Rect, state: &AnalyticsState) {
         Line::from(vec![
             Span::styled("Avg: ", Style::default().fg(Color::Gray)),
             Span::styled(
-                format!("{:.2} mi", state.analytics.average_distance_this_month),
+                validation::format_distance(state.analytics.average_distance_this_month, units),
+                theme::stat_style(),
+            ),
+            Span::styled("  Pace: ", Style::default().fg(Color::Gray)),
+            Span::styled(
+                pace_text(state.analytics.average_pace_this_month, units),
                 theme::stat_style(),
             ),
         ]),
@@ -170,7 +205,12 @@ fn render_stats(f: &mut Frame, area: Rect, state: &AnalyticsState) {
         Line::from(vec![
             Span::styled("Avg: ", Style::default().fg(Color::Gray)),
             Span::styled(
-                format!("{:.2} mi", state.analytics.average_distance_this_year),
+                validation::format_distance(state.analytics.average_distance_this_year, units),
+                theme::stat_style(),
+            ),
+            Span::styled("  Pace: ", Style::default().fg(Color::Gray)),
+            Span::styled(
+                pace_text(state.analytics.average_pace_this_year, units),
                 theme::stat_style(),
             ),
         ]),
@@ -181,7 +221,7 @@ fn render_stats(f: &mut Frame, area: Rect, state: &AnalyticsState) {
     f.render_widget(yearly_block, chunks[2]);
 }
 
-fn render_chart(f: &mut Frame, area: Rect, state: &AnalyticsState) {
+fn render_chart(f: &mut Frame, area: Rect, state: &AnalyticsState, units: DistanceUnit) {
     if state.analytics.recent_trend.is_empty() {
         let empty = Paragraph::new("No data to display")
             .block(Block::default().borders(Borders::ALL).title("Last 7 Days"))
@@ -202,7 +242,7 @@ fn render_chart(f: &mut Frame, area: Rect, state: &AnalyticsState) {
 
     let mut text_lines = vec![
         Line::from(Span::styled(
-            "Daily Mileage (Green = Goal Met ≥1.0 mi)",
+            format!("Daily Mileage (Green = Goal Met ≥{})", goal_text(units)),
             Style::default()
                 .fg(Color::Cyan)
                 .add_modifier(Modifier::BOLD),
@@ -212,9 +252,9 @@ fn render_chart(f: &mut Frame, area: Rect, state: &AnalyticsState) {
 
     for day_data in recent_data {
         let date_str = day_data.date.format("%m/%d").to_string();
-        let distance_str = format!("{:.2} mi", day_data.distance);
+        let distance_str = validation::format_distance(day_data.distance, units);
 
-        let style = if day_data.distance >= 1.0 {
+        let style = if day_data.distance >= DAILY_GOAL_MILES {
             Style::default()
                 .fg(Color::Green)
                 .add_modifier(Modifier::BOLD)
@@ -242,7 +282,12 @@ fn render_chart(f: &mut Frame, area: Rect, state: &AnalyticsState) {
     f.render_widget(chart, area);
 }
 
-fn render_monthly_breakdown(f: &mut Frame, area: Rect, state: &AnalyticsState) {
+fn render_monthly_breakdown(
+    f: &mut Frame,
+    area: Rect,
+    state: &AnalyticsState,
+    units: DistanceUnit,
+) {
     if state.analytics.monthly_breakdown.is_empty() {
         let empty = Paragraph::new("No data to display")
             .block(
@@ -257,7 +302,13 @@ fn render_monthly_breakdown(f: &mut Frame, area: Rect, state: &AnalyticsState) {
 
     let mut text_lines = vec![
         Line::from(Span::styled(
-            "Monthly Totals (Green ≥50 mi, Yellow ≥25 mi)",
+            format!(
+                "Monthly Totals (Green ≥{:.0} {}, Yellow ≥{:.0} {})",
+                units.convert_miles(MONTHLY_GREEN_MILES),
+                units.label(),
+                units.convert_miles(MONTHLY_YELLOW_MILES),
+                units.label()
+            ),
             Style::default()
                 .fg(Color::Cyan)
                 .add_modifier(Modifier::BOLD),
@@ -268,11 +319,11 @@ fn render_monthly_breakdown(f: &mut Frame, area: Rect, state: &AnalyticsState) {
     for month_data in &state.analytics.monthly_breakdown {
         let month_str = format!("{} {}", month_abbrev(month_data.month), month_data.year);
 
-        let style = if month_data.total_distance >= 50.0 {
+        let style = if month_data.total_distance >= MONTHLY_GREEN_MILES {
             Style::default()
                 .fg(Color::Green)
                 .add_modifier(Modifier::BOLD)
-        } else if month_data.total_distance >= 25.0 {
+        } else if month_data.total_distance >= MONTHLY_YELLOW_MILES {
             Style::default().fg(Color::Yellow)
         } else {
             Style::default().fg(Color::White)
@@ -283,11 +334,19 @@ fn render_monthly_breakdown(f: &mut Frame, area: Rect, state: &AnalyticsState) {
                 format!("{:<9}", month_str),
                 Style::default().fg(Color::Gray),
             ),
-            Span::styled(format!("{:>6.1} mi", month_data.total_distance), style),
+            Span::styled(
+                format!(
+                    "{:>6.1} {}",
+                    units.convert_miles(month_data.total_distance),
+                    units.label()
+                ),
+                style,
+            ),
             Span::styled(
                 format!(
                     "  ({} runs, {:.1} avg)",
-                    month_data.run_count, month_data.average_distance
+                    month_data.run_count,
+                    units.convert_miles(month_data.average_distance)
                 ),
                 Style::default().fg(Color::Gray),
             ),
@@ -303,6 +362,178 @@ fn render_monthly_breakdown(f: &mut Frame, area: Rect, state: &AnalyticsState) {
     f.render_widget(monthly_block, area);
 }
 
+fn render_type_breakdown(f: &mut Frame, area: Rect, state: &AnalyticsState, units: DistanceUnit) {
+    let mut text_lines = Vec::new();
+    for (title, totals) in [
+        ("Week", &state.analytics.type_breakdown_this_week),
+        ("Month", &state.analytics.type_breakdown_this_month),
+    ] {
+        text_lines.push(Line::from(Span::styled(
+            title,
+            Style::default()
+                .fg(Color::Cyan)
+                .add_modifier(Modifier::BOLD),
+        )));
+        if totals.is_empty() {
+            text_lines.push(Line::from(Span::styled(
+                "  No runs",
+                Style::default().fg(Color::Gray),
+            )));
+        }
+        for total in totals {
+            let name = total.run_type.map(|t| t.label()).unwrap_or("Untyped");
+            text_lines.push(Line::from(vec![
+                Span::styled(format!("  {:<10}", name), Style::default().fg(Color::Gray)),
+                Span::styled(
+                    format!(
+                        "{:>6.1} {}",
+                        units.convert_miles(total.total_distance),
+                        units.label()
+                    ),
+                    theme::stat_style(),
+                ),
+                Span::styled(
+                    format!("  ({} runs)", total.run_count),
+                    Style::default().fg(Color::Gray),
+                ),
+            ]));
+        }
+    }
+
+    let block = Paragraph::new(text_lines)
+        .block(Block::default().borders(Borders::ALL).title("By Run Type"));
+    f.render_widget(block, area);
+}
+
+fn render_weekly_trends(f: &mut Frame, area: Rect, state: &AnalyticsState, units: DistanceUnit) {
+    let mut text_lines = vec![
+        Line::from(Span::styled(
+            format!(
+                "{:<9}{:>5}{:>8}{:>8}{:>8}",
+                "Week of",
+                "Runs",
+                units.label(),
+                "Effort",
+                "Avg HR"
+            ),
+            Style::default()
+                .fg(Color::Cyan)
+                .add_modifier(Modifier::BOLD),
+        )),
+        Line::from(""),
+    ];
+
+    for week in &state.analytics.weekly_trends {
+        let effort = week
+            .average_effort
+            .map(|effort| format!("{:.1}", effort))
+            .unwrap_or_else(|| "--".to_string());
+        let heart_rate = week
+            .average_heart_rate
+            .map(|bpm| format!("{:.0}", bpm))
+            .unwrap_or_else(|| "--".to_string());
+        let effort_style = match week.average_effort {
+            Some(effort) if effort >= 8.0 => Style::default().fg(Color::Red),
+            Some(effort) if effort >= 6.0 => Style::default().fg(Color::Yellow),
+            Some(_) => Style::default().fg(Color::Green),
+            None => Style::default().fg(Color::Gray),
+        };
+
+        text_lines.push(Line::from(vec![
+            Span::styled(
+                format!("{:<9}", week.week_start.format("%b %d")),
+                Style::default().fg(Color::Gray),
+            ),
+            Span::styled(
+                format!("{:>5}", week.run_count),
+                Style::default().fg(Color::Gray),
+            ),
+            Span::styled(
+                format!("{:>8.1}", units.convert_miles(week.total_distance)),
+                theme::stat_style(),
+            ),
+            Span::styled(format!("{:>8}", effort), effort_style),
+            Span::styled(
+                format!("{:>8}", heart_rate),
+                Style::default().fg(Color::White),
+            ),
+        ]));
+    }
+
+    let block = Paragraph::new(text_lines).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .title("Weekly Trends"),
+    );
+    f.render_widget(block, area);
+}
+
+fn render_tag_totals(f: &mut Frame, area: Rect, state: &AnalyticsState, units: DistanceUnit) {
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title("Mileage by Tag");
+
+    if state.analytics.tag_totals.is_empty() {
+        let empty = Paragraph::new("No tagged runs yet")
+            .block(block)
+            .style(Style::default().fg(Color::Gray));
+        f.render_widget(empty, area);
+        return;
+    }
+
+    let text_lines: Vec<Line> = state
+        .analytics
+        .tag_totals
+        .iter()
+        .map(|tag| {
+            Line::from(vec![
+                Span::styled(
+                    format!("#{:<14}", tag.name),
+                    Style::default().fg(Color::Cyan),
+                ),
+                Span::styled(
+                    format!(
+                        "{:>7.1} {}",
+                        units.convert_miles(tag.total_distance),
+                        units.label()
+                    ),
+                    theme::stat_style(),
+                ),
+                Span::styled(
+                    format!("  ({} runs)", tag.run_count),
+                    Style::default().fg(Color::Gray),
+                ),
+            ])
+        })
+        .collect();
+
+    f.render_widget(Paragraph::new(text_lines).block(block), area);
+}
+
+fn pace_text(pace: Option<f64>, units: DistanceUnit) -> String {
+    match pace {
+        Some(seconds_per_mile) => format!(
+            "{} /{}",
+            validation::format_pace(units.pace_from_seconds_per_mile(seconds_per_mile)),
+            units.label()
+        ),
+        None => "--".to_string(),
+    }
+}
+
+fn goal_text(units: DistanceUnit) -> String {
+    match units {
+        DistanceUnit::Miles => format!("{:.0}{}", DAILY_GOAL_MILES, units.label()),
+        DistanceUnit::Kilometers => {
+            format!(
+                "{:.2}{}",
+                units.convert_miles(DAILY_GOAL_MILES),
+                units.label()
+            )
+        }
+    }
+}
+
 fn month_abbrev(month: u32) -> &'static str {
     match month {
         1 => "Jan",
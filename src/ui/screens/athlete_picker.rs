@@ -0,0 +1,105 @@
+use crate::app::AthletePicker;
+use crate::models::athlete::Athlete;
+use ratatui::{
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, Paragraph},
+    Frame,
+};
+
+/// The athlete switcher, drawn over the current screen.
+pub fn render(
+    f: &mut Frame,
+    area: Rect,
+    picker: &AthletePicker,
+    athletes: &[Athlete],
+    active_id: i64,
+) {
+    let width = area.width.min(50);
+    let height = (athletes.len() as u16 + 9).min(area.height);
+    let dialog_area = Rect {
+        x: area.x + (area.width - width) / 2,
+        y: area.y + (area.height - height) / 2,
+        width,
+        height,
+    };
+    f.render_widget(Clear, dialog_area);
+
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title("Athletes")
+        .border_style(Style::default().fg(Color::Yellow));
+    let inner = block.inner(dialog_area);
+    f.render_widget(block, dialog_area);
+
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Min(1),    // Athletes
+            Constraint::Length(3), // Name input
+            Constraint::Length(2), // Hints and status
+        ])
+        .split(inner);
+
+    let lines: Vec<Line> = athletes
+        .iter()
+        .enumerate()
+        .map(|(i, athlete)| {
+            let style = if i == picker.selected_index {
+                Style::default()
+                    .fg(Color::Yellow)
+                    .add_modifier(Modifier::BOLD)
+            } else {
+                Style::default()
+            };
+            let marker = if athlete.id == Some(active_id) {
+                "● "
+            } else {
+                "  "
+            };
+            Line::from(vec![
+                Span::styled(marker, Style::default().fg(Color::Green)),
+                Span::styled(athlete.name.as_str(), style),
+                Span::styled(
+                    format!("  {} runs", athlete.run_count),
+                    Style::default().fg(Color::Gray),
+                ),
+            ])
+        })
+        .collect();
+    f.render_widget(Paragraph::new(lines), chunks[0]);
+
+    if let Some(ref name) = picker.name_input {
+        let title = if picker.renaming {
+            "Rename - [Enter] Save  [Esc] Cancel"
+        } else {
+            "New athlete - [Enter] Add  [Esc] Cancel"
+        };
+        let input = Paragraph::new(format!("{}_", name)).block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(title)
+                .border_style(Style::default().fg(Color::Yellow)),
+        );
+        f.render_widget(input, chunks[1]);
+    }
+
+    let mut lines = vec![Line::from(vec![
+        Span::styled("[Enter] ", Style::default().fg(Color::Green)),
+        Span::raw("Switch  "),
+        Span::styled("[a] ", Style::default().fg(Color::Yellow)),
+        Span::raw("Add  "),
+        Span::styled("[r] ", Style::default().fg(Color::Yellow)),
+        Span::raw("Rename  "),
+        Span::styled("[Esc] ", Style::default().fg(Color::Yellow)),
+        Span::raw("Close"),
+    ])];
+    if let Some(ref message) = picker.message {
+        lines.push(Line::from(Span::styled(
+            message.as_str(),
+            Style::default().fg(Color::Gray),
+        )));
+    }
+    f.render_widget(Paragraph::new(lines), chunks[2]);
+}
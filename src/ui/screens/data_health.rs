@@ -0,0 +1,135 @@
+use crate::app::DataHealthState;
+use ratatui::{
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Cell, Paragraph, Row, Table},
+    Frame,
+};
+
+pub fn render(f: &mut Frame, area: Rect, state: &DataHealthState) {
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Min(10),   // Table
+            Constraint::Length(3), // Fix input
+            Constraint::Length(4), // Hints and status
+        ])
+        .split(area);
+
+    if state.runs.is_empty() {
+        let empty_message = Block::default()
+            .borders(Borders::ALL)
+            .title("Data Health")
+            .style(Style::default());
+        let empty_text = Paragraph::new(
+            "Every run can be read. Runs with values the app doesn't understand appear here.",
+        )
+        .block(empty_message)
+        .style(Style::default().fg(Color::Gray));
+        f.render_widget(empty_text, chunks[0]);
+        render_hints(f, chunks[2], state);
+        return;
+    }
+
+    let header_cells = ["Id", "Column", "Value", "Problem"].into_iter().map(|h| {
+        Cell::from(h).style(
+            Style::default()
+                .fg(Color::Yellow)
+                .add_modifier(Modifier::BOLD),
+        )
+    });
+    let header = Row::new(header_cells).height(1).bottom_margin(1);
+
+    let rows: Vec<Row> = state
+        .runs
+        .iter()
+        .enumerate()
+        .map(|(i, run)| {
+            let style = if i == state.selected_index {
+                Style::default()
+                    .fg(Color::Yellow)
+                    .add_modifier(Modifier::BOLD)
+            } else {
+                Style::default()
+            };
+
+            Row::new(vec![
+                Cell::from(format!("#{}", run.id)),
+                Cell::from(run.column.unwrap_or("-")),
+                Cell::from(run.value.as_str()),
+                Cell::from(run.reason.as_str()),
+            ])
+            .style(style)
+        })
+        .collect();
+
+    let table = Table::new(
+        rows,
+        [
+            Constraint::Length(8),
+            Constraint::Length(16),
+            Constraint::Length(28),
+            Constraint::Min(20),
+        ],
+    )
+    .header(header)
+    .block(Block::default().borders(Borders::ALL).title(format!(
+        "Data Health ({} unreadable runs)",
+        state.runs.len()
+    )));
+
+    f.render_widget(table, chunks[0]);
+    render_fix_input(f, chunks[1], state);
+    render_hints(f, chunks[2], state);
+}
+
+fn render_fix_input(f: &mut Frame, area: Rect, state: &DataHealthState) {
+    let (Some(ref input), Some(run)) = (&state.fix_input, state.selected()) else {
+        return;
+    };
+
+    let input = Paragraph::new(format!("{}_", input))
+        .style(Style::default().fg(Color::White))
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(format!(
+                    "New {} for run #{} - [Enter] Save  [Esc] Cancel",
+                    run.column.unwrap_or_default(),
+                    run.id
+                ))
+                .border_style(Style::default().fg(Color::Yellow)),
+        );
+
+    f.render_widget(input, area);
+}
+
+fn render_hints(f: &mut Frame, area: Rect, state: &DataHealthState) {
+    let mut lines = vec![Line::from(vec![
+        Span::styled("[↑↓] ", Style::default().fg(Color::Yellow)),
+        Span::raw("Navigate  "),
+        Span::styled("[f] ", Style::default().fg(Color::Green)),
+        Span::raw("Fix value  "),
+        Span::styled("[d] ", Style::default().fg(Color::Red)),
+        Span::raw("Delete run  "),
+        Span::styled("[r] ", Style::default().fg(Color::Yellow)),
+        Span::raw("Re-check  "),
+        Span::styled("[Esc→1-7] ", Style::default().fg(Color::Cyan)),
+        Span::raw("Switch screens"),
+    ])];
+
+    if let Some(ref message) = state.message {
+        let style = if state.confirm_delete {
+            Style::default().fg(Color::Red).add_modifier(Modifier::BOLD)
+        } else {
+            Style::default().fg(Color::Gray)
+        };
+        lines.push(Line::from(Span::styled(message.as_str(), style)));
+    }
+
+    let hints =
+        Paragraph::new(lines).block(Block::default().borders(Borders::ALL).title("Actions"));
+
+    f.render_widget(hints, area);
+}
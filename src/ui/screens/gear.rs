@@ -0,0 +1,204 @@
+use crate::app::{GearField, GearForm, GearState};
+use crate::models::gear::Gear;
+use crate::models::units::DistanceUnit;
+use ratatui::{
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Cell, Paragraph, Row, Table},
+    Frame,
+};
+
+/// Share of the retirement distance at which a shoe is shown as nearly worn.
+const NEARLY_WORN_FRACTION: f64 = 0.9;
+
+pub fn render(f: &mut Frame, area: Rect, state: &GearState, units: DistanceUnit) {
+    let hints_height = if state.form.is_some() { 5 } else { 4 };
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Min(10),              // Table
+            Constraint::Length(hints_height), // Form or hints and status
+        ])
+        .split(area);
+
+    if state.gear.is_empty() {
+        let empty_text = Paragraph::new("No shoes yet. Press [a] to add a pair.")
+            .block(Block::default().borders(Borders::ALL).title("Gear"))
+            .style(Style::default().fg(Color::Gray));
+        f.render_widget(empty_text, chunks[0]);
+    } else {
+        render_table(f, chunks[0], state, units);
+    }
+
+    match state.form {
+        Some(ref form) => render_form(f, chunks[1], form, units),
+        None => render_hints(f, chunks[1], state),
+    }
+}
+
+fn render_table(f: &mut Frame, area: Rect, state: &GearState, units: DistanceUnit) {
+    let header_cells = [
+        "Name".to_string(),
+        "Runs".to_string(),
+        format!("Distance ({})", units.label()),
+        format!("Retire at ({})", units.label()),
+        "Used".to_string(),
+        "Status".to_string(),
+    ]
+    .into_iter()
+    .map(|h| {
+        Cell::from(h).style(
+            Style::default()
+                .fg(Color::Yellow)
+                .add_modifier(Modifier::BOLD),
+        )
+    });
+    let header = Row::new(header_cells).height(1).bottom_margin(1);
+
+    let rows: Vec<Row> = state
+        .gear
+        .iter()
+        .enumerate()
+        .map(|(i, gear)| {
+            let name = if gear.id.is_some() && gear.id == state.default_gear_id {
+                format!("{} (default)", gear.name)
+            } else {
+                gear.name.clone()
+            };
+            let used = gear.total_miles / gear.retire_at_miles * 100.0;
+            let (status, status_style) = status(gear);
+
+            let style = if i == state.selected_index {
+                Style::default()
+                    .fg(Color::Yellow)
+                    .add_modifier(Modifier::BOLD)
+            } else if gear.retired {
+                Style::default().fg(Color::DarkGray)
+            } else {
+                Style::default()
+            };
+
+            Row::new(vec![
+                Cell::from(name),
+                Cell::from(gear.run_count.to_string()),
+                Cell::from(format!("{:.1}", units.convert_miles(gear.total_miles))),
+                Cell::from(format!("{:.0}", units.convert_miles(gear.retire_at_miles))),
+                Cell::from(format!("{:.0}%", used)),
+                Cell::from(status).style(status_style),
+            ])
+            .style(style)
+        })
+        .collect();
+
+    let table = Table::new(
+        rows,
+        [
+            Constraint::Min(20),
+            Constraint::Length(6),
+            Constraint::Length(15),
+            Constraint::Length(16),
+            Constraint::Length(7),
+            Constraint::Length(16),
+        ],
+    )
+    .header(header)
+    .block(
+        Block::default()
+            .borders(Borders::ALL)
+            .title(format!("Gear ({} shoes)", state.gear.len())),
+    );
+
+    f.render_widget(table, area);
+}
+
+fn status(gear: &Gear) -> (&'static str, Style) {
+    if gear.retired {
+        ("Retired", Style::default().fg(Color::DarkGray))
+    } else if gear.is_due_for_retirement() {
+        (
+            "Time to retire",
+            Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
+        )
+    } else if gear.total_miles >= gear.retire_at_miles * NEARLY_WORN_FRACTION {
+        ("Nearly worn", Style::default().fg(Color::Yellow))
+    } else {
+        ("In use", Style::default().fg(Color::Green))
+    }
+}
+
+fn render_form(f: &mut Frame, area: Rect, form: &GearForm, units: DistanceUnit) {
+    let field = |label: String, value: &str, focused: bool| {
+        let style = if focused {
+            Style::default().fg(Color::Yellow)
+        } else {
+            Style::default().fg(Color::Gray)
+        };
+        let cursor = if focused { "_" } else { "" };
+        Line::from(vec![
+            Span::styled(label, style),
+            Span::raw(format!("{}{}", value, cursor)),
+        ])
+    };
+
+    let mut lines = vec![
+        field(
+            "Name: ".to_string(),
+            &form.name,
+            form.focused_field == GearField::Name,
+        ),
+        field(
+            format!("Retire at ({}): ", units.label()),
+            &form.retire_at,
+            form.focused_field == GearField::RetireAt,
+        ),
+    ];
+    if let Some(ref error) = form.error_message {
+        lines.push(Line::from(Span::styled(
+            error.as_str(),
+            Style::default().fg(Color::Red),
+        )));
+    }
+
+    let title = if form.editing.is_some() {
+        "Edit shoe - [Tab] Next field  [Enter] Save  [Esc] Cancel"
+    } else {
+        "Add shoe - [Tab] Next field  [Enter] Save  [Esc] Cancel"
+    };
+    let widget = Paragraph::new(lines).block(Block::default().borders(Borders::ALL).title(title));
+
+    f.render_widget(widget, area);
+}
+
+fn render_hints(f: &mut Frame, area: Rect, state: &GearState) {
+    let mut lines = vec![Line::from(vec![
+        Span::styled("[↑↓] ", Style::default().fg(Color::Yellow)),
+        Span::raw("Navigate  "),
+        Span::styled("[a] ", Style::default().fg(Color::Green)),
+        Span::raw("Add  "),
+        Span::styled("[e] ", Style::default().fg(Color::Green)),
+        Span::raw("Edit  "),
+        Span::styled("[s] ", Style::default().fg(Color::Magenta)),
+        Span::raw("Set default  "),
+        Span::styled("[r] ", Style::default().fg(Color::Magenta)),
+        Span::raw("Retire/unretire  "),
+        Span::styled("[d] ", Style::default().fg(Color::Red)),
+        Span::raw("Delete  "),
+        Span::styled("[Esc→1-7] ", Style::default().fg(Color::Cyan)),
+        Span::raw("Switch screens"),
+    ])];
+
+    if let Some(ref message) = state.message {
+        let style = if state.confirm_delete {
+            Style::default().fg(Color::Red).add_modifier(Modifier::BOLD)
+        } else {
+            Style::default().fg(Color::Gray)
+        };
+        lines.push(Line::from(Span::styled(message.as_str(), style)));
+    }
+
+    let hints =
+        Paragraph::new(lines).block(Block::default().borders(Borders::ALL).title("Actions"));
+
+    f.render_widget(hints, area);
+}
@@ -5,8 +5,9 @@ use ratatui::{
     widgets::{Block, Borders, Paragraph, Wrap},
     Frame,
 };
+use std::path::Path;
 
-pub fn render(f: &mut Frame, area: Rect) {
+pub fn render(f: &mut Frame, area: Rect, db_path: Option<&Path>) {
     let help_text = vec![
         Line::from(""),
         Line::from(Span::styled(
@@ -20,13 +21,21 @@ pub fn render(f: &mut Frame, area: Rect) {
             "Navigation:",
             Style::default().fg(Color::Cyan),
         )),
-        Line::from("  [Esc] then [1/2/3] - Switch screens (works from anywhere)"),
+        Line::from("  [Esc] then [1-7] - Switch screens (works from anywhere)"),
         Line::from("    - Esc → 1: Quick Entry"),
         Line::from("    - Esc → 2: Run List"),
         Line::from("    - Esc → 3: Analytics"),
-        Line::from("  [1-3] - Switch screens (when NOT in Quick Entry)"),
+        Line::from("    - Esc → 4: Trash"),
+        Line::from("    - Esc → 5: Gear"),
+        Line::from("    - Esc → 6: Routes"),
+        Line::from("    - Esc → 7: Data Health"),
+        Line::from("  [1-7] - Switch screens (when NOT in Quick Entry)"),
         Line::from("  [h] or [?] - This help screen (works from any screen)"),
-        Line::from("  [q] - Quit application (from Help, Run List, Analytics)"),
+        Line::from("  [m] - Toggle miles / kilometres (when NOT in Quick Entry)"),
+        Line::from("  [A] - Switch athlete; [a] adds and [r] renames one (Esc → A in Quick Entry)"),
+        Line::from("  [u] or [Ctrl+Z] - Undo the last insert, edit or delete"),
+        Line::from("  [Ctrl+R] - Redo the last undone change"),
+        Line::from("  [q] - Quit application (from any screen except Quick Entry)"),
         Line::from("  [Ctrl+Q] or [Ctrl+C] - Quit from anywhere including Quick Entry"),
         Line::from(""),
         Line::from(Span::styled(
@@ -36,8 +45,13 @@ pub fn render(f: &mut Frame, area: Rect) {
         Line::from("  [Tab] - Move to next field"),
         Line::from("  [Shift+Tab] - Move to previous field"),
         Line::from("  [Enter] - Submit run entry"),
+        Line::from("        If another run starts at the same date and time: [o] overwrite it,"),
+        Line::from("        [m] merge distances, [s] shift to the next free minute, [c] cancel"),
+        Line::from("  [Left/Right Arrow] or [Space] - Choose the route, run type or shoe"),
+        Line::from("        Choosing a route fills in its distance"),
+        Line::from("  [Right Arrow] - Complete the suggested tag in the Tags field"),
         Line::from("  [Esc] [Esc] - Clear all fields (press Escape twice)"),
-        Line::from("  [Esc] [1/2/3] - Switch screens without leaving Quick Entry"),
+        Line::from("  [Esc] [1-7] - Switch screens without leaving Quick Entry"),
         Line::from("  Type numbers/letters directly in the focused field"),
         Line::from("  [Backspace] - Delete last character"),
         Line::from(""),
@@ -46,8 +60,66 @@ pub fn render(f: &mut Frame, area: Rect) {
             Style::default().fg(Color::Cyan),
         )),
         Line::from("  [Up/Down Arrow] - Navigate through runs"),
+        Line::from("  [Enter] - Show the selected run's details and laps"),
+        Line::from("  [l] - Edit the selected run's laps"),
         Line::from("  [e] - Edit the selected run"),
-        Line::from("  [d] - Delete the selected run"),
+        Line::from("  [H] - Show the selected run's change history"),
+        Line::from("  [d] - Move the selected run to the trash"),
+        Line::from("  [/] - Search notes (Enter applies, empty search clears)"),
+        Line::from("        Add #tag to only show runs with that tag"),
+        Line::from(""),
+        Line::from(Span::styled(
+            "Run Detail Screen:",
+            Style::default().fg(Color::Cyan),
+        )),
+        Line::from("  [l] - Edit laps ([Tab] next field, adds a lap after the last)"),
+        Line::from("        [Up/Down] select lap, [Ctrl+D] remove, [Enter] save, [Esc] cancel"),
+        Line::from("  [e] - Edit the run"),
+        Line::from("  [b] or [Backspace] - Back to the Run List"),
+        Line::from(""),
+        Line::from(Span::styled(
+            "Run History Screen:",
+            Style::default().fg(Color::Cyan),
+        )),
+        Line::from("  [Up/Down Arrow] - Select a change"),
+        Line::from("  [r] - Revert the run to how it was after that change (undo with [u])"),
+        Line::from("  [b] or [Backspace] - Back to the Run List"),
+        Line::from(""),
+        Line::from(Span::styled(
+            "Trash Screen:",
+            Style::default().fg(Color::Cyan),
+        )),
+        Line::from("  [Up/Down Arrow] - Navigate through deleted runs"),
+        Line::from("  [r] - Restore the selected run"),
+        Line::from("  [X] then [y] - Empty the trash (permanent)"),
+        Line::from(""),
+        Line::from(Span::styled(
+            "Gear Screen:",
+            Style::default().fg(Color::Cyan),
+        )),
+        Line::from("  [Up/Down Arrow] - Navigate through shoes"),
+        Line::from("  [a] / [e] - Add a shoe / edit the selected shoe"),
+        Line::from("  [s] - Make the selected shoe the default for new runs"),
+        Line::from("  [r] - Retire the selected shoe (or bring it back)"),
+        Line::from("  [d] then [y] - Delete the selected shoe (its runs are kept)"),
+        Line::from(""),
+        Line::from(Span::styled(
+            "Routes Screen:",
+            Style::default().fg(Color::Cyan),
+        )),
+        Line::from("  [Up/Down Arrow] - Navigate through routes"),
+        Line::from("  [a] / [e] - Add a route / edit the selected route"),
+        Line::from("  [d] then [y] - Delete the selected route (its runs are kept)"),
+        Line::from(""),
+        Line::from(Span::styled(
+            "Data Health Screen:",
+            Style::default().fg(Color::Cyan),
+        )),
+        Line::from("  Runs the app can't read are set aside here, with the reason"),
+        Line::from("  [Up/Down Arrow] - Navigate through unreadable runs"),
+        Line::from("  [f] or [Enter] - Type a corrected value ([Enter] saves, [Esc] cancels)"),
+        Line::from("  [d] then [y] - Delete the selected run (permanent)"),
+        Line::from("  [r] - Check every run again"),
         Line::from(""),
         Line::from(Span::styled(
             "Your Goal:",
@@ -60,15 +132,29 @@ pub fn render(f: &mut Frame, area: Rect) {
         Line::from(""),
         Line::from(Span::styled("Tips:", Style::default().fg(Color::Yellow))),
         Line::from("  - Date and time are pre-filled with current values"),
+        Line::from("  - Zone is the UTC offset where you ran (e.g. +09:00); when travelling,"),
+        Line::from("    enter the local time there. Streaks use each run's own date"),
         Line::from("  - Just enter distance and optionally a note"),
-        Line::from("  - Distance must be positive (e.g., 3.5 for 3.5 miles)"),
+        Line::from("  - Distance must be positive and is entered in your chosen unit"),
+        Line::from("  - Duration is optional (e.g., 28:30 or 1:05:00) and enables pace stats"),
+        Line::from("  - Heart rate (bpm) and effort (1-10) are optional"),
+        Line::from("  - Tags are comma-separated words, e.g. trail, race"),
+        Line::from("  - Runs, trash and analytics are per athlete; gear and routes are shared"),
         Line::from("  - Your streak counts consecutive days with 1+ mile total"),
         Line::from(""),
         Line::from(Span::styled(
             "Data Location:",
             Style::default().fg(Color::Cyan),
         )),
-        Line::from("  ~/Library/Application Support/runlogger/runs.db"),
+        Line::from(match db_path {
+            Some(path) => format!("  {}", path.display()),
+            None => "  In memory only - nothing is saved when you quit".to_string(),
+        }),
+        Line::from("  Daily backups are kept in the backups/ folder next to it"),
+        Line::from("  Run `runlogger backups` / `runlogger restore <n>` to restore one"),
+        Line::from("  Use --db <path> or --profile <name> to pick another database"),
+        Line::from("  Run `runlogger encrypt` to protect it with a passphrase"),
+        Line::from("  Run `runlogger merge <path>` to bring in another machine's runs"),
     ];
 
     let help_block = Paragraph::new(help_text)
@@ -1,4 +1,11 @@
 pub mod analytics;
+pub mod athlete_picker;
+pub mod data_health;
+pub mod gear;
 pub mod help;
 pub mod quick_entry;
+pub mod routes;
+pub mod run_detail;
+pub mod run_history;
 pub mod run_list;
+pub mod trash;
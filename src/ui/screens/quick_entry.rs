@@ -1,17 +1,26 @@
-use crate::app::{InputField, QuickEntryState};
+use crate::app::{InputField, QuickEntryState, RunConflict};
+use crate::logic::validation;
+use crate::models::run::{Run, RunType};
+use crate::models::units::DistanceUnit;
 use crate::ui::theme;
 use ratatui::{
     layout::{Constraint, Direction, Layout, Rect},
-    style::{Modifier, Style},
-    widgets::{Block, Borders, Paragraph, Wrap},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, Paragraph, Wrap},
     Frame,
 };
 
-pub fn render(f: &mut Frame, area: Rect, state: &QuickEntryState) {
+pub fn render(f: &mut Frame, area: Rect, state: &QuickEntryState, units: DistanceUnit) {
     let chunks = Layout::default()
         .direction(Direction::Vertical)
         .margin(2)
         .constraints([
+            Constraint::Length(3),
+            Constraint::Length(3),
+            Constraint::Length(3),
+            Constraint::Length(3),
+            Constraint::Length(3),
             Constraint::Length(3),
             Constraint::Length(3),
             Constraint::Length(3),
@@ -21,6 +30,25 @@ pub fn render(f: &mut Frame, area: Rect, state: &QuickEntryState) {
         ])
         .split(area);
 
+    // Time and zone, heart rate and effort, and run type and shoe share rows
+    // to keep the form on one screen
+    let time_fields = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
+        .split(chunks[2]);
+    let effort_fields = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([
+            Constraint::Percentage(33),
+            Constraint::Percentage(33),
+            Constraint::Percentage(34),
+        ])
+        .split(chunks[6]);
+    let selectors = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
+        .split(chunks[7]);
+
     let title = Block::default()
         .borders(Borders::NONE)
         .style(Style::default().add_modifier(Modifier::BOLD));
@@ -43,21 +71,81 @@ pub fn render(f: &mut Frame, area: Rect, state: &QuickEntryState) {
     );
     render_input_field(
         f,
-        chunks[2],
+        time_fields[0],
         "Time (HH:MM:SS)",
         &state.time,
         state.focused_field == InputField::Time,
     );
     render_input_field(
+        f,
+        time_fields[1],
+        "Zone (UTC offset where you ran, e.g. +09:00)",
+        &state.zone,
+        state.focused_field == InputField::Zone,
+    );
+    render_selector_field(
         f,
         chunks[3],
-        "Distance (miles)",
+        "Route (←/→ or Space to choose, fills in distance, optional)",
+        state
+            .route()
+            .map(|route| route.name.as_str())
+            .unwrap_or("None"),
+        state.focused_field == InputField::Route,
+    );
+    render_input_field(
+        f,
+        chunks[4],
+        &format!("Distance ({})", units.name()),
         &state.distance,
         state.focused_field == InputField::Distance,
     );
     render_input_field(
         f,
-        chunks[4],
+        chunks[5],
+        "Duration (MM:SS or H:MM:SS, optional)",
+        &state.duration,
+        state.focused_field == InputField::Duration,
+    );
+    render_input_field(
+        f,
+        effort_fields[0],
+        "Avg HR (bpm, optional)",
+        &state.avg_heart_rate,
+        state.focused_field == InputField::AvgHeartRate,
+    );
+    render_input_field(
+        f,
+        effort_fields[1],
+        "Max HR (bpm, optional)",
+        &state.max_heart_rate,
+        state.focused_field == InputField::MaxHeartRate,
+    );
+    render_input_field(
+        f,
+        effort_fields[2],
+        "Effort (1-10, optional)",
+        &state.effort,
+        state.focused_field == InputField::Effort,
+    );
+    render_selector_field(
+        f,
+        selectors[0],
+        "Type (←/→ or Space, optional)",
+        state.run_type.map(RunType::label).unwrap_or("None"),
+        state.focused_field == InputField::Type,
+    );
+    render_selector_field(
+        f,
+        selectors[1],
+        "Shoe (←/→ or Space, optional)",
+        state.gear_name(state.gear_id).unwrap_or("None"),
+        state.focused_field == InputField::Shoe,
+    );
+    render_tags_field(f, chunks[8], state);
+    render_input_field(
+        f,
+        chunks[9],
         "Note (optional)",
         &state.note,
         state.focused_field == InputField::Note,
@@ -67,13 +155,147 @@ pub fn render(f: &mut Frame, area: Rect, state: &QuickEntryState) {
         let error_widget = Paragraph::new(error.as_str())
             .style(theme::error_style())
             .wrap(Wrap { trim: false });
-        f.render_widget(error_widget, chunks[5]);
+        f.render_widget(error_widget, chunks[10]);
     } else if let Some(ref success) = state.success_message {
         let success_widget = Paragraph::new(success.as_str())
             .style(theme::success_style())
             .wrap(Wrap { trim: false });
-        f.render_widget(success_widget, chunks[5]);
+        f.render_widget(success_widget, chunks[10]);
+    }
+
+    if let Some(ref conflict) = state.conflict {
+        render_conflict_dialog(f, area, conflict, units);
+    }
+}
+
+/// Asks what to do when the run would start at the same date and time as
+/// another run.
+fn render_conflict_dialog(f: &mut Frame, area: Rect, conflict: &RunConflict, units: DistanceUnit) {
+    let summary = |run: &Run| {
+        let mut parts = vec![validation::format_distance(run.distance_miles, units)];
+        parts.extend(run.duration_seconds.map(validation::format_duration));
+        parts.extend(run.note.clone());
+        parts.join("  ")
+    };
+    let key = |text: &'static str| {
+        Span::styled(
+            text,
+            Style::default()
+                .fg(Color::Yellow)
+                .add_modifier(Modifier::BOLD),
+        )
+    };
+    let mut merge_hint = format!(
+        "Merge: add {} to the existing run",
+        validation::format_distance(conflict.run.distance_miles, units)
+    );
+    if conflict.run.id.is_some() {
+        merge_hint.push_str(" and trash this one");
     }
+
+    let lines = vec![
+        Line::from(format!(
+            "Another run already starts at {} {}.",
+            validation::format_date(&conflict.existing.date),
+            validation::format_time(&conflict.existing.time_started)
+        )),
+        Line::from(""),
+        Line::from(vec![
+            Span::styled("  Existing: ", Style::default().fg(Color::Gray)),
+            Span::raw(summary(&conflict.existing)),
+        ]),
+        Line::from(vec![
+            Span::styled("  This run: ", Style::default().fg(Color::Gray)),
+            Span::raw(summary(&conflict.run)),
+        ]),
+        Line::from(""),
+        Line::from(vec![
+            key("[o] "),
+            Span::raw("Overwrite: move the existing run to the trash"),
+        ]),
+        Line::from(vec![key("[m] "), Span::raw(merge_hint)]),
+        Line::from(vec![
+            key("[s] "),
+            Span::raw("Shift this run to the next free minute"),
+        ]),
+        Line::from(vec![key("[c] "), Span::raw("Cancel and keep editing")]),
+    ];
+
+    let width = area.width.min(70);
+    let height = (lines.len() as u16 + 2).min(area.height);
+    let dialog_area = Rect {
+        x: area.x + (area.width - width) / 2,
+        y: area.y + (area.height - height) / 2,
+        width,
+        height,
+    };
+
+    let dialog = Paragraph::new(lines).wrap(Wrap { trim: false }).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .title("Run Already Exists")
+            .border_style(Style::default().fg(Color::Red)),
+    );
+    f.render_widget(Clear, dialog_area);
+    f.render_widget(dialog, dialog_area);
+}
+
+/// A field whose value is chosen with the arrow keys rather than typed.
+fn render_selector_field(f: &mut Frame, area: Rect, label: &str, value: &str, is_focused: bool) {
+    let (style, border_style, text) = if is_focused {
+        (
+            theme::focused_input_style(),
+            Style::default().fg(Color::Yellow),
+            format!("◀ {} ▶", value),
+        )
+    } else {
+        (
+            theme::unfocused_input_style(),
+            Style::default().fg(Color::Gray),
+            value.to_string(),
+        )
+    };
+
+    let input = Paragraph::new(text).style(style).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .title(label)
+            .border_style(border_style),
+    );
+
+    f.render_widget(input, area);
+}
+
+/// The Tags field, with the completion of a known tag shown dimmed after the
+/// cursor.
+fn render_tags_field(f: &mut Frame, area: Rect, state: &QuickEntryState) {
+    let is_focused = state.focused_field == InputField::Tags;
+    let Some(suggestion) = state.tag_suggestion() else {
+        render_input_field(
+            f,
+            area,
+            "Tags (comma-separated, optional)",
+            &state.tags,
+            is_focused,
+        );
+        return;
+    };
+
+    let line = Line::from(vec![
+        Span::raw(state.tags.as_str()),
+        Span::styled(suggestion, Style::default().fg(Color::DarkGray)),
+        Span::raw("_"),
+    ]);
+    let input = Paragraph::new(line)
+        .style(theme::focused_input_style())
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title("Tags (comma-separated, optional) - [→] Complete")
+                .border_style(Style::default().fg(Color::Yellow)),
+        );
+
+    f.render_widget(input, area);
 }
 
 fn render_input_field(f: &mut Frame, area: Rect, label: &str, value: &str, is_focused: bool) {
@@ -0,0 +1,188 @@
+use crate::app::{RouteField, RouteForm, RoutesState};
+use crate::logic::validation;
+use crate::models::units::DistanceUnit;
+use ratatui::{
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Cell, Paragraph, Row, Table},
+    Frame,
+};
+
+pub fn render(f: &mut Frame, area: Rect, state: &RoutesState, units: DistanceUnit) {
+    let hints_height = if state.form.is_some() { 6 } else { 4 };
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Min(10),              // Table
+            Constraint::Length(hints_height), // Form or hints and status
+        ])
+        .split(area);
+
+    if state.routes.is_empty() {
+        let empty_text = Paragraph::new(
+            "No routes yet. Press [a] to save a route you run often, then pick it in Quick Entry.",
+        )
+        .block(Block::default().borders(Borders::ALL).title("Routes"))
+        .style(Style::default().fg(Color::Gray));
+        f.render_widget(empty_text, chunks[0]);
+    } else {
+        render_table(f, chunks[0], state, units);
+    }
+
+    match state.form {
+        Some(ref form) => render_form(f, chunks[1], form, units),
+        None => render_hints(f, chunks[1], state),
+    }
+}
+
+fn render_table(f: &mut Frame, area: Rect, state: &RoutesState, units: DistanceUnit) {
+    let header_cells = [
+        "Name".to_string(),
+        format!("Distance ({})", units.label()),
+        "Times Run".to_string(),
+        "Best".to_string(),
+        "Average".to_string(),
+        "Description".to_string(),
+    ]
+    .into_iter()
+    .map(|h| {
+        Cell::from(h).style(
+            Style::default()
+                .fg(Color::Yellow)
+                .add_modifier(Modifier::BOLD),
+        )
+    });
+    let header = Row::new(header_cells).height(1).bottom_margin(1);
+
+    let rows: Vec<Row> = state
+        .routes
+        .iter()
+        .enumerate()
+        .map(|(i, route)| {
+            let best = route
+                .best_duration_seconds
+                .map(validation::format_duration)
+                .unwrap_or_else(|| "--".to_string());
+            let average = route
+                .average_duration_seconds
+                .map(|seconds| validation::format_duration(seconds.round() as u32))
+                .unwrap_or_else(|| "--".to_string());
+
+            let style = if i == state.selected_index {
+                Style::default()
+                    .fg(Color::Yellow)
+                    .add_modifier(Modifier::BOLD)
+            } else {
+                Style::default()
+            };
+
+            Row::new(vec![
+                Cell::from(route.name.clone()),
+                Cell::from(format!("{:.2}", units.convert_miles(route.distance_miles))),
+                Cell::from(route.run_count.to_string()),
+                Cell::from(best).style(Style::default().fg(Color::Green)),
+                Cell::from(average),
+                Cell::from(route.description.clone().unwrap_or_default()),
+            ])
+            .style(style)
+        })
+        .collect();
+
+    let table = Table::new(
+        rows,
+        [
+            Constraint::Length(20),
+            Constraint::Length(15),
+            Constraint::Length(10),
+            Constraint::Length(10),
+            Constraint::Length(10),
+            Constraint::Min(20),
+        ],
+    )
+    .header(header)
+    .block(
+        Block::default()
+            .borders(Borders::ALL)
+            .title(format!("Routes ({})", state.routes.len())),
+    );
+
+    f.render_widget(table, area);
+}
+
+fn render_form(f: &mut Frame, area: Rect, form: &RouteForm, units: DistanceUnit) {
+    let field = |label: String, value: &str, focused: bool| {
+        let style = if focused {
+            Style::default().fg(Color::Yellow)
+        } else {
+            Style::default().fg(Color::Gray)
+        };
+        let cursor = if focused { "_" } else { "" };
+        Line::from(vec![
+            Span::styled(label, style),
+            Span::raw(format!("{}{}", value, cursor)),
+        ])
+    };
+
+    let mut lines = vec![
+        field(
+            "Name: ".to_string(),
+            &form.name,
+            form.focused_field == RouteField::Name,
+        ),
+        field(
+            format!("Distance ({}): ", units.label()),
+            &form.distance,
+            form.focused_field == RouteField::Distance,
+        ),
+        field(
+            "Description (optional): ".to_string(),
+            &form.description,
+            form.focused_field == RouteField::Description,
+        ),
+    ];
+    if let Some(ref error) = form.error_message {
+        lines.push(Line::from(Span::styled(
+            error.as_str(),
+            Style::default().fg(Color::Red),
+        )));
+    }
+
+    let title = if form.editing_id.is_some() {
+        "Edit route - [Tab] Next field  [Enter] Save  [Esc] Cancel"
+    } else {
+        "Add route - [Tab] Next field  [Enter] Save  [Esc] Cancel"
+    };
+    let widget = Paragraph::new(lines).block(Block::default().borders(Borders::ALL).title(title));
+
+    f.render_widget(widget, area);
+}
+
+fn render_hints(f: &mut Frame, area: Rect, state: &RoutesState) {
+    let mut lines = vec![Line::from(vec![
+        Span::styled("[↑↓] ", Style::default().fg(Color::Yellow)),
+        Span::raw("Navigate  "),
+        Span::styled("[a] ", Style::default().fg(Color::Green)),
+        Span::raw("Add  "),
+        Span::styled("[e] ", Style::default().fg(Color::Green)),
+        Span::raw("Edit  "),
+        Span::styled("[d] ", Style::default().fg(Color::Red)),
+        Span::raw("Delete  "),
+        Span::styled("[Esc→1-7] ", Style::default().fg(Color::Cyan)),
+        Span::raw("Switch screens"),
+    ])];
+
+    if let Some(ref message) = state.message {
+        let style = if state.confirm_delete {
+            Style::default().fg(Color::Red).add_modifier(Modifier::BOLD)
+        } else {
+            Style::default().fg(Color::Gray)
+        };
+        lines.push(Line::from(Span::styled(message.as_str(), style)));
+    }
+
+    let hints =
+        Paragraph::new(lines).block(Block::default().borders(Borders::ALL).title("Actions"));
+
+    f.render_widget(hints, area);
+}
@@ -0,0 +1,310 @@
+use crate::app::{LapEditor, LapField, RunDetailState};
+use crate::logic::validation;
+use crate::models::run::Run;
+use crate::models::split::{self, Split};
+use crate::models::units::DistanceUnit;
+use ratatui::{
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Cell, Paragraph, Row, Table, Wrap},
+    Frame,
+};
+
+pub fn render(f: &mut Frame, area: Rect, state: &RunDetailState, units: DistanceUnit) {
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(8), // Summary
+            Constraint::Min(6),    // Laps
+            Constraint::Length(4), // Hints and status
+        ])
+        .split(area);
+
+    let Some(ref run) = state.run else {
+        return;
+    };
+
+    render_summary(f, chunks[0], run, state, units);
+    match state.editor {
+        Some(ref editor) => render_editor(f, chunks[1], editor, units),
+        None => render_laps(f, chunks[1], run, &state.splits, units),
+    }
+    render_hints(f, chunks[2], state);
+}
+
+fn render_summary(
+    f: &mut Frame,
+    area: Rect,
+    run: &Run,
+    state: &RunDetailState,
+    units: DistanceUnit,
+) {
+    let label = |text: &'static str| Span::styled(text, Style::default().fg(Color::Gray));
+    let value = |text: String| Span::styled(text, Style::default().fg(Color::White));
+    let or_dash = |text: Option<String>| text.unwrap_or_else(|| "--".to_string());
+
+    let heart_rate = match (run.avg_heart_rate, run.max_heart_rate) {
+        (None, None) => None,
+        (avg, max) => Some(format!(
+            "{} avg / {} max",
+            or_dash(avg.map(|bpm| bpm.to_string())),
+            or_dash(max.map(|bpm| bpm.to_string()))
+        )),
+    };
+
+    let lines = vec![
+        Line::from(vec![
+            Span::styled(
+                format!(
+                    "{} {} {}",
+                    validation::format_date(&run.date),
+                    validation::format_time(&run.time_started),
+                    validation::format_utc_offset(&run.offset())
+                ),
+                Style::default()
+                    .fg(Color::Yellow)
+                    .add_modifier(Modifier::BOLD),
+            ),
+            Span::raw("  "),
+            Span::styled(
+                run.run_type.map(|t| t.label()).unwrap_or(""),
+                Style::default().fg(Color::Cyan),
+            ),
+        ]),
+        Line::from(vec![
+            label("Distance: "),
+            value(validation::format_distance(run.distance_miles, units)),
+            label("   Duration: "),
+            value(or_dash(
+                run.duration_seconds.map(validation::format_duration),
+            )),
+            label("   Pace: "),
+            value(or_dash(run.pace_seconds_per_mile().map(|pace| {
+                format!(
+                    "{} /{}",
+                    validation::format_pace(units.pace_from_seconds_per_mile(pace)),
+                    units.label()
+                )
+            }))),
+        ]),
+        Line::from(vec![
+            label("Heart rate: "),
+            value(or_dash(heart_rate)),
+            label("   Effort: "),
+            value(or_dash(run.effort.map(|effort| format!("{}/10", effort)))),
+        ]),
+        Line::from(vec![
+            label("Route: "),
+            value(or_dash(state.route_name.clone())),
+            label("   Shoe: "),
+            value(or_dash(state.gear_name.clone())),
+            label("   Tags: "),
+            value(or_dash(
+                Some(validation::format_tags(&run.tags)).filter(|tags| !tags.is_empty()),
+            )),
+        ]),
+        Line::from(vec![
+            label("Note: "),
+            value(run.note.clone().unwrap_or_default()),
+        ]),
+    ];
+
+    let summary = Paragraph::new(lines)
+        .wrap(Wrap { trim: false })
+        .block(Block::default().borders(Borders::ALL).title("Run Details"));
+    f.render_widget(summary, area);
+}
+
+fn render_laps(f: &mut Frame, area: Rect, run: &Run, splits: &[Split], units: DistanceUnit) {
+    if splits.is_empty() {
+        let empty = Paragraph::new("No laps recorded. Press [l] to add them.")
+            .block(Block::default().borders(Borders::ALL).title("Laps"))
+            .style(Style::default().fg(Color::Gray));
+        f.render_widget(empty, area);
+        return;
+    }
+
+    let header = Row::new(
+        [
+            "Lap".to_string(),
+            format!("Distance ({})", units.label()),
+            "Duration".to_string(),
+            format!("Pace (/{})", units.label()),
+            String::new(),
+        ]
+        .into_iter()
+        .map(|h| {
+            Cell::from(h).style(
+                Style::default()
+                    .fg(Color::Yellow)
+                    .add_modifier(Modifier::BOLD),
+            )
+        }),
+    )
+    .bottom_margin(1);
+
+    let extremes = split::fastest_and_slowest(splits);
+    let rows: Vec<Row> = splits
+        .iter()
+        .enumerate()
+        .map(|(i, split)| {
+            let (marker, style) = match extremes {
+                Some((fastest, _)) if fastest == i => (
+                    "Fastest",
+                    Style::default()
+                        .fg(Color::Green)
+                        .add_modifier(Modifier::BOLD),
+                ),
+                Some((_, slowest)) if slowest == i => ("Slowest", Style::default().fg(Color::Red)),
+                _ => ("", Style::default()),
+            };
+
+            Row::new(vec![
+                Cell::from(split.lap_number.to_string()),
+                Cell::from(format!("{:.2}", units.convert_miles(split.distance_miles))),
+                Cell::from(validation::format_duration(split.duration_seconds)),
+                Cell::from(validation::format_pace(
+                    units.pace_from_seconds_per_mile(split.pace_seconds_per_mile()),
+                )),
+                Cell::from(marker),
+            ])
+            .style(style)
+        })
+        .collect();
+
+    let lap_miles: f64 = splits.iter().map(|s| s.distance_miles).sum();
+    let lap_seconds: u32 = splits.iter().map(|s| s.duration_seconds).sum();
+    let title = format!(
+        "Laps - {} in {} (run: {})",
+        validation::format_distance(lap_miles, units),
+        validation::format_duration(lap_seconds),
+        validation::format_distance(run.distance_miles, units)
+    );
+
+    let table = Table::new(
+        rows,
+        [
+            Constraint::Length(5),
+            Constraint::Length(15),
+            Constraint::Length(10),
+            Constraint::Length(11),
+            Constraint::Min(8),
+        ],
+    )
+    .header(header)
+    .block(Block::default().borders(Borders::ALL).title(title));
+
+    f.render_widget(table, area);
+}
+
+fn render_editor(f: &mut Frame, area: Rect, editor: &LapEditor, units: DistanceUnit) {
+    let header = Row::new(
+        [
+            "Lap".to_string(),
+            format!("Distance ({})", units.label()),
+            "Duration (MM:SS)".to_string(),
+        ]
+        .into_iter()
+        .map(|h| {
+            Cell::from(h).style(
+                Style::default()
+                    .fg(Color::Yellow)
+                    .add_modifier(Modifier::BOLD),
+            )
+        }),
+    )
+    .bottom_margin(1);
+
+    let focused = Style::default()
+        .fg(Color::Black)
+        .bg(Color::Yellow)
+        .add_modifier(Modifier::BOLD);
+    let rows: Vec<Row> = editor
+        .laps
+        .iter()
+        .enumerate()
+        .map(|(i, lap)| {
+            let cell = |value: &str, field: LapField| {
+                if i == editor.selected_lap && editor.focused_field == field {
+                    Cell::from(format!("{}_", value)).style(focused)
+                } else {
+                    Cell::from(value.to_string())
+                }
+            };
+            Row::new(vec![
+                Cell::from((i + 1).to_string()),
+                cell(&lap.distance, LapField::Distance),
+                cell(&lap.duration, LapField::Duration),
+            ])
+        })
+        .collect();
+
+    let title = match editor.error_message {
+        Some(ref error) => format!("Edit Laps - {}", error),
+        None => "Edit Laps".to_string(),
+    };
+    let border_style = if editor.error_message.is_some() {
+        Style::default().fg(Color::Red)
+    } else {
+        Style::default().fg(Color::Yellow)
+    };
+
+    let table = Table::new(
+        rows,
+        [
+            Constraint::Length(5),
+            Constraint::Length(18),
+            Constraint::Length(18),
+        ],
+    )
+    .header(header)
+    .block(
+        Block::default()
+            .borders(Borders::ALL)
+            .title(title)
+            .border_style(border_style),
+    );
+
+    f.render_widget(table, area);
+}
+
+fn render_hints(f: &mut Frame, area: Rect, state: &RunDetailState) {
+    let line = if state.editor.is_some() {
+        Line::from(vec![
+            Span::styled("[Tab] ", Style::default().fg(Color::Yellow)),
+            Span::raw("Next field (adds a lap at the end)  "),
+            Span::styled("[↑↓] ", Style::default().fg(Color::Yellow)),
+            Span::raw("Select lap  "),
+            Span::styled("[Ctrl+D] ", Style::default().fg(Color::Red)),
+            Span::raw("Remove lap  "),
+            Span::styled("[Enter] ", Style::default().fg(Color::Green)),
+            Span::raw("Save  "),
+            Span::styled("[Esc] ", Style::default().fg(Color::Cyan)),
+            Span::raw("Cancel"),
+        ])
+    } else {
+        Line::from(vec![
+            Span::styled("[l] ", Style::default().fg(Color::Green)),
+            Span::raw("Edit laps  "),
+            Span::styled("[e] ", Style::default().fg(Color::Green)),
+            Span::raw("Edit run  "),
+            Span::styled("[b] ", Style::default().fg(Color::Yellow)),
+            Span::raw("Back to list  "),
+            Span::styled("[Esc→1-7] ", Style::default().fg(Color::Cyan)),
+            Span::raw("Switch screens"),
+        ])
+    };
+
+    let mut lines = vec![line];
+    if let Some(ref message) = state.message {
+        lines.push(Line::from(Span::styled(
+            message.as_str(),
+            Style::default().fg(Color::Gray),
+        )));
+    }
+
+    let hints =
+        Paragraph::new(lines).block(Block::default().borders(Borders::ALL).title("Actions"));
+    f.render_widget(hints, area);
+}
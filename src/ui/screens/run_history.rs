@@ -0,0 +1,226 @@
+use crate::app::{self, RunHistoryState};
+use crate::logic::validation;
+use crate::models::gear::Gear;
+use crate::models::history::{HistoryAction, RunVersion};
+use crate::models::route::Route;
+use crate::models::run::Run;
+use crate::models::units::DistanceUnit;
+use ratatui::{
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Cell, Paragraph, Row, Table},
+    Frame,
+};
+
+pub fn render(
+    f: &mut Frame,
+    area: Rect,
+    state: &RunHistoryState,
+    gear: &[Gear],
+    routes: &[Route],
+    units: DistanceUnit,
+) {
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Min(10),   // Table
+            Constraint::Length(4), // Hints and status
+        ])
+        .split(area);
+
+    let Some(ref run) = state.run else {
+        return;
+    };
+    let title = format!(
+        "History of the {} {} run ({} changes)",
+        validation::format_date(&run.date),
+        validation::format_time(&run.time_started),
+        state.versions.len()
+    );
+
+    if state.versions.is_empty() {
+        let empty_text = Paragraph::new(
+            "No changes recorded. Runs logged before history was kept have none until they're next changed.",
+        )
+        .block(Block::default().borders(Borders::ALL).title(title))
+        .style(Style::default().fg(Color::Gray));
+        f.render_widget(empty_text, chunks[0]);
+        render_hints(f, chunks[1], state);
+        return;
+    }
+
+    let header_cells = ["When", "Change", "Details"].into_iter().map(|h| {
+        Cell::from(h).style(
+            Style::default()
+                .fg(Color::Yellow)
+                .add_modifier(Modifier::BOLD),
+        )
+    });
+    let header = Row::new(header_cells).height(1).bottom_margin(1);
+
+    // Keep the selected change in view
+    let visible = chunks[0].height.saturating_sub(4) as usize;
+    let scroll_offset = (state.selected_index + 1).saturating_sub(visible.max(1));
+
+    let rows: Vec<Row> = state
+        .versions
+        .iter()
+        .enumerate()
+        .skip(scroll_offset)
+        .map(|(i, version)| {
+            let when = version
+                .changed_at
+                .with_timezone(&chrono::Local)
+                .format("%Y-%m-%d %H:%M:%S")
+                .to_string();
+            let mut change = version.action.label().to_string();
+            if version.matches(run) {
+                change.push_str(" (current)");
+            }
+
+            let style = if i == state.selected_index {
+                Style::default()
+                    .fg(Color::Yellow)
+                    .add_modifier(Modifier::BOLD)
+            } else {
+                Style::default()
+            };
+
+            Row::new(vec![
+                Cell::from(when),
+                Cell::from(change),
+                Cell::from(describe_change(version, gear, routes, units)),
+            ])
+            .style(style)
+        })
+        .collect();
+
+    let table = Table::new(
+        rows,
+        [
+            Constraint::Length(21),
+            Constraint::Length(20),
+            Constraint::Min(30),
+        ],
+    )
+    .header(header)
+    .block(Block::default().borders(Borders::ALL).title(title));
+
+    f.render_widget(table, chunks[0]);
+    render_hints(f, chunks[1], state);
+}
+
+/// What a change did, in words: the fields an edit changed, or the run that
+/// was logged.
+fn describe_change(
+    version: &RunVersion,
+    gear: &[Gear],
+    routes: &[Route],
+    units: DistanceUnit,
+) -> String {
+    match (version.action, &version.before, &version.after) {
+        (HistoryAction::Trash, ..) => "Moved to the trash".to_string(),
+        (HistoryAction::Restore, ..) => "Restored from the trash".to_string(),
+        (HistoryAction::Delete, ..) => "Permanently deleted".to_string(),
+        (_, Some(before), Some(after)) => {
+            let before = fields(before, gear, routes, units);
+            let after = fields(after, gear, routes, units);
+            let changes: Vec<String> = before
+                .into_iter()
+                .zip(after)
+                .filter(|((_, old), (_, new))| old != new)
+                .map(|((name, old), (_, new))| format!("{}: {} → {}", name, old, new))
+                .collect();
+            if changes.is_empty() {
+                "No visible change".to_string()
+            } else {
+                changes.join(", ")
+            }
+        }
+        (_, None, Some(after)) => fields(after, gear, routes, units)
+            .into_iter()
+            .filter(|(_, value)| value != "-")
+            .map(|(name, value)| format!("{}: {}", name, value))
+            .collect::<Vec<_>>()
+            .join(", "),
+        _ => "Values can't be read".to_string(),
+    }
+}
+
+/// The fields shown in the history, as text, with `-` for anything not
+/// recorded. Also used to show merge conflicts.
+pub fn fields(
+    run: &Run,
+    gear: &[Gear],
+    routes: &[Route],
+    units: DistanceUnit,
+) -> Vec<(&'static str, String)> {
+    let or_dash = |value: Option<String>| value.unwrap_or_else(|| "-".to_string());
+    let route = run.route_id.and_then(|id| {
+        routes
+            .iter()
+            .find(|route| route.id == Some(id))
+            .map(|route| route.name.clone())
+    });
+
+    vec![
+        ("date", validation::format_date(&run.date)),
+        ("time", validation::format_time(&run.time_started)),
+        ("zone", validation::format_utc_offset(&run.offset())),
+        (
+            "distance",
+            validation::format_distance(run.distance_miles, units),
+        ),
+        (
+            "duration",
+            or_dash(run.duration_seconds.map(validation::format_duration)),
+        ),
+        ("type", or_dash(run.run_type.map(|t| t.label().to_string()))),
+        (
+            "shoe",
+            or_dash(app::gear_name(gear, run.gear_id).map(str::to_string)),
+        ),
+        ("route", or_dash(route)),
+        (
+            "avg HR",
+            or_dash(run.avg_heart_rate.map(|bpm| bpm.to_string())),
+        ),
+        (
+            "max HR",
+            or_dash(run.max_heart_rate.map(|bpm| bpm.to_string())),
+        ),
+        ("effort", or_dash(run.effort.map(|e| e.to_string()))),
+        (
+            "tags",
+            or_dash(Some(validation::format_tags(&run.tags)).filter(|tags| !tags.is_empty())),
+        ),
+        ("note", or_dash(run.note.clone())),
+    ]
+}
+
+fn render_hints(f: &mut Frame, area: Rect, state: &RunHistoryState) {
+    let mut lines = vec![Line::from(vec![
+        Span::styled("[↑↓] ", Style::default().fg(Color::Yellow)),
+        Span::raw("Navigate  "),
+        Span::styled("[r] ", Style::default().fg(Color::Green)),
+        Span::raw("Revert to this version  "),
+        Span::styled("[u] ", Style::default().fg(Color::Yellow)),
+        Span::raw("Undo  "),
+        Span::styled("[b] ", Style::default().fg(Color::Yellow)),
+        Span::raw("Back to list  "),
+        Span::styled("[Esc→1-7] ", Style::default().fg(Color::Cyan)),
+        Span::raw("Switch screens"),
+    ])];
+
+    if let Some(ref message) = state.message {
+        lines.push(Line::from(Span::styled(
+            message.as_str(),
+            Style::default().fg(Color::Gray),
+        )));
+    }
+
+    let hints =
+        Paragraph::new(lines).block(Block::default().borders(Borders::ALL).title("Actions"));
+    f.render_widget(hints, area);
+}
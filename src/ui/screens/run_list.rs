@@ -1,13 +1,16 @@
-use crate::app::RunListState;
+use crate::app::{self, RunListState};
+use crate::logic::{search, validation};
+use crate::models::gear::Gear;
+use crate::models::units::DistanceUnit;
 use ratatui::{
     layout::{Constraint, Direction, Layout, Rect},
     style::{Color, Modifier, Style},
-    text::Line,
+    text::{Line, Span},
     widgets::{Block, Borders, Cell, Paragraph, Row, Table},
     Frame,
 };
 
-pub fn render(f: &mut Frame, area: Rect, state: &RunListState) {
+pub fn render(f: &mut Frame, area: Rect, state: &RunListState, gear: &[Gear], units: DistanceUnit) {
     // Split area for table and hints
     let chunks = Layout::default()
         .direction(Direction::Vertical)
@@ -20,18 +23,37 @@ pub fn render(f: &mut Frame, area: Rect, state: &RunListState) {
     if state.runs.is_empty() {
         let empty_message = Block::default()
             .borders(Borders::ALL)
-            .title("Run List")
+            .title(list_title(state))
             .style(Style::default());
-        let empty_text = Paragraph::new("No runs logged yet. Press Esc → 1 to add a run.")
+        let message = if state.search.is_empty() {
+            "No runs logged yet. Press Esc → 1 to add a run."
+        } else {
+            "No runs match this search. Press / and Enter on an empty search to clear it."
+        };
+        let empty_text = Paragraph::new(message)
             .block(empty_message)
             .style(Style::default().fg(Color::Gray));
         f.render_widget(empty_text, chunks[0]);
-        render_hints(f, chunks[1]);
+        render_hints(f, chunks[1], state);
         return;
     }
 
-    let header_cells = ["Date", "Time", "Distance (mi)", "Note"].iter().map(|h| {
-        Cell::from(*h).style(
+    let header_cells = [
+        "Date".to_string(),
+        "Time".to_string(),
+        format!("Distance ({})", units.label()),
+        "Duration".to_string(),
+        "Type".to_string(),
+        format!("Pace (/{})", units.label()),
+        "HR avg/max".to_string(),
+        "RPE".to_string(),
+        "Shoe".to_string(),
+        "Tags".to_string(),
+        "Note".to_string(),
+    ]
+    .into_iter()
+    .map(|h| {
+        Cell::from(h).style(
             Style::default()
                 .fg(Color::Yellow)
                 .add_modifier(Modifier::BOLD),
@@ -43,10 +65,37 @@ pub fn render(f: &mut Frame, area: Rect, state: &RunListState) {
         .runs
         .iter()
         .enumerate()
+        .skip(state.scroll_offset)
         .map(|(i, run)| {
             let date = run.date.format("%Y-%m-%d").to_string();
-            let time = run.time_started.format("%H:%M:%S").to_string();
-            let distance = format!("{:.2}", run.distance_miles);
+            let time = format!(
+                "{} {}",
+                validation::format_time(&run.time_started),
+                validation::format_utc_offset(&run.offset())
+            );
+            let distance = format!("{:.2}", units.convert_miles(run.distance_miles));
+            let duration = run
+                .duration_seconds
+                .map(validation::format_duration)
+                .unwrap_or_default();
+            let pace = run
+                .pace_seconds_per_mile()
+                .map(|pace| validation::format_pace(units.pace_from_seconds_per_mile(pace)))
+                .unwrap_or_default();
+            let run_type = run.run_type.map(|t| t.label()).unwrap_or("");
+            let heart_rate = match (run.avg_heart_rate, run.max_heart_rate) {
+                (None, None) => String::new(),
+                (avg, max) => format!(
+                    "{}/{}",
+                    avg.map(|bpm| bpm.to_string())
+                        .unwrap_or_else(|| "-".to_string()),
+                    max.map(|bpm| bpm.to_string())
+                        .unwrap_or_else(|| "-".to_string())
+                ),
+            };
+            let effort = run.effort.map(|e| e.to_string()).unwrap_or_default();
+            let shoe = app::gear_name(gear, run.gear_id).unwrap_or("");
+            let tags = validation::format_tags(&run.tags);
             let note = run.note.as_deref().unwrap_or("");
 
             let style = if i == state.selected_index {
@@ -61,7 +110,14 @@ pub fn render(f: &mut Frame, area: Rect, state: &RunListState) {
                 Cell::from(date),
                 Cell::from(time),
                 Cell::from(distance),
-                Cell::from(note),
+                Cell::from(duration),
+                Cell::from(run_type),
+                Cell::from(pace),
+                Cell::from(heart_rate),
+                Cell::from(effort),
+                Cell::from(shoe),
+                Cell::from(tags).style(Style::default().fg(Color::Cyan)),
+                Cell::from(highlight_matches(note, &state.search.terms)),
             ])
             .style(style)
         })
@@ -71,8 +127,15 @@ pub fn render(f: &mut Frame, area: Rect, state: &RunListState) {
         rows,
         [
             Constraint::Length(12),
-            Constraint::Length(10),
+            Constraint::Length(19),
             Constraint::Length(15),
+            Constraint::Length(10),
+            Constraint::Length(10),
+            Constraint::Length(11),
+            Constraint::Length(11),
+            Constraint::Length(4),
+            Constraint::Length(14),
+            Constraint::Length(18),
             Constraint::Min(20),
         ],
     )
@@ -80,22 +143,86 @@ pub fn render(f: &mut Frame, area: Rect, state: &RunListState) {
     .block(
         Block::default()
             .borders(Borders::ALL)
-            .title(format!("Run List ({} runs)", state.runs.len())),
+            .title(list_title(state)),
     );
 
     f.render_widget(table, chunks[0]);
-    render_hints(f, chunks[1]);
+    render_hints(f, chunks[1], state);
+}
+
+fn list_title(state: &RunListState) -> String {
+    // The total counts every match, not just the pages loaded so far
+    let count = if state.has_more() {
+        format!("{} runs, {} loaded", state.total_runs, state.runs.len())
+    } else {
+        format!("{} runs", state.total_runs)
+    };
+
+    if state.search.is_empty() {
+        format!("Run List ({})", count)
+    } else {
+        format!(
+            "Run List - matching \"{}\" ({})",
+            state.search.to_input(),
+            count
+        )
+    }
 }
 
-fn render_hints(f: &mut Frame, area: Rect) {
+/// Splits a note into spans with the words matching the search emphasised.
+fn highlight_matches<'a>(note: &'a str, terms: &[String]) -> Line<'a> {
+    let highlight = Style::default()
+        .fg(Color::Black)
+        .bg(Color::Yellow)
+        .add_modifier(Modifier::BOLD);
+
+    let mut spans = Vec::new();
+    let mut position = 0;
+    for (start, end) in search::match_ranges(note, terms) {
+        if start > position {
+            spans.push(Span::raw(&note[position..start]));
+        }
+        spans.push(Span::styled(&note[start..end], highlight));
+        position = end;
+    }
+    if position < note.len() {
+        spans.push(Span::raw(&note[position..]));
+    }
+
+    Line::from(spans)
+}
+
+fn render_hints(f: &mut Frame, area: Rect, state: &RunListState) {
+    if let Some(ref input) = state.search_input {
+        let prompt = Paragraph::new(Line::from(vec![
+            Span::styled("/", Style::default().fg(Color::Yellow)),
+            Span::raw(format!("{}_", input)),
+        ]))
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title("Search notes, #tag to filter - [Enter] Apply  [Esc] Cancel"),
+        );
+        f.render_widget(prompt, area);
+        return;
+    }
+
     let hints = Paragraph::new(Line::from(vec![
         ratatui::text::Span::styled("[↑↓] ", Style::default().fg(Color::Yellow)),
         ratatui::text::Span::raw("Navigate  "),
+        ratatui::text::Span::styled("[Enter] ", Style::default().fg(Color::Green)),
+        ratatui::text::Span::raw("Details  "),
+        ratatui::text::Span::styled("[l] ", Style::default().fg(Color::Green)),
+        ratatui::text::Span::raw("Laps  "),
         ratatui::text::Span::styled("[e] ", Style::default().fg(Color::Green)),
         ratatui::text::Span::raw("Edit  "),
+        ratatui::text::Span::styled("[H] ", Style::default().fg(Color::Green)),
+        ratatui::text::Span::raw("History  "),
         ratatui::text::Span::styled("[d] ", Style::default().fg(Color::Red)),
-        ratatui::text::Span::raw("Delete  "),
-        ratatui::text::Span::styled("[Esc→1/2/3] ", Style::default().fg(Color::Cyan)),
+        ratatui::text::Span::raw("Move to trash  "),
+        ratatui::text::Span::styled("[/] ", Style::default().fg(Color::Magenta)),
+        ratatui::text::Span::raw("Search  "),
+        ratatui::text::Span::styled("[Esc→1-7] ", Style::default().fg(Color::Cyan)),
         ratatui::text::Span::raw("Switch screens"),
     ]))
     .block(Block::default().borders(Borders::ALL).title("Actions"));
@@ -0,0 +1,135 @@
+use crate::app::TrashState;
+use crate::models::units::DistanceUnit;
+use ratatui::{
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Cell, Paragraph, Row, Table},
+    Frame,
+};
+
+pub fn render(f: &mut Frame, area: Rect, state: &TrashState, units: DistanceUnit) {
+    // Split area for table and hints
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Min(10),   // Table
+            Constraint::Length(4), // Hints and status
+        ])
+        .split(area);
+
+    if state.runs.is_empty() {
+        let empty_message = Block::default()
+            .borders(Borders::ALL)
+            .title("Trash")
+            .style(Style::default());
+        let empty_text =
+            Paragraph::new("Trash is empty. Runs deleted from the Run List appear here.")
+                .block(empty_message)
+                .style(Style::default().fg(Color::Gray));
+        f.render_widget(empty_text, chunks[0]);
+        render_hints(f, chunks[1], state);
+        return;
+    }
+
+    let header_cells = [
+        "Date".to_string(),
+        "Time".to_string(),
+        format!("Distance ({})", units.label()),
+        "Deleted".to_string(),
+        "Note".to_string(),
+    ]
+    .into_iter()
+    .map(|h| {
+        Cell::from(h).style(
+            Style::default()
+                .fg(Color::Yellow)
+                .add_modifier(Modifier::BOLD),
+        )
+    });
+    let header = Row::new(header_cells).height(1).bottom_margin(1);
+
+    let rows: Vec<Row> = state
+        .runs
+        .iter()
+        .enumerate()
+        .map(|(i, run)| {
+            let date = run.date.format("%Y-%m-%d").to_string();
+            let time = run.time_started.format("%H:%M:%S").to_string();
+            let distance = format!("{:.2}", units.convert_miles(run.distance_miles));
+            let deleted = run
+                .deleted_at
+                .map(|dt| {
+                    dt.with_timezone(&chrono::Local)
+                        .format("%Y-%m-%d %H:%M")
+                        .to_string()
+                })
+                .unwrap_or_default();
+            let note = run.note.as_deref().unwrap_or("");
+
+            let style = if i == state.selected_index {
+                Style::default()
+                    .fg(Color::Yellow)
+                    .add_modifier(Modifier::BOLD)
+            } else {
+                Style::default()
+            };
+
+            Row::new(vec![
+                Cell::from(date),
+                Cell::from(time),
+                Cell::from(distance),
+                Cell::from(deleted),
+                Cell::from(note),
+            ])
+            .style(style)
+        })
+        .collect();
+
+    let table = Table::new(
+        rows,
+        [
+            Constraint::Length(12),
+            Constraint::Length(10),
+            Constraint::Length(15),
+            Constraint::Length(18),
+            Constraint::Min(20),
+        ],
+    )
+    .header(header)
+    .block(
+        Block::default()
+            .borders(Borders::ALL)
+            .title(format!("Trash ({} runs)", state.runs.len())),
+    );
+
+    f.render_widget(table, chunks[0]);
+    render_hints(f, chunks[1], state);
+}
+
+fn render_hints(f: &mut Frame, area: Rect, state: &TrashState) {
+    let mut lines = vec![Line::from(vec![
+        Span::styled("[↑↓] ", Style::default().fg(Color::Yellow)),
+        Span::raw("Navigate  "),
+        Span::styled("[r] ", Style::default().fg(Color::Green)),
+        Span::raw("Restore  "),
+        Span::styled("[X] ", Style::default().fg(Color::Red)),
+        Span::raw("Empty trash  "),
+        Span::styled("[Esc→1-7] ", Style::default().fg(Color::Cyan)),
+        Span::raw("Switch screens"),
+    ])];
+
+    if let Some(ref message) = state.message {
+        let style = if state.confirm_empty {
+            Style::default().fg(Color::Red).add_modifier(Modifier::BOLD)
+        } else {
+            Style::default().fg(Color::Gray)
+        };
+        lines.push(Line::from(Span::styled(message.as_str(), style)));
+    }
+
+    let hints =
+        Paragraph::new(lines).block(Block::default().borders(Borders::ALL).title("Actions"));
+
+    f.render_widget(hints, area);
+}